@@ -0,0 +1,246 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{MpesaError, MpesaResult};
+
+/// An amount of money in whole Kenyan shillings (KES)
+///
+/// M-Pesa only transacts whole shillings, an `Amount` is always at least 1 KES and never goes
+/// through floating point arithmetic. It is sent to the API as a JSON integer.
+///
+/// Amounts can be created from integers, and from floats or strings holding a whole number of
+/// shillings. Conversions never round: `10.5` and `"10.50"` are rejected.
+///
+/// # Example
+///
+/// ```rust
+/// use mpesa::Amount;
+///
+/// let amount = Amount::new(1_000).unwrap();
+/// let fee: Amount = "33".parse().unwrap();
+/// assert_eq!(amount.checked_add(fee), Some(Amount::new(1_033).unwrap()));
+///
+/// assert!(Amount::new(0).is_err());
+/// assert!(Amount::try_from(10.5).is_err());
+/// assert!(Amount::try_from(-10).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(u32);
+
+impl Amount {
+    /// Creates an amount of `shillings`
+    ///
+    /// # Errors
+    /// Returns a `Message` error if `shillings` is 0
+    pub fn new(shillings: u32) -> MpesaResult<Self> {
+        if shillings == 0 {
+            return Err(MpesaError::Message("amount must be at least 1 KES".into()));
+        }
+        Ok(Amount(shillings))
+    }
+
+    /// The amount in shillings
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+
+    /// Adds two amounts, returning `None` on overflow
+    pub fn checked_add(self, other: Amount) -> Option<Amount> {
+        self.0.checked_add(other.0).map(Amount)
+    }
+
+    /// Subtracts `other` from the amount, returning `None` if the result is less than 1 KES
+    pub fn checked_sub(self, other: Amount) -> Option<Amount> {
+        self.0
+            .checked_sub(other.0)
+            .filter(|&shillings| shillings > 0)
+            .map(Amount)
+    }
+
+    /// Multiplies the amount, e.g. by a quantity, returning `None` on overflow or if `rhs` is 0
+    pub fn checked_mul(self, rhs: u32) -> Option<Amount> {
+        self.0
+            .checked_mul(rhs)
+            .filter(|&shillings| shillings > 0)
+            .map(Amount)
+    }
+}
+
+impl Display for Amount {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<Amount> for u32 {
+    fn from(amount: Amount) -> Self {
+        amount.0
+    }
+}
+
+impl From<Amount> for u64 {
+    fn from(amount: Amount) -> Self {
+        amount.0.into()
+    }
+}
+
+macro_rules! impl_try_from_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl TryFrom<$ty> for Amount {
+                type Error = MpesaError;
+
+                fn try_from(shillings: $ty) -> Result<Self, Self::Error> {
+                    let shillings = u32::try_from(shillings)
+                        .map_err(|_| MpesaError::Message("amount is out of range".into()))?;
+                    Amount::new(shillings)
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_integer!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl TryFrom<f64> for Amount {
+    type Error = MpesaError;
+
+    /// Converts a float holding a whole number of shillings, fractional values are rejected
+    /// rather than rounded
+    fn try_from(shillings: f64) -> Result<Self, Self::Error> {
+        if shillings.fract() != 0.0 {
+            return Err(MpesaError::Message(
+                "amount must be a whole number of shillings".into(),
+            ));
+        }
+        if !(0.0..=f64::from(u32::MAX)).contains(&shillings) {
+            return Err(MpesaError::Message("amount is out of range".into()));
+        }
+        Amount::new(shillings as u32)
+    }
+}
+
+impl FromStr for Amount {
+    type Err = MpesaError;
+
+    /// Parses a whole number of shillings, optionally followed by zero cents, e.g. `"100"` or
+    /// `"100.00"`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let shillings = match s.split_once('.') {
+            Some((shillings, cents)) if !cents.is_empty() && cents.bytes().all(|b| b == b'0') => {
+                shillings
+            }
+            Some(_) => {
+                return Err(MpesaError::Message(
+                    "amount must be a whole number of shillings".into(),
+                ))
+            }
+            None => s,
+        };
+
+        if shillings.is_empty() || !shillings.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(MpesaError::Message("amount must be a number".into()));
+        }
+        shillings
+            .parse::<u32>()
+            .map_err(|_| MpesaError::Message("amount is out of range".into()))
+            .and_then(Amount::new)
+    }
+}
+
+impl TryFrom<&str> for Amount {
+    type Error = MpesaError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    /// Deserializes a JSON number or string holding a whole number of shillings
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Integer(u64),
+            Float(f64),
+            String(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Integer(shillings) => Amount::try_from(shillings),
+            Repr::Float(shillings) => Amount::try_from(shillings),
+            Repr::String(shillings) => shillings.parse(),
+        }
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amount_is_at_least_one_shilling() {
+        assert_eq!(Amount::new(1).unwrap().as_u32(), 1);
+        assert!(Amount::new(0).is_err());
+        assert!(Amount::try_from(-1_i32).is_err());
+        assert!(Amount::try_from(u64::MAX).is_err());
+    }
+
+    #[test]
+    fn test_floats_are_never_rounded() {
+        assert_eq!(Amount::try_from(100.0).unwrap().as_u32(), 100);
+        assert!(Amount::try_from(100.5).is_err());
+        assert!(Amount::try_from(0.1 + 0.2).is_err());
+        assert!(Amount::try_from(-100.0).is_err());
+        assert!(Amount::try_from(f64::NAN).is_err());
+        assert!(Amount::try_from(f64::INFINITY).is_err());
+        assert!(Amount::try_from(1e12).is_err());
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        assert_eq!("100".parse::<Amount>().unwrap().as_u32(), 100);
+        assert_eq!("100.00".parse::<Amount>().unwrap().as_u32(), 100);
+        assert!("100.50".parse::<Amount>().is_err());
+        assert!("100.".parse::<Amount>().is_err());
+        assert!("-100".parse::<Amount>().is_err());
+        assert!("+100".parse::<Amount>().is_err());
+        assert!("1e3".parse::<Amount>().is_err());
+        assert!("".parse::<Amount>().is_err());
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let amount = Amount::new(100).unwrap();
+        assert_eq!(amount.checked_add(amount).unwrap().as_u32(), 200);
+        assert_eq!(amount.checked_mul(3).unwrap().as_u32(), 300);
+        assert!(amount.checked_sub(amount).is_none());
+        assert!(amount.checked_mul(0).is_none());
+        assert!(Amount::new(u32::MAX).unwrap().checked_add(amount).is_none());
+    }
+
+    #[test]
+    fn test_amount_serde() {
+        let amount = Amount::new(1000).unwrap();
+        assert_eq!(serde_json::to_string(&amount).unwrap(), "1000");
+        assert_eq!(amount.to_string(), "1000");
+
+        for json in ["1000", "1000.0", "\"1000\"", "\"1000.00\""] {
+            assert_eq!(serde_json::from_str::<Amount>(json).unwrap(), amount);
+        }
+        for json in ["0", "-1", "10.5", "\"10.50\"", "null"] {
+            assert!(serde_json::from_str::<Amount>(json).is_err());
+        }
+    }
+}
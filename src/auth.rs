@@ -1,37 +1,46 @@
-use cached::proc_macro::cached;
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 use serde_aux::field_attributes::deserialize_number_from_string;
 
-use crate::{Mpesa, MpesaError, MpesaResult, ResponseError};
+use crate::client::parse_body;
+use crate::retry::{retry, Failure};
+use crate::{Mpesa, MpesaError, MpesaResult};
 
 const AUTHENTICATION_URL: &str = "/oauth/v1/generate?grant_type=client_credentials";
 
-#[cached(
-    size = 1,
-    time = 3600,
-    key = "String",
-    result = true,
-    convert = r#"{ format!("{}", client.client_key()) }"#
-)]
-pub(crate) async fn auth(client: &Mpesa) -> MpesaResult<String> {
+/// Cached tokens are considered expired this long before the expiry reported by M-Pesa,
+/// so that a token never expires while a request is in flight
+pub(crate) const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// Requests a new access token from the Safaricom API, retrying according to the client's
+/// `RetryPolicy`
+pub(crate) async fn auth(client: &Mpesa) -> MpesaResult<AuthenticationResponse> {
+    retry(&client.retry_policy, || auth_once(client), |_| false).await
+}
+
+async fn auth_once(client: &Mpesa) -> Result<AuthenticationResponse, Failure> {
     let url = format!("{}{}", client.base_url, AUTHENTICATION_URL);
 
-    let response = client
+    let request = client
         .http_client
         .get(&url)
         .basic_auth(client.client_key(), Some(&client.client_secret()))
-        .send()
-        .await?;
+        .build()?;
+    let path = request.url().path().to_owned();
+    let response = client.execute(request).await?;
 
-    if response.status().is_success() {
-        let value = response.json::<AuthenticationResponse>().await?;
-        let access_token = value.access_token;
+    let status = response.status();
+    let body = response.text().await?;
 
-        return Ok(access_token);
+    if status.is_success() {
+        return Ok(parse_body(status, &path, &body)?);
     }
 
-    let error = response.json::<ResponseError>().await?;
-    Err(MpesaError::Service(error))
+    Err(Failure {
+        status: Some(status),
+        error: MpesaError::from_response(status, &path, &body),
+    })
 }
 
 /// Response returned from the authentication function
@@ -57,9 +66,11 @@ impl std::fmt::Display for AuthenticationResponse {
 #[cfg(test)]
 mod tests {
     use crate::ApiEnvironment;
-    use wiremock::{Mock, MockServer};
+    use wiremock::matchers::{basic_auth, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
     use super::*;
+    use crate::TokenCache;
 
     #[derive(Debug, Clone)]
     pub struct TestEnvironment {
@@ -84,35 +95,71 @@ mod tests {
         }
     }
 
+    async fn mount_auth(server: &MockServer, client_key: &str, expires_in: u64, expect: u64) {
+        Mock::given(method("GET"))
+            .and(basic_auth(client_key, "test_public_key"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(AuthenticationResponse {
+                    access_token: format!("{client_key}_token"),
+                    expires_in,
+                }),
+            )
+            .expect(expect)
+            .mount(server)
+            .await;
+    }
+
     #[tokio::test]
     async fn test_cached_auth() {
-        use cached::Cached;
+        let server = MockServer::start().await;
+        let env = TestEnvironment::new(&server).await;
+        let client = Mpesa::new("test_api_key", "test_public_key", env);
+        mount_auth(&server, "test_api_key", 3600, 1).await;
 
-        use crate::Mpesa;
+        assert_eq!(client.auth().await.unwrap(), "test_api_key_token");
+        assert_eq!(client.auth().await.unwrap(), "test_api_key_token");
+    }
 
+    #[tokio::test]
+    async fn test_auth_honors_expires_in() {
         let server = MockServer::start().await;
-
         let env = TestEnvironment::new(&server).await;
-
         let client = Mpesa::new("test_api_key", "test_public_key", env);
+        // Expires within the safety margin, so the token is never reused
+        mount_auth(&server, "test_api_key", 30, 2).await;
 
-        Mock::given(wiremock::matchers::method("GET"))
-            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(
-                AuthenticationResponse {
-                    access_token: "test_token".to_string(),
-                    expires_in: 3600,
-                },
-            ))
-            .expect(1)
-            .mount(&server)
-            .await;
+        client.auth().await.unwrap();
+        client.auth().await.unwrap();
+    }
 
-        auth_prime_cache(&client).await.unwrap();
+    #[tokio::test]
+    async fn test_shared_cache_holds_multiple_credentials() {
+        let server = MockServer::start().await;
+        let env = TestEnvironment::new(&server).await;
+        let cache = TokenCache::new();
+        let client_a =
+            Mpesa::new("client_a", "test_public_key", env.clone()).with_token_store(cache.clone());
+        let client_b =
+            Mpesa::new("client_b", "test_public_key", env).with_token_store(cache.clone());
+        mount_auth(&server, "client_a", 3600, 1).await;
+        mount_auth(&server, "client_b", 3600, 1).await;
+
+        for _ in 0..3 {
+            assert_eq!(client_a.auth().await.unwrap(), "client_a_token");
+            assert_eq!(client_b.auth().await.unwrap(), "client_b_token");
+        }
+        assert_eq!(cache.len(), 2);
+    }
 
-        let mut cache = AUTH.lock().await;
+    #[tokio::test]
+    async fn test_clients_do_not_share_cache_by_default() {
+        let server = MockServer::start().await;
+        let env = TestEnvironment::new(&server).await;
+        let client_a = Mpesa::new("test_api_key", "test_public_key", env.clone());
+        let client_b = Mpesa::new("test_api_key", "test_public_key", env);
+        mount_auth(&server, "test_api_key", 3600, 2).await;
 
-        assert!(cache.cache_get(&client.client_key().to_string()).is_some());
-        assert_eq!(cache.cache_hits().unwrap(), 1);
-        assert_eq!(cache.cache_capacity().unwrap(), 1);
+        client_a.auth().await.unwrap();
+        client_b.auth().await.unwrap();
     }
 }
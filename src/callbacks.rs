@@ -0,0 +1,1072 @@
+//!# MPESA Callbacks
+//!
+//! Most of the M-Pesa APIs are asynchronous; the response returned by a builder's `send` method is
+//! only an acknowledgement that the request has been accepted for processing. The actual result is
+//! later sent by M-Pesa as a JSON `POST` request to the `result_url`/ `callback_url` that was
+//! provided when making the request.
+//!
+//! This module contains deserializable types for those payloads:
+//! - [`ResultCallback`]: the `Result` envelope sent for B2C, B2B, Account Balance, Transaction Status
+//!   and Transaction Reversal requests. The service specific `ResultParameters` can be parsed into
+//!   [`B2cResultParameters`], [`B2bResultParameters`], [`AccountBalanceResultParameters`],
+//!   [`TransactionStatusResultParameters`] and [`TransactionReversalResultParameters`]
+//! - [`StkPushCallback`]: the `stkCallback` body sent for Mpesa Express/ STK push requests
+//! - [`C2bValidationRequest`]/ [`C2bConfirmation`]: the body sent to the URLs registered with the
+//!   C2B Register API. Validation requests are answered with a [`ValidationResponse`] which either
+//!   accepts or rejects the payment
+//! - [`BillManagerPaymentNotification`]: the body sent to the Bill Manager `callback_url` when an
+//!   invoice is paid
+//!
+//! # Example
+//! ```rust
+//! use mpesa::callbacks::{B2cResultParameters, ResultCallback};
+//!
+//! let body = r#"{
+//!     "Result": {
+//!         "ResultType": 0,
+//!         "ResultCode": 0,
+//!         "ResultDesc": "The service request is processed successfully.",
+//!         "OriginatorConversationID": "10571-7910404-1",
+//!         "ConversationID": "AG_20191219_00004e48cf7e3533f581",
+//!         "TransactionID": "NLJ41HAY6Q",
+//!         "ResultParameters": {
+//!             "ResultParameter": [
+//!                 { "Key": "TransactionAmount", "Value": 10 },
+//!                 { "Key": "TransactionReceipt", "Value": "NLJ41HAY6Q" }
+//!             ]
+//!         }
+//!     }
+//! }"#;
+//!
+//! let callback: ResultCallback = serde_json::from_str(body).unwrap();
+//! let params: B2cResultParameters = callback.result.parameters().unwrap();
+//!
+//! assert_eq!(params.transaction_amount, Some(10.0));
+//! assert_eq!(params.transaction_receipt.as_deref(), Some("NLJ41HAY6Q"));
+//! ```
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_aux::field_attributes::{
+    deserialize_number_from_string, deserialize_option_number_from_string,
+    deserialize_string_from_number,
+};
+use serde_json::{Map, Value};
+
+use crate::{MpesaResponseCode, MpesaResult};
+
+/// Body sent to the `result_url` of the B2C, B2B, Account Balance, Transaction Status and
+/// Transaction Reversal APIs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultCallback {
+    #[serde(rename = "Result")]
+    pub result: CallbackResult,
+}
+
+/// The `Result` envelope of an asynchronous API callback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CallbackResult {
+    /// Status code indicating whether the transaction was already sent to your listener,
+    /// usually `0`
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub result_type: i32,
+    /// Status code of the transaction processing
+    pub result_code: MpesaResponseCode,
+    /// Description of the `result_code`
+    pub result_desc: String,
+    /// Unique request identifier assigned by the API gateway
+    #[serde(rename = "OriginatorConversationID")]
+    pub originator_conversation_id: String,
+    /// Unique request identifier assigned by M-Pesa
+    #[serde(rename = "ConversationID")]
+    pub conversation_id: String,
+    /// Unique M-Pesa transaction ID for the transaction
+    #[serde(rename = "TransactionID", default)]
+    pub transaction_id: Option<String>,
+    /// Additional transaction details, only present for processed transactions
+    #[serde(default)]
+    pub result_parameters: Option<ResultParameters>,
+    /// Additional data associated with the request
+    #[serde(default)]
+    pub reference_data: Option<ReferenceData>,
+}
+
+impl CallbackResult {
+    /// Returns `true` if M-Pesa processed the transaction successfully
+    pub fn is_success(&self) -> bool {
+        self.result_code.is_success()
+    }
+
+    /// Parses the `ResultParameters` into a service specific type such as
+    /// [`B2cResultParameters`]. Missing parameters are treated as empty.
+    ///
+    /// # Errors
+    /// Returns a `ParseError` if the parameters do not match the requested type
+    pub fn parameters<T: DeserializeOwned>(&self) -> MpesaResult<T> {
+        match &self.result_parameters {
+            Some(parameters) => parameters.parse(),
+            None => Ok(serde_json::from_value(Value::Object(Map::new()))?),
+        }
+    }
+}
+
+/// List of key/ value pairs sent in the `ResultParameters` of a callback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultParameters {
+    #[serde(rename = "ResultParameter", deserialize_with = "one_or_many")]
+    pub result_parameter: Vec<KeyValue>,
+}
+
+impl ResultParameters {
+    /// Gets the value of the parameter named `key`
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        find(&self.result_parameter, key)
+    }
+
+    /// Parses the parameters into a type whose fields are named after the parameter keys
+    ///
+    /// # Errors
+    /// Returns a `ParseError` if the parameters do not match the requested type
+    pub fn parse<T: DeserializeOwned>(&self) -> MpesaResult<T> {
+        Ok(serde_json::from_value(to_object(&self.result_parameter))?)
+    }
+}
+
+/// List of key/ value pairs sent in the `ReferenceData` of a callback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferenceData {
+    #[serde(rename = "ReferenceItem", deserialize_with = "one_or_many")]
+    pub reference_item: Vec<KeyValue>,
+}
+
+impl ReferenceData {
+    /// Gets the value of the reference item named `key`
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        find(&self.reference_item, key)
+    }
+}
+
+/// A single `ResultParameter` or `ReferenceItem`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyValue {
+    #[serde(rename = "Key")]
+    pub key: String,
+    /// Can be either a json string or number. Some items are sent without a value.
+    #[serde(rename = "Value", default)]
+    pub value: Option<Value>,
+}
+
+/// Typed `ResultParameters` of a B2C callback
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct B2cResultParameters {
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub transaction_amount: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub transaction_receipt: Option<String>,
+    /// `"Y"` if the recipient is a registered M-Pesa customer, otherwise `"N"`
+    #[serde(
+        rename = "B2CRecipientIsRegisteredCustomer",
+        default,
+        deserialize_with = "deserialize_option_string_from_any"
+    )]
+    pub recipient_is_registered_customer: Option<String>,
+    #[serde(
+        rename = "B2CChargesPaidAccountAvailableFunds",
+        default,
+        deserialize_with = "deserialize_option_number_from_string"
+    )]
+    pub charges_paid_account_available_funds: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub receiver_party_public_name: Option<String>,
+    /// Formatted as `dd.mm.yyyy hh:mm:ss`
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub transaction_completed_date_time: Option<String>,
+    #[serde(
+        rename = "B2CUtilityAccountAvailableFunds",
+        default,
+        deserialize_with = "deserialize_option_number_from_string"
+    )]
+    pub utility_account_available_funds: Option<f64>,
+    #[serde(
+        rename = "B2CWorkingAccountAvailableFunds",
+        default,
+        deserialize_with = "deserialize_option_number_from_string"
+    )]
+    pub working_account_available_funds: Option<f64>,
+}
+
+/// Typed `ResultParameters` of a B2B callback
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct B2bResultParameters {
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub amount: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub currency: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub debit_account_balance: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub debit_party_affected_account_balance: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub debit_party_charges: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub initiator_account_current_balance: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub receiver_party_public_name: Option<String>,
+    /// Formatted as `yyyymmddhhmmss`
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub trans_completed_time: Option<String>,
+}
+
+/// Typed `ResultParameters` of an Account Balance callback
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AccountBalanceResultParameters {
+    /// `&` separated list of accounts in the format `Name|Currency|Amount|...`
+    #[serde(
+        rename = "AccountBalance",
+        default,
+        deserialize_with = "deserialize_option_string_from_any"
+    )]
+    pub account_balance: Option<String>,
+    /// Formatted as `yyyymmddhhmmss`
+    #[serde(
+        rename = "BOCompletedTime",
+        default,
+        deserialize_with = "deserialize_option_string_from_any"
+    )]
+    pub completed_time: Option<String>,
+}
+
+/// Typed `ResultParameters` of a Transaction Status callback
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TransactionStatusResultParameters {
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub receipt_no: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub amount: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub transaction_status: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub reason_type: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub transaction_reason: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub debit_party_name: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub credit_party_name: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub debit_account_type: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub debit_party_charges: Option<f64>,
+    /// Formatted as `yyyymmddhhmmss`
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub initiated_time: Option<String>,
+    /// Formatted as `yyyymmddhhmmss`
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub finalised_time: Option<String>,
+    #[serde(
+        rename = "ConversationID",
+        default,
+        deserialize_with = "deserialize_option_string_from_any"
+    )]
+    pub conversation_id: Option<String>,
+    #[serde(
+        rename = "OriginatorConversationID",
+        default,
+        deserialize_with = "deserialize_option_string_from_any"
+    )]
+    pub originator_conversation_id: Option<String>,
+}
+
+/// Typed `ResultParameters` of a Transaction Reversal callback
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TransactionReversalResultParameters {
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub amount: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub charge: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub debit_account_balance: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub credit_party_public_name: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub debit_party_public_name: Option<String>,
+    #[serde(
+        rename = "OriginalTransactionID",
+        default,
+        deserialize_with = "deserialize_option_string_from_any"
+    )]
+    pub original_transaction_id: Option<String>,
+    /// Formatted as `yyyymmddhhmmss`
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub trans_completed_time: Option<String>,
+}
+
+/// Body sent to the `callback_url` of the Mpesa Express/ STK push API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StkPushCallback {
+    #[serde(rename = "Body")]
+    pub body: StkPushCallbackBody,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StkPushCallbackBody {
+    #[serde(rename = "stkCallback")]
+    pub stk_callback: StkCallback,
+}
+
+/// The `stkCallback` of an Mpesa Express/ STK push callback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StkCallback {
+    #[serde(rename = "MerchantRequestID")]
+    pub merchant_request_id: String,
+    /// Matches the `checkout_request_id` of the `MpesaExpressRequestResponse`
+    #[serde(rename = "CheckoutRequestID")]
+    pub checkout_request_id: String,
+    /// Status of the transaction, e.g. `MpesaResponseCode::CancelledByUser`
+    pub result_code: MpesaResponseCode,
+    pub result_desc: String,
+    /// Only present when the customer completed the payment
+    #[serde(default)]
+    pub callback_metadata: Option<CallbackMetadata>,
+}
+
+impl StkCallback {
+    /// Returns `true` if the customer completed the payment
+    pub fn is_success(&self) -> bool {
+        self.result_code.is_success()
+    }
+
+    /// Parses the `CallbackMetadata` into a [`StkCallbackMetadata`]
+    ///
+    /// # Errors
+    /// Returns a `ParseError` if the metadata items cannot be parsed
+    pub fn metadata(&self) -> MpesaResult<StkCallbackMetadata> {
+        match &self.callback_metadata {
+            Some(metadata) => metadata.parse(),
+            None => Ok(StkCallbackMetadata::default()),
+        }
+    }
+}
+
+/// List of items sent in the `CallbackMetadata` of a successful STK push
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallbackMetadata {
+    #[serde(rename = "Item", deserialize_with = "one_or_many")]
+    pub item: Vec<CallbackItem>,
+}
+
+impl CallbackMetadata {
+    /// Gets the value of the item named `name`
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.item
+            .iter()
+            .find(|item| item.name == name)
+            .and_then(|item| item.value.as_ref())
+    }
+
+    /// Parses the items into a type whose fields are named after the item names
+    ///
+    /// # Errors
+    /// Returns a `ParseError` if the items do not match the requested type
+    pub fn parse<T: DeserializeOwned>(&self) -> MpesaResult<T> {
+        let object = self
+            .item
+            .iter()
+            .filter_map(|item| Some((item.name.clone(), item.value.clone()?)))
+            .collect();
+        Ok(serde_json::from_value(Value::Object(object))?)
+    }
+}
+
+/// A single `CallbackMetadata` item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallbackItem {
+    #[serde(rename = "Name")]
+    pub name: String,
+    /// Can be either a json string or number. Some items, such as `Balance`, are sent without a value.
+    #[serde(rename = "Value", default)]
+    pub value: Option<Value>,
+}
+
+/// Typed `CallbackMetadata` of a successful STK push
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StkCallbackMetadata {
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub amount: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub mpesa_receipt_number: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub balance: Option<f64>,
+    /// Formatted as `yyyymmddhhmmss`
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub transaction_date: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_option_string_from_any")]
+    pub phone_number: Option<String>,
+}
+
+/// Body sent to the `validation_url` and `confirmation_url` registered with the C2B Register API
+///
+/// The same payload is sent for both validation and confirmation requests, only the
+/// `org_account_balance` is empty for validation requests.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct C2bTransaction {
+    /// The transaction type e.g `"Pay Bill"` or `"Buy Goods"`
+    pub transaction_type: String,
+    #[serde(rename = "TransID")]
+    pub trans_id: String,
+    /// Formatted as `yyyymmddhhmmss`
+    pub trans_time: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub trans_amount: f64,
+    pub business_short_code: String,
+    /// The account number entered by the customer, only applicable to paybills
+    #[serde(default)]
+    pub bill_ref_number: String,
+    #[serde(default)]
+    pub invoice_number: String,
+    /// The new balance of the shortcode after the payment, empty for validation requests
+    #[serde(default, deserialize_with = "deserialize_string_from_any")]
+    pub org_account_balance: String,
+    #[serde(rename = "ThirdPartyTransID", default)]
+    pub third_party_trans_id: String,
+    /// The customer's phone number, may be masked or hashed
+    #[serde(rename = "MSISDN")]
+    pub msisdn: String,
+    #[serde(default)]
+    pub first_name: String,
+    #[serde(default)]
+    pub middle_name: String,
+    #[serde(default)]
+    pub last_name: String,
+}
+
+/// Body sent to the `validation_url` registered with the C2B Register API
+pub type C2bValidationRequest = C2bTransaction;
+
+/// Body sent to the `confirmation_url` registered with the C2B Register API
+pub type C2bConfirmation = C2bTransaction;
+
+/// Result codes used to reject a C2B payment in a [`ValidationResponse`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum C2bValidationResultCode {
+    /// `C2B00011`
+    InvalidMsisdn,
+    /// `C2B00012`
+    InvalidAccountNumber,
+    /// `C2B00013`
+    InvalidAmount,
+    /// `C2B00014`
+    InvalidKycDetails,
+    /// `C2B00015`
+    InvalidShortcode,
+    /// `C2B00016`
+    OtherError,
+}
+
+impl C2bValidationResultCode {
+    /// The result code expected by M-Pesa
+    pub fn code(&self) -> &'static str {
+        match self {
+            C2bValidationResultCode::InvalidMsisdn => "C2B00011",
+            C2bValidationResultCode::InvalidAccountNumber => "C2B00012",
+            C2bValidationResultCode::InvalidAmount => "C2B00013",
+            C2bValidationResultCode::InvalidKycDetails => "C2B00014",
+            C2bValidationResultCode::InvalidShortcode => "C2B00015",
+            C2bValidationResultCode::OtherError => "C2B00016",
+        }
+    }
+}
+
+impl Display for C2bValidationResultCode {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// Response to a [`C2bValidationRequest`], telling M-Pesa whether to complete or cancel the payment
+///
+/// # Example
+/// ```rust
+/// use mpesa::callbacks::{C2bValidationResultCode, ValidationResponse};
+///
+/// let response = ValidationResponse::reject(C2bValidationResultCode::InvalidAccountNumber);
+///
+/// assert_eq!(
+///     serde_json::to_string(&response).unwrap(),
+///     r#"{"ResultCode":"C2B00012","ResultDesc":"Rejected"}"#
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ValidationResponse {
+    pub result_code: MpesaResponseCode,
+    pub result_desc: String,
+    /// Optional identifier of the payment in your system, echoed back in the
+    /// [`C2bConfirmation`]
+    #[serde(
+        rename = "ThirdPartyTransID",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub third_party_trans_id: Option<String>,
+}
+
+impl ValidationResponse {
+    /// Accepts the payment, M-Pesa will go ahead and complete the transaction
+    pub fn accept() -> Self {
+        ValidationResponse {
+            result_code: MpesaResponseCode::Success,
+            result_desc: "Accepted".to_owned(),
+            third_party_trans_id: None,
+        }
+    }
+
+    /// Rejects the payment with the given result code, M-Pesa will cancel the transaction
+    pub fn reject(code: C2bValidationResultCode) -> Self {
+        ValidationResponse {
+            result_code: code.into(),
+            result_desc: "Rejected".to_owned(),
+            third_party_trans_id: None,
+        }
+    }
+
+    /// Adds a `ThirdPartyTransID` to the response
+    pub fn third_party_trans_id<S: Into<String>>(mut self, third_party_trans_id: S) -> Self {
+        self.third_party_trans_id = Some(third_party_trans_id.into());
+        self
+    }
+
+    /// Returns `true` if the payment is accepted
+    pub fn is_accepted(&self) -> bool {
+        self.result_code.is_success()
+    }
+}
+
+/// Body sent to the Bill Manager `callback_url` when a customer pays an invoice
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BillManagerPaymentNotification {
+    pub transaction_id: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub paid_amount: f64,
+    #[serde(deserialize_with = "deserialize_string_from_number")]
+    pub msisdn: String,
+    /// Formatted as `yyyy-mm-dd`
+    pub date_created: String,
+    pub account_reference: String,
+    #[serde(deserialize_with = "deserialize_string_from_number")]
+    pub short_code: String,
+}
+
+fn find<'a>(items: &'a [KeyValue], key: &str) -> Option<&'a Value> {
+    items
+        .iter()
+        .find(|item| item.key == key)
+        .and_then(|item| item.value.as_ref())
+}
+
+fn to_object(items: &[KeyValue]) -> Value {
+    Value::Object(
+        items
+            .iter()
+            .filter_map(|item| Some((item.key.clone(), item.value.clone()?)))
+            .collect(),
+    )
+}
+
+/// M-Pesa sends a single object instead of an array when a list has only one entry
+fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        One(T),
+        Many(Vec<T>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(item) => vec![item],
+        OneOrMany::Many(items) => items,
+    })
+}
+
+fn deserialize_string_from_any<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(deserialize_option_string_from_any(deserializer)?.unwrap_or_default())
+}
+
+fn deserialize_option_string_from_any<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => None,
+        Some(Value::String(s)) => Some(s),
+        Some(other) => Some(other.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_b2c_result_callback() {
+        let body = r#"{
+            "Result": {
+                "ResultType": 0,
+                "ResultCode": 0,
+                "ResultDesc": "The service request is processed successfully.",
+                "OriginatorConversationID": "10571-7910404-1",
+                "ConversationID": "AG_20191219_00004e48cf7e3533f581",
+                "TransactionID": "NLJ41HAY6Q",
+                "ResultParameters": {
+                    "ResultParameter": [
+                        { "Key": "TransactionAmount", "Value": 10 },
+                        { "Key": "TransactionReceipt", "Value": "NLJ41HAY6Q" },
+                        { "Key": "B2CRecipientIsRegisteredCustomer", "Value": "Y" },
+                        { "Key": "B2CChargesPaidAccountAvailableFunds", "Value": -4510.00 },
+                        { "Key": "ReceiverPartyPublicName", "Value": "254708374149 - John Doe" },
+                        { "Key": "TransactionCompletedDateTime", "Value": "19.12.2019 11:45:50" },
+                        { "Key": "B2CUtilityAccountAvailableFunds", "Value": 10116.00 },
+                        { "Key": "B2CWorkingAccountAvailableFunds", "Value": 900000.00 }
+                    ]
+                },
+                "ReferenceData": {
+                    "ReferenceItem": {
+                        "Key": "QueueTimeoutURL",
+                        "Value": "https://internalsandbox.safaricom.co.ke/mpesa/b2cresults/v1/submit"
+                    }
+                }
+            }
+        }"#;
+
+        let callback: ResultCallback = serde_json::from_str(body).unwrap();
+        let result = callback.result;
+        assert!(result.is_success());
+        assert_eq!(result.result_type, 0);
+        assert_eq!(result.conversation_id, "AG_20191219_00004e48cf7e3533f581");
+        assert_eq!(result.transaction_id.as_deref(), Some("NLJ41HAY6Q"));
+        assert_eq!(
+            result
+                .reference_data
+                .as_ref()
+                .unwrap()
+                .get("QueueTimeoutURL"),
+            Some(&Value::from(
+                "https://internalsandbox.safaricom.co.ke/mpesa/b2cresults/v1/submit"
+            ))
+        );
+
+        let params: B2cResultParameters = result.parameters().unwrap();
+        assert_eq!(params.transaction_amount, Some(10.0));
+        assert_eq!(params.transaction_receipt.as_deref(), Some("NLJ41HAY6Q"));
+        assert_eq!(
+            params.recipient_is_registered_customer.as_deref(),
+            Some("Y")
+        );
+        assert_eq!(params.charges_paid_account_available_funds, Some(-4510.0));
+        assert_eq!(
+            params.receiver_party_public_name.as_deref(),
+            Some("254708374149 - John Doe")
+        );
+        assert_eq!(params.working_account_available_funds, Some(900000.0));
+    }
+
+    #[test]
+    fn test_failed_result_callback_without_parameters() {
+        let body = r#"{
+            "Result": {
+                "ResultType": 0,
+                "ResultCode": 2001,
+                "ResultDesc": "The initiator information is invalid.",
+                "OriginatorConversationID": "29112-34801843-1",
+                "ConversationID": "AG_20191219_00006c6fddb15123addf",
+                "TransactionID": "NLJ0000000",
+                "ReferenceData": {
+                    "ReferenceItem": {
+                        "Key": "QueueTimeoutURL",
+                        "Value": "https://internalsandbox.safaricom.co.ke/mpesa/b2cresults/v1/submit"
+                    }
+                }
+            }
+        }"#;
+
+        let callback: ResultCallback = serde_json::from_str(body).unwrap();
+        assert!(!callback.result.is_success());
+        assert_eq!(callback.result.result_code, "2001");
+        assert!(callback.result.result_parameters.is_none());
+
+        let params: B2cResultParameters = callback.result.parameters().unwrap();
+        assert!(params.transaction_receipt.is_none());
+    }
+
+    #[test]
+    fn test_b2b_result_callback() {
+        let body = r#"{
+            "Result": {
+                "ResultType": "0",
+                "ResultCode": "0",
+                "ResultDesc": "The service request is processed successfully",
+                "OriginatorConversationID": "626f6ddf-ab37-4650-b882-b1de92ec9aa4",
+                "ConversationID": "12345677dfdf89099B3",
+                "TransactionID": "QKA81LK5CY",
+                "ResultParameters": {
+                    "ResultParameter": [
+                        { "Key": "DebitAccountBalance", "Value": "{Amount={CurrencyCode=KES, MinimumAmount=618683, BasicAmount=6186.83}}" },
+                        { "Key": "Amount", "Value": "190.00" },
+                        { "Key": "DebitPartyAffectedAccountBalance", "Value": "Working Account|KES|346768.83|6186.83|340582.00|0.00" },
+                        { "Key": "TransCompletedTime", "Value": "20221110110717" },
+                        { "Key": "DebitPartyCharges", "Value": "" },
+                        { "Key": "ReceiverPartyPublicName", "Value": "000000– 1234567" },
+                        { "Key": "Currency", "Value": "KES" },
+                        { "Key": "InitiatorAccountCurrentBalance", "Value": "{Amount={CurrencyCode=KES, MinimumAmount=618683, BasicAmount=6186.83}}" }
+                    ]
+                },
+                "ReferenceData": {
+                    "ReferenceItem": [
+                        { "Key": "BillReferenceNumber", "Value": "19008" },
+                        { "Key": "QueueTimeoutURL", "Value": "https://mydomain.com/b2b/businessbuygoods/queue/" }
+                    ]
+                }
+            }
+        }"#;
+
+        let callback: ResultCallback = serde_json::from_str(body).unwrap();
+        assert!(callback.result.is_success());
+        assert_eq!(
+            callback
+                .result
+                .reference_data
+                .as_ref()
+                .unwrap()
+                .reference_item
+                .len(),
+            2
+        );
+
+        let params: B2bResultParameters = callback.result.parameters().unwrap();
+        assert_eq!(params.amount, Some(190.0));
+        assert_eq!(params.currency.as_deref(), Some("KES"));
+        assert_eq!(
+            params.trans_completed_time.as_deref(),
+            Some("20221110110717")
+        );
+    }
+
+    #[test]
+    fn test_account_balance_result_callback() {
+        let body = r#"{
+            "Result": {
+                "ResultType": 0,
+                "ResultCode": 0,
+                "ResultDesc": "The service request is processed successfully.",
+                "OriginatorConversationID": "16917-22577599-3",
+                "ConversationID": "AG_20200206_00005e091a8ec6b9eac5",
+                "TransactionID": "OA90000000",
+                "ResultParameters": {
+                    "ResultParameter": [
+                        { "Key": "AccountBalance", "Value": "Working Account|KES|700000.00|700000.00|0.00|0.00&Float Account|KES|0.00|0.00|0.00|0.00" },
+                        { "Key": "BOCompletedTime", "Value": 20200109125710 }
+                    ]
+                },
+                "ReferenceData": {
+                    "ReferenceItem": {
+                        "Key": "QueueTimeoutURL",
+                        "Value": "https://internalsandbox.safaricom.co.ke/mpesa/abresults/v1/submit"
+                    }
+                }
+            }
+        }"#;
+
+        let callback: ResultCallback = serde_json::from_str(body).unwrap();
+        let params: AccountBalanceResultParameters = callback.result.parameters().unwrap();
+        assert_eq!(
+            params.account_balance.as_deref(),
+            Some("Working Account|KES|700000.00|700000.00|0.00|0.00&Float Account|KES|0.00|0.00|0.00|0.00")
+        );
+        assert_eq!(params.completed_time.as_deref(), Some("20200109125710"));
+    }
+
+    #[test]
+    fn test_transaction_status_result_callback() {
+        let body = r#"{
+            "Result": {
+                "ResultType": 0,
+                "ResultCode": 0,
+                "ResultDesc": "The service request is processed successfully.",
+                "OriginatorConversationID": "10816-694520-2",
+                "ConversationID": "AG_20200927_00007d4c98884c889b25",
+                "TransactionID": "LXXXXXX1234",
+                "ResultParameters": {
+                    "ResultParameter": [
+                        { "Key": "DebitPartyName", "Value": "600310 - Safaricom333" },
+                        { "Key": "CreditPartyName", "Value": "254708374149 - John Doe" },
+                        { "Key": "OriginatorConversationID", "Value": "12345-67890-1" },
+                        { "Key": "InitiatedTime", "Value": 20200927103417 },
+                        { "Key": "DebitAccountType", "Value": "Utility Account" },
+                        { "Key": "DebitPartyCharges", "Value": "" },
+                        { "Key": "TransactionReason" },
+                        { "Key": "ReasonType", "Value": "Business Payment to Customer via API" },
+                        { "Key": "TransactionStatus", "Value": "Completed" },
+                        { "Key": "FinalisedTime", "Value": 20200927103417 },
+                        { "Key": "Amount", "Value": 10 },
+                        { "Key": "ConversationID", "Value": "AG_20200927_00007d4c98884c889b25" },
+                        { "Key": "ReceiptNo", "Value": "LXXXXXX1234" }
+                    ]
+                },
+                "ReferenceData": {
+                    "ReferenceItem": { "Key": "Occasion" }
+                }
+            }
+        }"#;
+
+        let callback: ResultCallback = serde_json::from_str(body).unwrap();
+        assert!(callback
+            .result
+            .reference_data
+            .as_ref()
+            .unwrap()
+            .get("Occasion")
+            .is_none());
+
+        let params: TransactionStatusResultParameters = callback.result.parameters().unwrap();
+        assert_eq!(params.receipt_no.as_deref(), Some("LXXXXXX1234"));
+        assert_eq!(params.transaction_status.as_deref(), Some("Completed"));
+        assert_eq!(params.amount, Some(10.0));
+        assert_eq!(params.debit_party_charges, None);
+        assert!(params.transaction_reason.is_none());
+        assert_eq!(params.finalised_time.as_deref(), Some("20200927103417"));
+    }
+
+    #[test]
+    fn test_transaction_reversal_result_callback() {
+        let body = r#"{
+            "Result": {
+                "ResultType": 0,
+                "ResultCode": 0,
+                "ResultDesc": "The service request is processed successfully.",
+                "OriginatorConversationID": "8521-4298025-1",
+                "ConversationID": "AG_20181005_00004d7ee675c0c7ee0b",
+                "TransactionID": "MJ561H6X5O",
+                "ResultParameters": {
+                    "ResultParameter": [
+                        { "Key": "DebitAccountBalance", "Value": "Utility Account|KES|51661.00|51661.00|0.00|0.00" },
+                        { "Key": "Amount", "Value": 100 },
+                        { "Key": "TransCompletedTime", "Value": 20181005153225 },
+                        { "Key": "OriginalTransactionID", "Value": "MJ551H6X5D" },
+                        { "Key": "Charge", "Value": 0 },
+                        { "Key": "CreditPartyPublicName", "Value": "254708374149 - John Doe" },
+                        { "Key": "DebitPartyPublicName", "Value": "601315 - Safaricom1338" }
+                    ]
+                },
+                "ReferenceData": {
+                    "ReferenceItem": {
+                        "Key": "QueueTimeoutURL",
+                        "Value": "https://internalsandbox.safaricom.co.ke/mpesa/reversalresults/v1/submit"
+                    }
+                }
+            }
+        }"#;
+
+        let callback: ResultCallback = serde_json::from_str(body).unwrap();
+        let params: TransactionReversalResultParameters = callback.result.parameters().unwrap();
+        assert_eq!(params.amount, Some(100.0));
+        assert_eq!(params.charge, Some(0.0));
+        assert_eq!(
+            params.original_transaction_id.as_deref(),
+            Some("MJ551H6X5D")
+        );
+        assert_eq!(
+            params.trans_completed_time.as_deref(),
+            Some("20181005153225")
+        );
+    }
+
+    #[test]
+    fn test_successful_stk_push_callback() {
+        let body = r#"{
+            "Body": {
+                "stkCallback": {
+                    "MerchantRequestID": "29115-34620561-1",
+                    "CheckoutRequestID": "ws_CO_191220191020363925",
+                    "ResultCode": 0,
+                    "ResultDesc": "The service request is processed successfully.",
+                    "CallbackMetadata": {
+                        "Item": [
+                            { "Name": "Amount", "Value": 1.00 },
+                            { "Name": "MpesaReceiptNumber", "Value": "NLJ7RT61SV" },
+                            { "Name": "Balance" },
+                            { "Name": "TransactionDate", "Value": 20191219102115 },
+                            { "Name": "PhoneNumber", "Value": 254708374149 }
+                        ]
+                    }
+                }
+            }
+        }"#;
+
+        let callback: StkPushCallback = serde_json::from_str(body).unwrap();
+        let stk_callback = callback.body.stk_callback;
+        assert!(stk_callback.is_success());
+        assert_eq!(stk_callback.checkout_request_id, "ws_CO_191220191020363925");
+        assert_eq!(
+            stk_callback
+                .callback_metadata
+                .as_ref()
+                .unwrap()
+                .get("Amount"),
+            Some(&Value::from(1.0))
+        );
+
+        let metadata = stk_callback.metadata().unwrap();
+        assert_eq!(metadata.amount, Some(1.0));
+        assert_eq!(metadata.mpesa_receipt_number.as_deref(), Some("NLJ7RT61SV"));
+        assert_eq!(metadata.balance, None);
+        assert_eq!(metadata.transaction_date.as_deref(), Some("20191219102115"));
+        assert_eq!(metadata.phone_number.as_deref(), Some("254708374149"));
+    }
+
+    #[test]
+    fn test_cancelled_stk_push_callback() {
+        let body = r#"{
+            "Body": {
+                "stkCallback": {
+                    "MerchantRequestID": "29115-34620561-1",
+                    "CheckoutRequestID": "ws_CO_191220191020363925",
+                    "ResultCode": 1032,
+                    "ResultDesc": "Request cancelled by user."
+                }
+            }
+        }"#;
+
+        let callback: StkPushCallback = serde_json::from_str(body).unwrap();
+        let stk_callback = callback.body.stk_callback;
+        assert!(!stk_callback.is_success());
+        assert_eq!(stk_callback.result_code, "1032");
+        assert_eq!(stk_callback.result_code, MpesaResponseCode::CancelledByUser);
+        assert!(stk_callback.callback_metadata.is_none());
+        assert!(stk_callback.metadata().unwrap().amount.is_none());
+    }
+
+    #[test]
+    fn test_c2b_validation_request() {
+        let body = r#"{
+            "TransactionType": "Pay Bill",
+            "TransID": "RKTQDM7W6S",
+            "TransTime": "20191122063845",
+            "TransAmount": "10",
+            "BusinessShortCode": "600638",
+            "BillRefNumber": "invoice008",
+            "InvoiceNumber": "",
+            "OrgAccountBalance": "",
+            "ThirdPartyTransID": "",
+            "MSISDN": "25470****149",
+            "FirstName": "John",
+            "MiddleName": "",
+            "LastName": "Doe"
+        }"#;
+
+        let request: C2bValidationRequest = serde_json::from_str(body).unwrap();
+        assert_eq!(request.trans_id, "RKTQDM7W6S");
+        assert_eq!(request.trans_amount, 10.0);
+        assert_eq!(request.bill_ref_number, "invoice008");
+        assert_eq!(request.org_account_balance, "");
+        assert_eq!(request.first_name, "John");
+    }
+
+    #[test]
+    fn test_c2b_confirmation() {
+        let body = r#"{
+            "TransactionType": "Pay Bill",
+            "TransID": "RKTQDM7W6S",
+            "TransTime": "20191122063845",
+            "TransAmount": "10.00",
+            "BusinessShortCode": "600638",
+            "BillRefNumber": "invoice008",
+            "OrgAccountBalance": 49197.00,
+            "MSISDN": "25470****149",
+            "FirstName": "John"
+        }"#;
+
+        let confirmation: C2bConfirmation = serde_json::from_str(body).unwrap();
+        assert_eq!(confirmation.trans_amount, 10.0);
+        assert_eq!(confirmation.org_account_balance, "49197.0");
+        assert_eq!(confirmation.invoice_number, "");
+        assert_eq!(confirmation.last_name, "");
+    }
+
+    #[test]
+    fn test_bill_manager_payment_notification() {
+        let body = r#"{
+            "transactionId": "RJB53MYR1N",
+            "paidAmount": "5000",
+            "msisdn": "254710119383",
+            "dateCreated": "2021-09-15",
+            "accountReference": "LGHJIO789",
+            "shortCode": "174379"
+        }"#;
+
+        let notification: BillManagerPaymentNotification = serde_json::from_str(body).unwrap();
+        assert_eq!(notification.transaction_id, "RJB53MYR1N");
+        assert_eq!(notification.paid_amount, 5000.0);
+        assert_eq!(notification.account_reference, "LGHJIO789");
+        assert_eq!(notification.short_code, "174379");
+    }
+
+    #[test]
+    fn test_validation_response_accept() {
+        let response = ValidationResponse::accept();
+        assert!(response.is_accepted());
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            serde_json::json!({ "ResultCode": "0", "ResultDesc": "Accepted" })
+        );
+
+        let response = ValidationResponse::accept().third_party_trans_id("1234567890");
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            serde_json::json!({
+                "ResultCode": "0",
+                "ResultDesc": "Accepted",
+                "ThirdPartyTransID": "1234567890"
+            })
+        );
+    }
+
+    #[test]
+    fn test_validation_response_reject() {
+        let codes = [
+            (C2bValidationResultCode::InvalidMsisdn, "C2B00011"),
+            (C2bValidationResultCode::InvalidAccountNumber, "C2B00012"),
+            (C2bValidationResultCode::InvalidAmount, "C2B00013"),
+            (C2bValidationResultCode::InvalidKycDetails, "C2B00014"),
+            (C2bValidationResultCode::InvalidShortcode, "C2B00015"),
+            (C2bValidationResultCode::OtherError, "C2B00016"),
+        ];
+
+        for (code, expected) in codes {
+            let response = ValidationResponse::reject(code);
+            assert!(!response.is_accepted());
+            assert_eq!(code.to_string(), expected);
+            assert_eq!(
+                serde_json::to_value(&response).unwrap(),
+                serde_json::json!({ "ResultCode": expected, "ResultDesc": "Rejected" })
+            );
+        }
+    }
+}
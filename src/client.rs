@@ -1,38 +1,182 @@
-use std::cell::RefCell;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
-use cached::Cached;
-use openssl::base64;
-use openssl::rsa::Padding;
-use openssl::x509::X509;
-use reqwest::Client as HttpClient;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use reqwest::{Client as HttpClient, Response, StatusCode};
 use secrecy::{ExposeSecret, Secret};
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+#[cfg(any(
+    feature = "account_balance",
+    feature = "b2b",
+    feature = "b2c",
+    feature = "bill_manager",
+    feature = "c2b_register",
+    feature = "c2b_simulate",
+    feature = "dynamic_qr",
+    feature = "express_query",
+    feature = "express_request",
+    feature = "transaction_reversal",
+    feature = "transaction_status"
+))]
+use serde::{Deserialize, Serialize};
 
-use crate::auth::AUTH;
-use crate::environment::ApiEnvironment;
+use crate::auth::EXPIRY_MARGIN;
+use crate::environment::{ApiEnvironment, Certificate};
+#[cfg(any(
+    feature = "account_balance",
+    feature = "b2b",
+    feature = "b2c",
+    feature = "bill_manager",
+    feature = "c2b_register",
+    feature = "express_request",
+    feature = "transaction_reversal",
+    feature = "transaction_status"
+))]
+use crate::errors::BuilderError;
+use crate::errors::{HttpContext, REDACTED};
+use crate::middleware::Middleware;
+#[cfg(any(
+    feature = "account_balance",
+    feature = "b2b",
+    feature = "b2c",
+    feature = "bill_manager",
+    feature = "c2b_register",
+    feature = "c2b_simulate",
+    feature = "dynamic_qr",
+    feature = "express_query",
+    feature = "express_request",
+    feature = "transaction_reversal",
+    feature = "transaction_status"
+))]
+use crate::rate_limit::{RateLimit, RateLimits};
+use crate::retry::RetryPolicy;
+#[cfg(any(
+    feature = "account_balance",
+    feature = "b2b",
+    feature = "b2c",
+    feature = "bill_manager",
+    feature = "c2b_register",
+    feature = "c2b_simulate",
+    feature = "dynamic_qr",
+    feature = "express_query",
+    feature = "express_request",
+    feature = "transaction_reversal",
+    feature = "transaction_status"
+))]
+use crate::retry::{retry, Failure};
+#[cfg(feature = "account_balance")]
+use crate::services::AccountBalanceBuilder;
+#[cfg(feature = "b2b")]
+use crate::services::B2bBuilder;
+#[cfg(feature = "c2b_register")]
+use crate::services::C2bRegisterBuilder;
+#[cfg(feature = "c2b_simulate")]
+use crate::services::C2bSimulateBuilder;
+#[cfg(feature = "express_query")]
+use crate::services::MpesaExpressQueryBuilder;
+#[cfg(feature = "transaction_reversal")]
+use crate::services::TransactionReversalBuilder;
+#[cfg(feature = "transaction_status")]
+use crate::services::TransactionStatusBuilder;
+#[cfg(feature = "b2c")]
+use crate::services::{B2cBuilder, TypedB2cBuilder};
+#[cfg(feature = "bill_manager")]
 use crate::services::{
-    AccountBalanceBuilder, B2bBuilder, B2cBuilder, BulkInvoiceBuilder, C2bRegisterBuilder,
-    C2bSimulateBuilder, CancelInvoiceBuilder, DynamicQR, DynamicQRBuilder,
-    MpesaExpressRequestBuilder, OnboardBuilder, OnboardModifyBuilder, ReconciliationBuilder,
-    SingleInvoiceBuilder, TransactionReversalBuilder, TransactionStatusBuilder,
+    BulkInvoiceBuilder, CancelInvoiceBuilder, OnboardBuilder, OnboardModifyBuilder,
+    ReconciliationBuilder, SingleInvoiceBuilder, TypedReconciliationBuilder,
 };
-use crate::{auth, MpesaResult};
+#[cfg(feature = "dynamic_qr")]
+use crate::services::{DynamicQR, DynamicQRBuilder};
+#[cfg(feature = "express_request")]
+use crate::services::{MpesaExpressRequestBuilder, TypedMpesaExpressRequestBuilder};
+use crate::token_store::{TokenCache, TokenStore};
+#[cfg(any(
+    feature = "account_balance",
+    feature = "b2b",
+    feature = "b2c",
+    feature = "bill_manager",
+    feature = "c2b_register",
+    feature = "c2b_simulate",
+    feature = "dynamic_qr",
+    feature = "express_query",
+    feature = "express_request",
+    feature = "transaction_reversal",
+    feature = "transaction_status"
+))]
+use crate::MpesaResponseCode;
+#[cfg(any(
+    feature = "account_balance",
+    feature = "b2b",
+    feature = "b2c",
+    feature = "bill_manager",
+    feature = "c2b_register",
+    feature = "express_request",
+    feature = "transaction_reversal",
+    feature = "transaction_status"
+))]
+use crate::Url;
+use crate::{auth, MpesaError, MpesaResult};
 
 /// Source: [test credentials](https://developer.safaricom.co.ke/test_credentials)
 const DEFAULT_INITIATOR_PASSWORD: &str = "Safcom496!";
 /// Get current package version from metadata
 const CARGO_PACKAGE_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// The initiator password and the security credential generated from it
+#[derive(Default)]
+struct Initiator {
+    password: Option<Secret<String>>,
+    security_credential: Option<String>,
+}
+
+impl fmt::Debug for Initiator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Initiator")
+            .field("password", &self.password.as_ref().map(|_| REDACTED))
+            .field(
+                "security_credential",
+                &self.security_credential.as_ref().map(|_| REDACTED),
+            )
+            .finish()
+    }
+}
+
 /// Mpesa client that will facilitate communication with the Safaricom API
+///
+/// The client is `Send + Sync`, it can be shared across tasks in an `Arc` or cloned. Clones share
+/// the same http connection pool, token store and initiator password.
 #[derive(Clone, Debug)]
 pub struct Mpesa {
     client_key: String,
     client_secret: Secret<String>,
-    initiator_password: RefCell<Option<Secret<String>>>,
+    initiator: Arc<RwLock<Initiator>>,
+    #[cfg(any(feature = "express_request", feature = "express_query"))]
+    pass_key: Option<Secret<String>>,
     pub(crate) base_url: String,
-    certificate: String,
+    certificate: Certificate,
+    production: bool,
     pub(crate) http_client: HttpClient,
+    token_store: Arc<dyn TokenStore>,
+    /// Held while authenticating, so that concurrent requests wait for a single new token
+    auth_lock: Arc<tokio::sync::Mutex<()>>,
+    pub(crate) retry_policy: RetryPolicy,
+    #[cfg(any(
+        feature = "account_balance",
+        feature = "b2b",
+        feature = "b2c",
+        feature = "bill_manager",
+        feature = "c2b_register",
+        feature = "c2b_simulate",
+        feature = "dynamic_qr",
+        feature = "express_query",
+        feature = "express_request",
+        feature = "transaction_reversal",
+        feature = "transaction_status"
+    ))]
+    rate_limits: RateLimits,
+    middleware: Vec<Arc<dyn Middleware>>,
 }
 
 impl Mpesa {
@@ -56,42 +200,190 @@ impl Mpesa {
     ///    assert!(client.is_connected().await);
     /// }
     /// ```
+    ///
     /// # Panics
     /// This method can panic if a TLS backend cannot be initialized for the internal http_client
+    /// or if the certificate of a custom `ApiEnvironment` is invalid, the certificates bundled
+    /// with [`Environment`](crate::Environment) are always valid. Use [`Mpesa::builder`] to
+    /// handle these errors
     pub fn new<S: Into<String>>(
         client_key: S,
         client_secret: S,
         environment: impl ApiEnvironment,
     ) -> Self {
-        let http_client = HttpClient::builder()
-            .connect_timeout(std::time::Duration::from_millis(10_000))
-            .user_agent(format!("mpesa-rust@{CARGO_PACKAGE_VERSION}"))
-            // TODO: Potentialy return a `Result` enum from Mpesa::new?
-            //       Making assumption that creation of http client cannot fail
+        Self::builder()
+            .client_key(client_key)
+            .client_secret(client_secret)
+            .environment(environment)
             .build()
-            .expect("Error building http client");
+            .expect("Error building Mpesa client")
+    }
+
+    /// Creates a [`MpesaBuilder`] to configure a new `Mpesa` client.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// use mpesa::{Environment, Mpesa};
+    ///
+    /// let client = Mpesa::builder()
+    ///     .client_key("client_key")
+    ///     .client_secret("client_secret")
+    ///     .environment(Environment::Sandbox)
+    ///     .initiator_password("initiator_password")
+    ///     .timeout(Duration::from_secs(30))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn builder() -> MpesaBuilder {
+        MpesaBuilder::default()
+    }
 
-        let base_url = environment.base_url().to_owned();
-        let certificate = environment.get_certificate().to_owned();
+    /// Replaces the store in which the client keeps its access tokens.
+    /// Defaults to an in-memory [`TokenCache`] owned by the client.
+    ///
+    /// Stores can be shared by several clients with different credentials, or even several
+    /// processes when backed by a file, Redis etc. See [`TokenStore`] for an example.
+    pub fn with_token_store<S: TokenStore + 'static>(mut self, token_store: S) -> Self {
+        self.token_store = Arc::new(token_store);
+        self
+    }
 
-        Self {
-            client_key: client_key.into(),
-            client_secret: Secret::new(client_secret.into()),
-            initiator_password: RefCell::new(None),
-            base_url,
-            certificate,
-            http_client,
+    /// Sets the policy used to retry authentication and idempotent requests, e.g. queries, that
+    /// fail with a transient error. Defaults to [`RetryPolicy::none`].
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Limits the rate and concurrency of every request sent by the client, authentication
+    /// excepted. Requests over the limit wait until they are allowed through.
+    #[cfg(any(
+        feature = "account_balance",
+        feature = "b2b",
+        feature = "b2c",
+        feature = "bill_manager",
+        feature = "c2b_register",
+        feature = "c2b_simulate",
+        feature = "dynamic_qr",
+        feature = "express_query",
+        feature = "express_request",
+        feature = "transaction_reversal",
+        feature = "transaction_status"
+    ))]
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limits.set_client_limit(rate_limit);
+        self
+    }
+
+    /// Limits the rate and concurrency of the requests sent to a single endpoint, e.g.
+    /// [`B2C_URL`](crate::services::B2C_URL), on top of the client wide limit
+    #[cfg(any(
+        feature = "account_balance",
+        feature = "b2b",
+        feature = "b2c",
+        feature = "bill_manager",
+        feature = "c2b_register",
+        feature = "c2b_simulate",
+        feature = "dynamic_qr",
+        feature = "express_query",
+        feature = "express_request",
+        feature = "transaction_reversal",
+        feature = "transaction_status"
+    ))]
+    pub fn with_endpoint_rate_limit(mut self, path: &'static str, rate_limit: RateLimit) -> Self {
+        self.rate_limits.set_endpoint_limit(path, rate_limit);
+        self
+    }
+
+    /// Adds a [`Middleware`] hooking into every request sent by the client.
+    /// Middleware run in the order they were added.
+    pub fn with_middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Sends an http request through the client's middleware
+    pub(crate) async fn execute(&self, mut request: reqwest::Request) -> MpesaResult<Response> {
+        for middleware in &self.middleware {
+            middleware.before_send(&mut request).await?;
+        }
+
+        if self.middleware.is_empty() {
+            return Ok(self.http_client.execute(request).await?);
+        }
+
+        let sent = request.try_clone().unwrap_or_else(|| {
+            let mut sent = reqwest::Request::new(request.method().clone(), request.url().clone());
+            *sent.headers_mut() = request.headers().clone();
+            sent
+        });
+        let response = self.http_client.execute(request).await?;
+
+        for middleware in &self.middleware {
+            middleware.after_response(&sent, &response).await?;
         }
+        Ok(response)
     }
 
     /// Gets the initiator password
-    /// If `None`, the default password is `"Safcom496!"`
-    pub(crate) fn initiator_password(&self) -> String {
-        self.initiator_password
-            .borrow()
+    /// If `None`, the default password is `"Safcom496!"`, outside of production
+    #[cfg(test)]
+    pub(crate) fn initiator_password(&self) -> MpesaResult<String> {
+        self.password_of(&self.initiator.read().unwrap())
+    }
+
+    /// The password of `initiator`, or the sandbox default if none was set
+    fn password_of(&self, initiator: &Initiator) -> MpesaResult<String> {
+        match initiator.password.as_ref() {
+            Some(password) => Ok(password.expose_secret().into()),
+            None => self
+                .sandbox_default("initiator_password", DEFAULT_INITIATOR_PASSWORD)
+                .map(Into::into),
+        }
+    }
+
+    /// Returns the sandbox `default` of `field`, or an error if the client targets production
+    pub(crate) fn sandbox_default(
+        &self,
+        field: &'static str,
+        default: &'static str,
+    ) -> MpesaResult<&'static str> {
+        if self.production {
+            return Err(MpesaError::SandboxDefaultInProduction(field));
+        }
+        Ok(default)
+    }
+
+    /// Validates the callback url `field`, which must use https if the client targets production
+    #[cfg(any(
+        feature = "account_balance",
+        feature = "b2b",
+        feature = "b2c",
+        feature = "bill_manager",
+        feature = "c2b_register",
+        feature = "express_request",
+        feature = "transaction_reversal",
+        feature = "transaction_status"
+    ))]
+    pub(crate) fn callback_url(&self, field: &'static str, url: &str) -> MpesaResult<Url> {
+        let url = Url::parse(url).map_err(|e| e.for_field(field))?;
+        if self.production && !url.is_https() {
+            return Err(MpesaError::BuilderError(BuilderError::ValidationError(
+                format!("{field} is invalid: a URL must use https in production"),
+            )));
+        }
+        Ok(url)
+    }
+
+    /// Gets the pass key used by Mpesa Express requests that do not set one
+    #[cfg(any(feature = "express_request", feature = "express_query"))]
+    pub(crate) fn pass_key(&self) -> Option<&str> {
+        self.pass_key
             .as_ref()
-            .map(|password| password.expose_secret().into())
-            .unwrap_or(DEFAULT_INITIATOR_PASSWORD.to_owned())
+            .map(|pass_key| pass_key.expose_secret().as_str())
     }
 
     /// Get the client key
@@ -111,7 +403,8 @@ impl Mpesa {
     /// - `transaction_reversal`
     /// - `transaction_status`
     ///
-    /// You will need to call this method and set your production initiator password.
+    /// You will need to call this method and set your production initiator password, requests
+    /// to these apis fail with `MpesaError::SandboxDefaultInProduction` otherwise.
     /// If in development, a default initiator password from the test credentials is already pre-set
     ///
     /// # Example
@@ -133,7 +426,10 @@ impl Mpesa {
     /// }
     /// ```
     pub fn set_initiator_password<S: Into<String>>(&self, initiator_password: S) {
-        *self.initiator_password.borrow_mut() = Some(Secret::new(initiator_password.into()));
+        *self.initiator.write().unwrap() = Initiator {
+            password: Some(Secret::new(initiator_password.into())),
+            security_credential: None,
+        };
     }
 
     /// Checks if the client can be authenticated
@@ -145,94 +441,131 @@ impl Mpesa {
     ///
     /// Safaricom API docs [reference](https://developer.safaricom.co.ke/APIs/Authorization)
     ///
-    /// Returns auth token as a `String` that is ttl-cached in memory for subsequent requests.
+    /// Returns auth token as a `String` that is kept in the client's `TokenStore` for subsequent
+    /// requests, until shortly before the expiry reported by M-Pesa.
+    ///
+    /// Only one request per client, and its clones, authenticates at a time. Requests that find
+    /// no token while another is being generated wait for it rather than authenticating again.
     ///
     /// # Errors
     /// Returns a `MpesaError` on failure
     pub(crate) async fn auth(&self) -> MpesaResult<String> {
-        if let Some(token) = AUTH.lock().await.cache_get(&self.client_key) {
-            return Ok(token.to_owned());
+        if let Some(token) = self.token_store.get(&self.client_key).await? {
+            return Ok(token);
+        }
+
+        let _auth_lock = self.auth_lock.lock().await;
+        if let Some(token) = self.token_store.get(&self.client_key).await? {
+            return Ok(token);
         }
 
         // Generate a new access token
-        let new_token = auth::auth_prime_cache(self).await?;
+        let response = auth::auth(self).await?;
 
-        // Double-check if the access token is cached by another thread
-        if let Some(token) = AUTH.lock().await.cache_get(&self.client_key) {
-            return Ok(token.to_owned());
-        }
+        // Store the new token for the lifetime reported by M-Pesa, minus a safety margin
+        self.token_store
+            .set(
+                &self.client_key,
+                &response.access_token,
+                Duration::from_secs(response.expires_in).saturating_sub(EXPIRY_MARGIN),
+            )
+            .await?;
 
-        // Cache the new token
-        AUTH.lock()
-            .await
-            .cache_set(self.client_key.clone(), new_token.to_owned());
+        Ok(response.access_token)
+    }
 
-        Ok(new_token)
+    /// Discards the stored access token and requests a new one.
+    ///
+    /// Tokens are only refreshed automatically once they have expired, the first request after
+    /// the expiry authenticates while concurrent requests wait for its token. The client does not
+    /// refresh tokens ahead of time, to keep authentication off the request path call this
+    /// periodically, e.g. from a background task a few minutes before the token expires.
+    ///
+    /// # Errors
+    /// Returns a `MpesaError` on failure
+    pub async fn refresh_token(&self) -> MpesaResult<()> {
+        self.token_store.remove(&self.client_key).await?;
+        self.auth().await?;
+        Ok(())
     }
 
     #[cfg(feature = "b2c")]
     #[doc = include_str!("../docs/client/b2c.md")]
-    pub fn b2c<'a>(&'a self, initiator_name: &'a str) -> B2cBuilder {
+    pub fn b2c<'a>(&'a self, initiator_name: &'a str) -> B2cBuilder<'a> {
         B2cBuilder::new(self, initiator_name)
     }
 
+    #[cfg(feature = "b2c")]
+    /// Returns a [`TypedB2cBuilder`], a B2C builder that only compiles `send` once every
+    /// required field is set
+    pub fn b2c_typed<'a>(&'a self, initiator_name: &'a str) -> TypedB2cBuilder<'a> {
+        TypedB2cBuilder::new(self, initiator_name)
+    }
+
     #[cfg(feature = "b2b")]
     #[doc = include_str!("../docs/client/b2b.md")]
-    pub fn b2b<'a>(&'a self, initiator_name: &'a str) -> B2bBuilder {
+    pub fn b2b<'a>(&'a self, initiator_name: &'a str) -> B2bBuilder<'a> {
         B2bBuilder::new(self, initiator_name)
     }
 
     #[cfg(feature = "bill_manager")]
     #[doc = include_str!("../docs/client/bill_manager/onboard.md")]
-    pub fn onboard(&self) -> OnboardBuilder {
+    pub fn onboard(&self) -> OnboardBuilder<'_> {
         OnboardBuilder::new(self)
     }
 
     #[cfg(feature = "bill_manager")]
     #[doc = include_str!("../docs/client/bill_manager/onboard_modify.md")]
-    pub fn onboard_modify(&self) -> OnboardModifyBuilder {
+    pub fn onboard_modify(&self) -> OnboardModifyBuilder<'_> {
         OnboardModifyBuilder::new(self)
     }
 
     #[cfg(feature = "bill_manager")]
     #[doc = include_str!("../docs/client/bill_manager/bulk_invoice.md")]
-    pub fn bulk_invoice(&self) -> BulkInvoiceBuilder {
+    pub fn bulk_invoice(&self) -> BulkInvoiceBuilder<'_> {
         BulkInvoiceBuilder::new(self)
     }
 
     #[cfg(feature = "bill_manager")]
     #[doc = include_str!("../docs/client/bill_manager/single_invoice.md")]
-    pub fn single_invoice(&self) -> SingleInvoiceBuilder {
+    pub fn single_invoice(&self) -> SingleInvoiceBuilder<'_> {
         SingleInvoiceBuilder::new(self)
     }
 
     #[cfg(feature = "bill_manager")]
     #[doc = include_str!("../docs/client/bill_manager/reconciliation.md")]
-    pub fn reconciliation(&self) -> ReconciliationBuilder {
+    pub fn reconciliation(&self) -> ReconciliationBuilder<'_> {
         ReconciliationBuilder::new(self)
     }
 
+    #[cfg(feature = "bill_manager")]
+    /// Returns a [`TypedReconciliationBuilder`], a reconciliation builder that only compiles
+    /// `send` once every field is set
+    pub fn reconciliation_typed(&self) -> TypedReconciliationBuilder<'_> {
+        TypedReconciliationBuilder::new(self)
+    }
+
     #[cfg(feature = "bill_manager")]
     #[doc = include_str!("../docs/client/bill_manager/cancel_invoice.md")]
-    pub fn cancel_invoice(&self) -> CancelInvoiceBuilder {
+    pub fn cancel_invoice(&self) -> CancelInvoiceBuilder<'_> {
         CancelInvoiceBuilder::new(self)
     }
 
     #[cfg(feature = "c2b_register")]
     #[doc = include_str!("../docs/client/c2b_register.md")]
-    pub fn c2b_register(&self) -> C2bRegisterBuilder {
+    pub fn c2b_register(&self) -> C2bRegisterBuilder<'_> {
         C2bRegisterBuilder::new(self)
     }
 
     #[cfg(feature = "c2b_simulate")]
     #[doc = include_str!("../docs/client/c2b_simulate.md")]
-    pub fn c2b_simulate(&self) -> C2bSimulateBuilder {
+    pub fn c2b_simulate(&self) -> C2bSimulateBuilder<'_> {
         C2bSimulateBuilder::new(self)
     }
 
     #[cfg(feature = "account_balance")]
     #[doc = include_str!("../docs/client/account_balance.md")]
-    pub fn account_balance<'a>(&'a self, initiator_name: &'a str) -> AccountBalanceBuilder {
+    pub fn account_balance<'a>(&'a self, initiator_name: &'a str) -> AccountBalanceBuilder<'a> {
         AccountBalanceBuilder::new(self, initiator_name)
     }
 
@@ -241,31 +574,79 @@ impl Mpesa {
     pub fn express_request<'a>(
         &'a self,
         business_short_code: &'a str,
-    ) -> MpesaExpressRequestBuilder {
+    ) -> MpesaExpressRequestBuilder<'a> {
         MpesaExpressRequestBuilder::new(self, business_short_code)
     }
 
+    #[cfg(feature = "express_request")]
+    /// Returns a [`TypedMpesaExpressRequestBuilder`], an M-Pesa Express builder that only
+    /// compiles `send` once every required field is set
+    pub fn express_request_typed<'a>(
+        &'a self,
+        business_short_code: &'a str,
+    ) -> TypedMpesaExpressRequestBuilder<'a> {
+        TypedMpesaExpressRequestBuilder::new(self, business_short_code)
+    }
+
+    #[cfg(feature = "express_query")]
+    #[doc = include_str!("../docs/client/express_query.md")]
+    pub fn express_query<'a>(
+        &'a self,
+        business_short_code: &'a str,
+    ) -> MpesaExpressQueryBuilder<'a> {
+        MpesaExpressQueryBuilder::new(self, business_short_code)
+    }
+
     #[cfg(feature = "transaction_reversal")]
     #[doc = include_str!("../docs/client/transaction_reversal.md")]
     pub fn transaction_reversal<'a>(
         &'a self,
         initiator_name: &'a str,
-    ) -> TransactionReversalBuilder {
+    ) -> TransactionReversalBuilder<'a> {
         TransactionReversalBuilder::new(self, initiator_name)
     }
 
     #[cfg(feature = "transaction_status")]
     #[doc = include_str!("../docs/client/transaction_status.md")]
-    pub fn transaction_status<'a>(&'a self, initiator_name: &'a str) -> TransactionStatusBuilder {
+    pub fn transaction_status<'a>(
+        &'a self,
+        initiator_name: &'a str,
+    ) -> TransactionStatusBuilder<'a> {
         TransactionStatusBuilder::new(self, initiator_name)
     }
 
     #[cfg(feature = "dynamic_qr")]
     #[doc = include_str!("../docs/client/dynamic_qr.md")]
-    pub fn dynamic_qr(&self) -> DynamicQRBuilder {
+    pub fn dynamic_qr(&self) -> DynamicQRBuilder<'_> {
         DynamicQR::builder(self)
     }
 
+    /// Returns the security credential sent with `account_balance`, `b2b`, `b2c`,
+    /// `transaction_reversal` and `transaction_status` requests.
+    ///
+    /// The credential is generated from the initiator password on first use and reused until the
+    /// password changes. Call this method when your application starts to avoid generating it
+    /// on the request path, or provide a precomputed credential with
+    /// [`MpesaBuilder::security_credential`].
+    ///
+    /// # Errors
+    /// Returns a `MpesaError` if the credential cannot be generated
+    pub fn security_credential(&self) -> MpesaResult<String> {
+        if let Some(credential) = &self.initiator.read().unwrap().security_credential {
+            return Ok(credential.clone());
+        }
+
+        // Generated under the write lock so that a credential computed from a password that
+        // has since been replaced by `set_initiator_password` is never stored
+        let mut initiator = self.initiator.write().unwrap();
+        if let Some(credential) = &initiator.security_credential {
+            return Ok(credential.clone());
+        }
+        let credential = self.gen_security_credentials(&self.password_of(&initiator)?)?;
+        initiator.security_credential = Some(credential.clone());
+        Ok(credential)
+    }
+
     /// Generates security credentials
     /// M-Pesa Core authenticates a transaction by decrypting the security credentials.
     /// Security credentials are generated by encrypting the base64 encoded initiator password with M-Pesa’s public key, a X509 certificate.
@@ -273,71 +654,463 @@ impl Mpesa {
     ///
     /// # Errors
     /// Returns `EncryptionError` variant of `MpesaError`
-    pub(crate) fn gen_security_credentials(&self) -> MpesaResult<String> {
-        let pem = self.certificate.as_bytes();
-        let cert = X509::from_pem(pem)?;
-        // getting the public and rsa keys
-        let pub_key = cert.public_key()?;
-        let rsa_key = pub_key.rsa()?;
-        // configuring the buffer
-        let buf_len = pub_key.size();
-        let mut buffer = vec![0; buf_len];
-
-        rsa_key.public_encrypt(
-            self.initiator_password().as_bytes(),
-            &mut buffer,
-            Padding::PKCS1,
-        )?;
-        Ok(base64::encode_block(&buffer))
+    fn gen_security_credentials(&self, initiator_password: &str) -> MpesaResult<String> {
+        let encrypted = self
+            .certificate
+            .public_key()
+            .encrypt(initiator_password.as_bytes())?;
+        Ok(BASE64.encode(encrypted))
     }
 
     /// Sends a request to the Safaricom API
     /// This method is used by all the builders to send requests to the
     /// Safaricom API
+    ///
+    /// Idempotent requests are retried according to the client's `RetryPolicy`.
+    #[cfg(any(
+        feature = "account_balance",
+        feature = "b2b",
+        feature = "b2c",
+        feature = "bill_manager",
+        feature = "c2b_register",
+        feature = "c2b_simulate",
+        feature = "dynamic_qr",
+        feature = "express_query",
+        feature = "express_request",
+        feature = "transaction_reversal",
+        feature = "transaction_status"
+    ))]
     pub(crate) async fn send<Req, Res>(&self, req: Request<Req>) -> MpesaResult<Res>
+    where
+        Req: Serialize + Send,
+        Res: DeserializeOwned,
+    {
+        let result = if req.idempotent {
+            retry(
+                &self.retry_policy,
+                || self.send_once(&req),
+                |(_, code): &(Res, Option<MpesaResponseCode>)| {
+                    code.as_ref().is_some_and(MpesaResponseCode::is_retryable)
+                },
+            )
+            .await
+        } else {
+            self.send_once(&req).await.map_err(|failure| failure.error)
+        }
+        .map(|(response, _)| response);
+
+        if let Err(error) = &result {
+            for middleware in &self.middleware {
+                middleware.on_error(error).await;
+            }
+        }
+        result
+    }
+
+    /// Makes a single attempt at sending a request
+    ///
+    /// If M-Pesa rejects the access token, e.g. because it was revoked before its reported
+    /// expiry, the token is evicted from the `TokenStore` and the request is replayed once
+    /// with a new token.
+    ///
+    /// Successful responses are returned along with their `ResponseCode`, if they have one.
+    #[cfg(any(
+        feature = "account_balance",
+        feature = "b2b",
+        feature = "b2c",
+        feature = "bill_manager",
+        feature = "c2b_register",
+        feature = "c2b_simulate",
+        feature = "dynamic_qr",
+        feature = "express_query",
+        feature = "express_request",
+        feature = "transaction_reversal",
+        feature = "transaction_status"
+    ))]
+    async fn send_once<Req, Res>(
+        &self,
+        req: &Request<Req>,
+    ) -> Result<(Res, Option<MpesaResponseCode>), Failure>
     where
         Req: Serialize + Send,
         Res: DeserializeOwned,
     {
         let url = format!("{}/{}", self.base_url, req.path);
+        let mut replayed = false;
 
-        let req = self
-            .http_client
-            .request(req.method, url)
-            .bearer_auth(self.auth().await?)
-            .json(&req.body);
+        loop {
+            let _permit = self.rate_limits.acquire(req.path).await;
+            let request = self
+                .http_client
+                .request(req.method.clone(), &url)
+                .bearer_auth(self.auth().await?)
+                .json(&req.body)
+                .build()?;
+            let path = request.url().path().to_owned();
+            let res = self.execute(request).await?;
 
-        let res = req.send().await?;
+            let status = res.status();
+            let body = res.text().await?;
 
-        if res.status().is_success() {
-            let body = res.json().await?;
+            if status.is_success() {
+                let response = parse_body(status, &path, &body)?;
+                return Ok((response, response_code(&body)));
+            }
 
-            Ok(body)
-        } else {
-            let err = res.json::<crate::ResponseError>().await?;
+            let error = MpesaError::from_response(status, &path, &body);
+            let invalid_token = status == StatusCode::UNAUTHORIZED
+                || matches!(&error, MpesaError::Service(err) if err.is_invalid_access_token());
 
-            Err(crate::MpesaError::Service(err))
+            if invalid_token && !replayed {
+                replayed = true;
+                self.token_store.remove(&self.client_key).await?;
+                continue;
+            }
+
+            return Err(Failure {
+                status: Some(status),
+                error,
+            });
         }
     }
 }
 
+/// The `ResponseCode` of a successful response body, `None` if it has none
+#[cfg(any(
+    feature = "account_balance",
+    feature = "b2b",
+    feature = "b2c",
+    feature = "bill_manager",
+    feature = "c2b_register",
+    feature = "c2b_simulate",
+    feature = "dynamic_qr",
+    feature = "express_query",
+    feature = "express_request",
+    feature = "transaction_reversal",
+    feature = "transaction_status"
+))]
+fn response_code(body: &str) -> Option<MpesaResponseCode> {
+    #[derive(Deserialize)]
+    struct Body {
+        #[serde(rename = "ResponseCode")]
+        response_code: Option<MpesaResponseCode>,
+    }
+
+    serde_json::from_str::<Body>(body).ok()?.response_code
+}
+
+/// Deserializes the `body` of a successful response to the request to `path`
+///
+/// Bodies that are not JSON, e.g. HTML pages returned by a gateway, yield
+/// `MpesaError::UnexpectedResponse`, bodies that do not match `T` a `MpesaError::DecodeError`.
+pub(crate) fn parse_body<T: DeserializeOwned>(
+    status: StatusCode,
+    path: &str,
+    body: &str,
+) -> MpesaResult<T> {
+    serde_json::from_str(body).map_err(|source| {
+        let http = Box::new(HttpContext::new(status, path, body));
+        if source.is_syntax() || source.is_eof() {
+            MpesaError::UnexpectedResponse(http)
+        } else {
+            MpesaError::DecodeError { http, source }
+        }
+    })
+}
+
+/// Builder of a [`Mpesa`] client, created with [`Mpesa::builder`]
+///
+/// The credentials and environment are required, everything else is optional.
+#[derive(Debug, Default)]
+pub struct MpesaBuilder {
+    client_key: Option<String>,
+    client_secret: Option<Secret<String>>,
+    base_url: Option<String>,
+    certificate: Option<String>,
+    production: bool,
+    initiator: Initiator,
+    #[cfg(any(feature = "express_request", feature = "express_query"))]
+    pass_key: Option<Secret<String>>,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    user_agent: Option<String>,
+    proxy: Option<reqwest::Proxy>,
+    http_client: Option<HttpClient>,
+}
+
+impl MpesaBuilder {
+    /// Your app's consumer key. This is a required field
+    pub fn client_key<S: Into<String>>(mut self, client_key: S) -> Self {
+        self.client_key = Some(client_key.into());
+        self
+    }
+
+    /// Your app's consumer secret. This is a required field
+    pub fn client_secret<S: Into<String>>(mut self, client_secret: S) -> Self {
+        self.client_secret = Some(Secret::new(client_secret.into()));
+        self
+    }
+
+    /// The environment requests are sent to. This is a required field
+    pub fn environment(mut self, environment: impl ApiEnvironment) -> Self {
+        self.base_url = Some(environment.base_url().to_owned());
+        self.certificate = Some(environment.get_certificate().to_owned());
+        self.production = environment.is_production();
+        self
+    }
+
+    /// The initiator password, see [`Mpesa::set_initiator_password`]
+    pub fn initiator_password<S: Into<String>>(mut self, initiator_password: S) -> Self {
+        self.initiator.password = Some(Secret::new(initiator_password.into()));
+        self
+    }
+
+    /// A precomputed security credential, see [`Mpesa::security_credential`]. It is discarded
+    /// if the initiator password is changed with [`Mpesa::set_initiator_password`]
+    pub fn security_credential<S: Into<String>>(mut self, security_credential: S) -> Self {
+        self.initiator.security_credential = Some(security_credential.into());
+        self
+    }
+
+    /// The pass key used by `express_request` and `express_query` when none is set on the
+    /// request. Defaults to the key provided in Safaricom's [test credentials](https://developer.safaricom.co.ke/test_credentials)
+    #[cfg(any(feature = "express_request", feature = "express_query"))]
+    pub fn pass_key<S: Into<String>>(mut self, pass_key: S) -> Self {
+        self.pass_key = Some(Secret::new(pass_key.into()));
+        self
+    }
+
+    /// Timeout for establishing connections. Defaults to 10 seconds
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Timeout for whole requests, from connecting until the response body has been read.
+    /// No timeout by default
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// The `User-Agent` header sent with every request. Defaults to `mpesa-rust@<version>`
+    pub fn user_agent<S: Into<String>>(mut self, user_agent: S) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sends requests through a proxy
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Sends requests with a pre-built http client, e.g. to share its connection pool.
+    ///
+    /// The timeouts, user agent and proxy set on the builder are ignored, configure them on the
+    /// http client instead.
+    pub fn http_client(mut self, http_client: HttpClient) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// Builds the `Mpesa` client
+    ///
+    /// # Errors
+    /// Returns a `MpesaError` if a required field is missing, the environment's certificate is
+    /// invalid or the http client cannot be built
+    pub fn build(self) -> MpesaResult<Mpesa> {
+        let certificate = Certificate::from_pem(
+            self.certificate
+                .ok_or(MpesaError::Message("environment is required".into()))?,
+        )?;
+
+        let http_client = match self.http_client {
+            Some(http_client) => http_client,
+            None => {
+                let mut builder = HttpClient::builder()
+                    .connect_timeout(
+                        self.connect_timeout
+                            .unwrap_or(Duration::from_millis(10_000)),
+                    )
+                    .user_agent(
+                        self.user_agent
+                            .unwrap_or(format!("mpesa-rust@{CARGO_PACKAGE_VERSION}")),
+                    );
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(proxy) = self.proxy {
+                    builder = builder.proxy(proxy);
+                }
+                builder.build()?
+            }
+        };
+
+        Ok(Mpesa {
+            client_key: self
+                .client_key
+                .ok_or(MpesaError::Message("client_key is required".into()))?,
+            client_secret: self
+                .client_secret
+                .ok_or(MpesaError::Message("client_secret is required".into()))?,
+            initiator: Arc::new(RwLock::new(self.initiator)),
+            #[cfg(any(feature = "express_request", feature = "express_query"))]
+            pass_key: self.pass_key,
+            base_url: self
+                .base_url
+                .ok_or(MpesaError::Message("environment is required".into()))?,
+            certificate,
+            production: self.production,
+            http_client,
+            token_store: Arc::new(TokenCache::new()),
+            auth_lock: Arc::new(tokio::sync::Mutex::new(())),
+            retry_policy: RetryPolicy::none(),
+            #[cfg(any(
+                feature = "account_balance",
+                feature = "b2b",
+                feature = "b2c",
+                feature = "bill_manager",
+                feature = "c2b_register",
+                feature = "c2b_simulate",
+                feature = "dynamic_qr",
+                feature = "express_query",
+                feature = "express_request",
+                feature = "transaction_reversal",
+                feature = "transaction_status"
+            ))]
+            rate_limits: RateLimits::default(),
+            middleware: Vec::new(),
+        })
+    }
+}
+
+// `Mpesa` must stay shareable across threads
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Mpesa>();
+};
+
+#[cfg(any(
+    feature = "account_balance",
+    feature = "b2b",
+    feature = "b2c",
+    feature = "bill_manager",
+    feature = "c2b_register",
+    feature = "c2b_simulate",
+    feature = "dynamic_qr",
+    feature = "express_query",
+    feature = "express_request",
+    feature = "transaction_reversal",
+    feature = "transaction_status"
+))]
 pub struct Request<Body: Serialize + Send> {
     pub method: reqwest::Method,
     pub path: &'static str,
     pub body: Body,
+    /// Whether the request can safely be retried, i.e. it does not move money
+    pub idempotent: bool,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Sandbox;
+    use crate::{Production, Sandbox};
 
     #[test]
     fn test_setting_initator_password() {
         let client = Mpesa::new("client_key", "client_secret", Sandbox);
-        assert_eq!(client.initiator_password(), DEFAULT_INITIATOR_PASSWORD);
+        assert_eq!(
+            client.initiator_password().unwrap(),
+            DEFAULT_INITIATOR_PASSWORD
+        );
+        client.set_initiator_password("foo_bar");
+        assert_eq!(client.initiator_password().unwrap(), "foo_bar".to_string());
+    }
+
+    #[test]
+    fn test_clones_share_initiator_password() {
+        let client = Mpesa::new("client_key", "client_secret", Sandbox);
+        let clone = client.clone();
+        client.set_initiator_password("foo_bar");
+        assert_eq!(clone.initiator_password().unwrap(), "foo_bar".to_string());
+    }
+
+    #[test]
+    fn test_builder_requires_credentials_and_environment() {
+        let err = Mpesa::builder()
+            .client_secret("client_secret")
+            .environment(Sandbox)
+            .build()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            MpesaError::Message(msg) if msg == "client_key is required"
+        ));
+
+        let err = Mpesa::builder()
+            .client_key("client_key")
+            .environment(Sandbox)
+            .build()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            MpesaError::Message(msg) if msg == "client_secret is required"
+        ));
+
+        let err = Mpesa::builder()
+            .client_key("client_key")
+            .client_secret("client_secret")
+            .build()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            MpesaError::Message(msg) if msg == "environment is required"
+        ));
+    }
+
+    #[test]
+    fn test_builder_sets_initiator_password() {
+        let client = Mpesa::builder()
+            .client_key("client_key")
+            .client_secret("client_secret")
+            .environment(TestEnvironment)
+            .initiator_password("foo_bar")
+            .build()
+            .unwrap();
+        assert_eq!(client.initiator_password().unwrap(), "foo_bar");
+        assert_eq!(&client.base_url, "https://example.com");
+    }
+
+    #[cfg(any(feature = "express_request", feature = "express_query"))]
+    #[test]
+    fn test_builder_sets_pass_key() {
+        let client = Mpesa::builder()
+            .client_key("client_key")
+            .client_secret("client_secret")
+            .environment(TestEnvironment)
+            .pass_key("pass_key")
+            .build()
+            .unwrap();
+        assert_eq!(client.pass_key(), Some("pass_key"));
+
+        let client = Mpesa::new("client_key", "client_secret", Sandbox);
+        assert_eq!(client.pass_key(), None);
+    }
+
+    #[test]
+    fn test_production_requires_initiator_password() {
+        let client = Mpesa::new("client_key", "client_secret", Production);
+        let err = client.initiator_password().unwrap_err();
+        assert!(matches!(
+            err,
+            MpesaError::SandboxDefaultInProduction("initiator_password")
+        ));
+        assert!(client.security_credential().is_err());
+
         client.set_initiator_password("foo_bar");
-        assert_eq!(client.initiator_password(), "foo_bar".to_string());
+        assert_eq!(client.initiator_password().unwrap(), "foo_bar");
+        assert!(client.security_credential().is_ok());
     }
 
     #[derive(Clone)]
@@ -348,6 +1121,19 @@ mod tests {
             "https://example.com"
         }
 
+        fn get_certificate(&self) -> &str {
+            include_str!("../src/certificates/sandbox")
+        }
+    }
+
+    #[derive(Clone)]
+    struct InvalidCertificateEnvironment;
+
+    impl ApiEnvironment for InvalidCertificateEnvironment {
+        fn base_url(&self) -> &str {
+            "https://example.com"
+        }
+
         fn get_certificate(&self) -> &str {
             // not a valid pem
             "certificate"
@@ -358,13 +1144,85 @@ mod tests {
     fn test_custom_environment() {
         let client = Mpesa::new("client_key", "client_secret", TestEnvironment);
         assert_eq!(&client.base_url, "https://example.com");
-        assert_eq!(&client.certificate, "certificate");
+        assert_eq!(
+            client.certificate.as_pem(),
+            include_str!("../src/certificates/sandbox")
+        );
+    }
+
+    #[test]
+    fn test_invalid_pem_fails_at_construction() {
+        let err = Mpesa::builder()
+            .client_key("client_key")
+            .client_secret("client_secret")
+            .environment(InvalidCertificateEnvironment)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, MpesaError::CertificateError(_)));
     }
 
     #[test]
     #[should_panic]
-    fn test_gen_security_credentials_fails_with_invalid_pem() {
+    fn test_new_panics_with_invalid_pem() {
+        let _ = Mpesa::new("client_key", "client_secret", InvalidCertificateEnvironment);
+    }
+
+    #[test]
+    fn test_bundled_certificates_are_valid() {
+        for environment in [crate::Environment::Sandbox, crate::Environment::Production] {
+            assert!(Mpesa::builder()
+                .client_key("client_key")
+                .client_secret("client_secret")
+                .environment(environment)
+                .build()
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn test_security_credential_is_cached() {
         let client = Mpesa::new("client_key", "client_secret", TestEnvironment);
-        let _ = client.gen_security_credentials().unwrap();
+        let credential = client.security_credential().unwrap();
+        // PKCS1 padding is randomized, a new credential would differ
+        assert_eq!(client.security_credential().unwrap(), credential);
+        assert_eq!(client.clone().security_credential().unwrap(), credential);
+
+        client.set_initiator_password("foo_bar");
+        assert_ne!(client.security_credential().unwrap(), credential);
+    }
+
+    #[test]
+    fn test_precomputed_security_credential() {
+        let client = Mpesa::builder()
+            .client_key("client_key")
+            .client_secret("client_secret")
+            .environment(Production)
+            .security_credential("precomputed")
+            .build()
+            .unwrap();
+        assert_eq!(client.security_credential().unwrap(), "precomputed");
+    }
+
+    #[test]
+    fn test_debug_redacts_initiator() {
+        let builder = Mpesa::builder()
+            .client_key("client_key")
+            .client_secret("client_secret")
+            .environment(Sandbox)
+            .initiator_password("foo_bar")
+            .security_credential("precomputed");
+        let debug = format!("{builder:?}");
+        assert!(!debug.contains("foo_bar"));
+        assert!(!debug.contains("precomputed"));
+
+        let client = builder.build().unwrap();
+        client.set_initiator_password("baz_qux");
+        let credential = client.security_credential().unwrap();
+        let debug = format!("{client:?}");
+        assert!(debug.contains(
+            r#"Initiator { password: Some("[REDACTED]"), security_credential: Some("[REDACTED]") }"#
+        ));
+        assert!(!debug.contains("baz_qux"));
+        assert!(!debug.contains(&credential));
     }
 }
@@ -0,0 +1,346 @@
+//!# Client configuration
+//!
+//! [`MpesaConfig`] holds everything needed to create a `Mpesa` client, along with the short
+//! code and callback urls your application uses, so that they can be kept out of the code.
+//! It can be loaded from environment variables, a JSON file or, with the `toml` cargo feature,
+//! a TOML file.
+//!
+//! Only the credentials, environment, initiator password and pass key are used to build the
+//! client. The short code and callback urls are validated when the configuration is loaded but
+//! are **not** applied to requests, pass them to the request builders yourself.
+//!
+//! Files can either hold a single configuration or several named profiles, e.g. one for
+//! sandbox and one per production paybill:
+//!
+//! ```toml
+//! [profiles.sandbox]
+//! client_key = "..."
+//! client_secret = "..."
+//! environment = "sandbox"
+//!
+//! [profiles.paybill_a]
+//! client_key = "..."
+//! client_secret = "..."
+//! environment = "production"
+//! initiator_password = "..."
+//! pass_key = "..."
+//! short_code = "600496"
+//! result_url = "https://example.com/payments/result"
+//! queue_timeout_url = "https://example.com/payments/timeout"
+//! ```
+
+use std::collections::HashMap;
+use std::env::VarError;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
+
+use secrecy::{ExposeSecret, Secret};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::{Environment, Mpesa, MpesaBuilder, MpesaError, MpesaResult, ShortCode, Url};
+
+/// Configuration of a `Mpesa` client
+///
+/// | Field                | Environment variable       | Required |
+/// | -------------------- | -------------------------- | -------- |
+/// | `client_key`         | `MPESA_CLIENT_KEY`         | yes      |
+/// | `client_secret`      | `MPESA_CLIENT_SECRET`      | yes      |
+/// | `environment`        | `MPESA_ENVIRONMENT`        | yes      |
+/// | `initiator_password` | `MPESA_INITIATOR_PASSWORD` | no       |
+/// | `pass_key`           | `MPESA_PASSKEY`            | no       |
+/// | `short_code`         | `MPESA_SHORT_CODE`         | no       |
+/// | `callback_url`       | `MPESA_CALLBACK_URL`       | no       |
+/// | `result_url`         | `MPESA_RESULT_URL`         | no       |
+/// | `queue_timeout_url`  | `MPESA_QUEUE_TIMEOUT_URL`  | no       |
+/// | `confirmation_url`   | `MPESA_CONFIRMATION_URL`   | no       |
+/// | `validation_url`     | `MPESA_VALIDATION_URL`     | no       |
+///
+/// The short code must be 5 to 7 digits and the urls valid callback [`Url`]s, loading a
+/// configuration holding invalid values fails.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use mpesa::MpesaConfig;
+///
+/// let config = MpesaConfig::from_env().unwrap();
+/// let client = config.build().unwrap();
+///
+/// // The short code and callback urls are not applied by the client, pass them to the
+/// // request builders, e.g. `client.b2c(..).party_a(short_code).result_url(result_url)`
+/// let short_code = config.short_code.as_ref().map(|code| code.as_str());
+/// let result_url = config.result_url.as_ref().map(|url| url.as_str());
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct MpesaConfig {
+    pub client_key: String,
+    pub client_secret: Secret<String>,
+    pub environment: Environment,
+    pub initiator_password: Option<Secret<String>>,
+    pub pass_key: Option<Secret<String>>,
+    /// Short code, paybill or till number of the application
+    pub short_code: Option<ShortCode>,
+    /// Url receiving Mpesa Express/ STK push results
+    pub callback_url: Option<Url>,
+    /// Url receiving the results of asynchronous APIs, e.g. B2C
+    pub result_url: Option<Url>,
+    /// Url notified when asynchronous requests time out
+    pub queue_timeout_url: Option<Url>,
+    /// Url receiving C2B confirmations
+    pub confirmation_url: Option<Url>,
+    /// Url receiving C2B validation requests
+    pub validation_url: Option<Url>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Profiles {
+    profiles: HashMap<String, MpesaConfig>,
+}
+
+impl MpesaConfig {
+    /// Loads the configuration from `MPESA_*` environment variables
+    ///
+    /// # Errors
+    /// Returns an `EnvironmentalVariableError` if a required variable is missing and a
+    /// `BuilderError` if the short code or a url is invalid
+    pub fn from_env() -> MpesaResult<Self> {
+        Self::from_vars("MPESA_", |name| std::env::var(name))
+    }
+
+    /// Loads the configuration of a named profile from `MPESA_<PROFILE>_*` environment
+    /// variables, e.g. `MPESA_PAYBILL_A_CLIENT_KEY` for the `paybill_a` profile
+    ///
+    /// # Errors
+    /// Returns an `EnvironmentalVariableError` if a required variable is missing and a
+    /// `BuilderError` if the short code or a url is invalid
+    pub fn from_env_profile(profile: &str) -> MpesaResult<Self> {
+        let prefix = format!("MPESA_{}_", profile.to_uppercase().replace('-', "_"));
+        Self::from_vars(&prefix, |name| std::env::var(name))
+    }
+
+    fn from_vars<F>(prefix: &str, var: F) -> MpesaResult<Self>
+    where
+        F: Fn(&str) -> Result<String, VarError>,
+    {
+        let required = |name: &str| var(&format!("{prefix}{name}"));
+        let optional = |name: &str| match var(&format!("{prefix}{name}")) {
+            Ok(value) => Ok(Some(value)),
+            Err(VarError::NotPresent) => Ok(None),
+            Err(e) => Err(e),
+        };
+        let url = |name: &str| -> MpesaResult<Option<Url>> {
+            optional(name)?
+                .map(|url| Url::parse(&url).map_err(|e| e.for_field(&format!("{prefix}{name}"))))
+                .transpose()
+        };
+
+        Ok(MpesaConfig {
+            client_key: required("CLIENT_KEY")?,
+            client_secret: Secret::new(required("CLIENT_SECRET")?),
+            environment: required("ENVIRONMENT")?.try_into()?,
+            initiator_password: optional("INITIATOR_PASSWORD")?.map(Secret::new),
+            pass_key: optional("PASSKEY")?.map(Secret::new),
+            short_code: optional("SHORT_CODE")?
+                .map(|code| {
+                    ShortCode::parse(&code).map_err(|e| e.for_field(&format!("{prefix}SHORT_CODE")))
+                })
+                .transpose()?,
+            callback_url: url("CALLBACK_URL")?,
+            result_url: url("RESULT_URL")?,
+            queue_timeout_url: url("QUEUE_TIMEOUT_URL")?,
+            confirmation_url: url("CONFIRMATION_URL")?,
+            validation_url: url("VALIDATION_URL")?,
+        })
+    }
+
+    /// Parses a configuration from a JSON string
+    pub fn from_json(json: &str) -> MpesaResult<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Parses a configuration from a TOML string
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> MpesaResult<Self> {
+        toml::from_str(toml).map_err(|e| MpesaError::ConfigError(Box::new(e)))
+    }
+
+    /// Loads the configuration from a file, parsed as TOML if its extension is `.toml` and as
+    /// JSON otherwise
+    pub fn from_file<P: AsRef<Path>>(path: P) -> MpesaResult<Self> {
+        read_file(path.as_ref())
+    }
+
+    /// Loads a named profile from a file holding several profiles under a `profiles` key
+    ///
+    /// # Errors
+    /// Returns a `ConfigError` if the file cannot be read or does not hold the profile
+    pub fn from_file_profile<P: AsRef<Path>>(path: P, profile: &str) -> MpesaResult<Self> {
+        read_file::<Profiles>(path.as_ref())?
+            .profiles
+            .remove(profile)
+            .ok_or_else(|| MpesaError::ConfigError(format!("profile `{profile}` not found").into()))
+    }
+
+    /// Creates a [`MpesaBuilder`] with the credentials, environment, initiator password and
+    /// pass key of the configuration, to further configure the client
+    ///
+    /// The short code and callback urls are not passed to the builder.
+    pub fn builder(&self) -> MpesaBuilder {
+        let mut builder = Mpesa::builder()
+            .client_key(self.client_key.as_str())
+            .client_secret(self.client_secret.expose_secret().as_str())
+            .environment(self.environment.clone());
+
+        if let Some(initiator_password) = &self.initiator_password {
+            builder = builder.initiator_password(initiator_password.expose_secret().as_str());
+        }
+        #[cfg(any(feature = "express_request", feature = "express_query"))]
+        if let Some(pass_key) = &self.pass_key {
+            builder = builder.pass_key(pass_key.expose_secret().as_str());
+        }
+        builder
+    }
+
+    /// Creates a `Mpesa` client from the configuration
+    pub fn build(&self) -> MpesaResult<Mpesa> {
+        self.builder().build()
+    }
+}
+
+fn read_file<T: DeserializeOwned>(path: &Path) -> MpesaResult<T> {
+    let contents = fs::read_to_string(path).map_err(|e| MpesaError::ConfigError(Box::new(e)))?;
+
+    if path.extension() == Some(OsStr::new("toml")) {
+        #[cfg(feature = "toml")]
+        return toml::from_str(&contents).map_err(|e| MpesaError::ConfigError(Box::new(e)));
+        #[cfg(not(feature = "toml"))]
+        return Err(MpesaError::ConfigError(
+            "TOML files require the `toml` cargo feature".into(),
+        ));
+    }
+
+    Ok(serde_json::from_str(&contents)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(vars: &[(&str, &str)]) -> impl Fn(&str) -> Result<String, VarError> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned().ok_or(VarError::NotPresent)
+    }
+
+    #[test]
+    fn test_config_from_vars() {
+        let config = MpesaConfig::from_vars(
+            "MPESA_",
+            vars(&[
+                ("MPESA_CLIENT_KEY", "client_key"),
+                ("MPESA_CLIENT_SECRET", "client_secret"),
+                ("MPESA_ENVIRONMENT", "Production"),
+                ("MPESA_PASSKEY", "pass_key"),
+                ("MPESA_SHORT_CODE", "600496"),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(config.client_key, "client_key");
+        assert_eq!(config.client_secret.expose_secret(), "client_secret");
+        assert_eq!(config.environment, Environment::Production);
+        assert_eq!(config.pass_key.unwrap().expose_secret(), "pass_key");
+        assert_eq!(config.short_code.unwrap(), "600496");
+        assert!(config.initiator_password.is_none());
+        assert!(config.result_url.is_none());
+    }
+
+    #[test]
+    fn test_config_from_vars_requires_credentials() {
+        let err = MpesaConfig::from_vars(
+            "MPESA_",
+            vars(&[
+                ("MPESA_CLIENT_KEY", "client_key"),
+                ("MPESA_ENVIRONMENT", "sandbox"),
+            ]),
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            err,
+            MpesaError::EnvironmentalVariableError(VarError::NotPresent)
+        ));
+    }
+
+    #[test]
+    fn test_config_from_json() {
+        let config = MpesaConfig::from_json(
+            r#"{
+                "client_key": "client_key",
+                "client_secret": "client_secret",
+                "environment": "sandbox",
+                "initiator_password": "initiator_password",
+                "result_url": "https://example.com/result"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.environment, Environment::Sandbox);
+        assert_eq!(
+            config.result_url.as_ref().unwrap().as_str(),
+            "https://example.com/result"
+        );
+
+        let client = config.build().unwrap();
+        assert_eq!(client.initiator_password().unwrap(), "initiator_password");
+    }
+
+    #[test]
+    fn test_config_validates_short_code_and_urls() {
+        let err = MpesaConfig::from_vars(
+            "MPESA_",
+            vars(&[
+                ("MPESA_CLIENT_KEY", "client_key"),
+                ("MPESA_CLIENT_SECRET", "client_secret"),
+                ("MPESA_ENVIRONMENT", "sandbox"),
+                ("MPESA_RESULT_URL", "https://example.com/mpesa/result"),
+            ]),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("MPESA_RESULT_URL is invalid"));
+
+        for json in [
+            r#"{"client_key": "key", "client_secret": "secret", "environment": "sandbox", "short_code": "12"}"#,
+            r#"{"client_key": "key", "client_secret": "secret", "environment": "sandbox", "callback_url": "/callback"}"#,
+        ] {
+            assert!(matches!(
+                MpesaConfig::from_json(json),
+                Err(MpesaError::ParseError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_config_rejects_unknown_environment() {
+        let err = MpesaConfig::from_json(
+            r#"{"client_key": "key", "client_secret": "secret", "environment": "staging"}"#,
+        )
+        .unwrap_err();
+
+        assert!(matches!(err, MpesaError::ParseError(_)));
+    }
+
+    #[test]
+    fn test_config_debug_redacts_secrets() {
+        let config = MpesaConfig::from_json(
+            r#"{"client_key": "key", "client_secret": "secret", "environment": "sandbox"}"#,
+        )
+        .unwrap();
+
+        assert!(!format!("{config:?}").contains("\"secret\""));
+    }
+}
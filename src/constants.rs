@@ -1,10 +1,31 @@
 use std::fmt::{Display, Formatter, Result as FmtResult};
 
+#[cfg(feature = "bill_manager")]
 use chrono::prelude::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
+use crate::callbacks::C2bValidationResultCode;
+#[cfg(feature = "bill_manager")]
+use crate::Amount;
 use crate::MpesaError;
+#[cfg(any(
+    feature = "account_balance",
+    feature = "b2b",
+    feature = "bill_manager",
+    feature = "dynamic_qr",
+    feature = "transaction_reversal",
+    feature = "transaction_status"
+))]
+use crate::Msisdn;
+#[cfg(any(
+    feature = "account_balance",
+    feature = "b2b",
+    feature = "dynamic_qr",
+    feature = "transaction_reversal",
+    feature = "transaction_status"
+))]
+use crate::{MpesaResult, ShortCode, TillNumber};
 
 /// Mpesa command ids
 #[derive(Debug, Serialize, Deserialize)]
@@ -42,40 +63,242 @@ pub enum IdentifierTypes {
     Reversal = 11,
 }
 
+impl IdentifierTypes {
+    /// Validates the `party` identified by this type, returning it normalized
+    #[cfg(any(
+        feature = "account_balance",
+        feature = "b2b",
+        feature = "transaction_reversal",
+        feature = "transaction_status"
+    ))]
+    pub(crate) fn validate_party(self, field: &str, party: &str) -> MpesaResult<String> {
+        match self {
+            IdentifierTypes::MSISDN => Msisdn::parse(party).map(String::from),
+            IdentifierTypes::TillNumber => TillNumber::parse(party).map(String::from),
+            IdentifierTypes::ShortCode | IdentifierTypes::Reversal => {
+                ShortCode::parse(party).map(String::from)
+            }
+        }
+        .map_err(|e| e.for_field(field))
+    }
+}
+
 impl Display for IdentifierTypes {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         write!(f, "{:?}", *self as u16)
     }
 }
 
-/// TODO: Enable deserializing of json numbers/ strings to `MpesaResponseCode`
-/// M-pesa result and response codes
-#[derive(Debug, Copy, Clone, Deserialize_repr)]
-#[repr(u16)]
-#[allow(unused)]
-pub enum MpesaResponseCode {
-    Success = 0,
-    InsufficientFunds = 1,
-    LessThanMinimum = 2,
-    MoreThanMaximum = 3,
-    ExceededDailyLimit = 4,
-    ExceededMinimumBalance = 5,
-    UnresolvedPrimaryParty = 6,
-    UnresolvedReceiverParty = 7,
-    ExceededMaximumBalance = 8,
-    InvalidDebitAccount = 11,
-    InvalidCreditAccount = 12,
-    UnresolvedDebitAccount = 13,
-    UnresolvedCreditAccount = 14,
-    DuplicateDetected = 15,
-    InternalFailure = 17,
-    UnresolvedInitiator = 20,
-    TrafficBlocking = 26,
+const C2B_VALIDATION_CODES: [C2bValidationResultCode; 6] = [
+    C2bValidationResultCode::InvalidMsisdn,
+    C2bValidationResultCode::InvalidAccountNumber,
+    C2bValidationResultCode::InvalidAmount,
+    C2bValidationResultCode::InvalidKycDetails,
+    C2bValidationResultCode::InvalidShortcode,
+    C2bValidationResultCode::OtherError,
+];
+
+macro_rules! response_codes {
+    ($($(#[$meta:meta])* $variant:ident = $code:literal,)*) => {
+        /// M-Pesa response and result codes
+        ///
+        /// Returned in the `ResponseCode` of the acknowledgement of a request and the `ResultCode`
+        /// of its callback. Codes are deserialized from JSON numbers or strings, codes this crate
+        /// does not know about are kept as `Unknown`.
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// use mpesa::MpesaResponseCode;
+        ///
+        /// let code: MpesaResponseCode = serde_json::from_str("1032").unwrap();
+        /// assert_eq!(code, MpesaResponseCode::CancelledByUser);
+        /// assert_eq!(code, "1032");
+        /// assert!(!code.is_success());
+        ///
+        /// let code: MpesaResponseCode = serde_json::from_str(r#""1037""#).unwrap();
+        /// assert!(code.is_transient_callback_result());
+        ///
+        /// let code: MpesaResponseCode = serde_json::from_str("26").unwrap();
+        /// assert!(code.is_retryable());
+        ///
+        /// let code: MpesaResponseCode = serde_json::from_str(r#""SFC_IC0003""#).unwrap();
+        /// assert_eq!(code, MpesaResponseCode::Unknown("SFC_IC0003".to_owned()));
+        /// ```
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum MpesaResponseCode {
+            $(
+                $(#[$meta])*
+                #[doc = concat!("`", $code, "`")]
+                $variant,
+            )*
+            /// A code returned to reject a C2B payment, see [`ValidationResponse`](crate::callbacks::ValidationResponse)
+            C2bValidation(C2bValidationResultCode),
+            /// A code this crate does not know about, as sent by M-Pesa
+            Unknown(String),
+        }
+
+        impl MpesaResponseCode {
+            /// The code as sent by M-Pesa
+            pub fn code(&self) -> &str {
+                match self {
+                    $(MpesaResponseCode::$variant => $code,)*
+                    MpesaResponseCode::C2bValidation(code) => code.code(),
+                    MpesaResponseCode::Unknown(code) => code,
+                }
+            }
+
+            /// Looks up a code, numeric codes are matched by value, e.g. `"00"` is `Success`
+            fn from_code(code: &str) -> Self {
+                let code = code.trim();
+                let canonical = match code.parse::<u64>() {
+                    Ok(n) if code.bytes().all(|b| b.is_ascii_digit()) => n.to_string(),
+                    _ => code.to_owned(),
+                };
+                match canonical.as_str() {
+                    $($code => MpesaResponseCode::$variant,)*
+                    _ => C2B_VALIDATION_CODES
+                        .into_iter()
+                        .find(|c2b_code| c2b_code.code() == canonical)
+                        .map_or_else(
+                            || MpesaResponseCode::Unknown(code.to_owned()),
+                            MpesaResponseCode::C2bValidation,
+                        ),
+                }
+            }
+        }
+    };
+}
+
+response_codes! {
+    /// The request was accepted, or the transaction completed successfully
+    Success = "0",
+    /// Insufficient funds, also returned when the customer's balance is insufficient for an
+    /// STK push
+    InsufficientFunds = "1",
+    LessThanMinimum = "2",
+    MoreThanMaximum = "3",
+    ExceededDailyLimit = "4",
+    ExceededMinimumBalance = "5",
+    UnresolvedPrimaryParty = "6",
+    UnresolvedReceiverParty = "7",
+    ExceededMaximumBalance = "8",
+    InvalidDebitAccount = "11",
+    InvalidCreditAccount = "12",
+    UnresolvedDebitAccount = "13",
+    UnresolvedCreditAccount = "14",
+    DuplicateDetected = "15",
+    InternalFailure = "17",
+    InitiatorCredentialCheckFailure = "18",
+    MessageSequencingFailure = "19",
+    UnresolvedInitiator = "20",
+    InitiatorToPrimaryPartyPermissionFailure = "21",
+    InitiatorToReceiverPartyPermissionFailure = "22",
+    RequestSchemaValidationError = "23",
+    MissingMandatoryFields = "24",
+    InvalidRequestParameters = "25",
+    TrafficBlocking = "26",
+    /// The customer has another transaction in progress
+    SubscriberLocked = "1001",
+    /// The STK push expired before the customer responded
+    TransactionExpired = "1019",
+    /// The STK push could not be sent to the customer
+    PushRequestError = "1025",
+    /// The customer cancelled the STK push
+    CancelledByUser = "1032",
+    /// The customer could not be reached, e.g. their phone is off
+    SubscriberUnreachable = "1037",
+    /// The initiator information is invalid, e.g. the customer entered a wrong PIN
+    InvalidInitiatorInformation = "2001",
+    /// The request is not permitted by the products assigned to the short code
+    ProductNotPermitted = "2028",
+    /// The credit party is not a registered M-Pesa customer
+    UnregisteredCreditParty = "2040",
+    /// The initiator's security credential is locked
+    SecurityCredentialLocked = "8006",
+    /// The STK push could not be sent, an error occurred on M-Pesa's side
+    PushRequestFailed = "9999",
+    /// The Bill Manager request was accepted, Bill Manager APIs use `200` rather than `0`
+    BillManagerSuccess = "200",
+}
+
+impl MpesaResponseCode {
+    /// Returns `true` if the request was accepted, or the transaction completed successfully
+    pub fn is_success(&self) -> bool {
+        matches!(
+            self,
+            MpesaResponseCode::Success | MpesaResponseCode::BillManagerSuccess
+        )
+    }
+
+    /// Returns `true` if a synchronous response reports a transient failure and sending the
+    /// request again later may succeed, e.g. M-Pesa is throttling requests
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            MpesaResponseCode::InternalFailure | MpesaResponseCode::TrafficBlocking
+        )
+    }
+
+    /// Returns `true` if the result of an asynchronous transaction, only sent in callbacks,
+    /// reports a transient failure and initiating a new transaction may succeed, e.g. the
+    /// customer could not be reached
+    pub fn is_transient_callback_result(&self) -> bool {
+        matches!(
+            self,
+            MpesaResponseCode::SubscriberLocked
+                | MpesaResponseCode::TransactionExpired
+                | MpesaResponseCode::PushRequestError
+                | MpesaResponseCode::SubscriberUnreachable
+                | MpesaResponseCode::PushRequestFailed
+        )
+    }
 }
 
 impl Display for MpesaResponseCode {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "{:?}", *self as u16)
+        f.write_str(self.code())
+    }
+}
+
+impl PartialEq<&str> for MpesaResponseCode {
+    fn eq(&self, other: &&str) -> bool {
+        self.code() == *other
+    }
+}
+
+impl From<&str> for MpesaResponseCode {
+    fn from(code: &str) -> Self {
+        MpesaResponseCode::from_code(code)
+    }
+}
+
+impl From<C2bValidationResultCode> for MpesaResponseCode {
+    fn from(code: C2bValidationResultCode) -> Self {
+        MpesaResponseCode::C2bValidation(code)
+    }
+}
+
+impl Serialize for MpesaResponseCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for MpesaResponseCode {
+    /// Deserializes a JSON number or string holding a code
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Number(i64),
+            String(String),
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Number(n) => MpesaResponseCode::from_code(&n.to_string()),
+            Repr::String(s) => MpesaResponseCode::from_code(&s),
+        })
     }
 }
 
@@ -105,14 +328,17 @@ impl Display for SendRemindersTypes {
     }
 }
 
+#[cfg(feature = "bill_manager")]
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Invoice<'i> {
-    pub amount: f64,
+    pub amount: Amount,
     pub account_reference: &'i str,
     pub billed_full_name: &'i str,
     pub billed_period: &'i str,
-    pub billed_phone_number: &'i str,
+    /// Sent in the local `07XXXXXXXX` format
+    #[serde(serialize_with = "Msisdn::serialize_local")]
+    pub billed_phone_number: Msisdn,
     pub due_date: DateTime<Utc>,
     pub external_reference: &'i str,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -120,6 +346,7 @@ pub struct Invoice<'i> {
     pub invoice_name: &'i str,
 }
 
+#[cfg(feature = "bill_manager")]
 impl<'i> Display for Invoice<'i> {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         write!(
@@ -133,12 +360,14 @@ impl<'i> Display for Invoice<'i> {
     }
 }
 
+#[cfg(feature = "bill_manager")]
 #[derive(Debug, Serialize)]
 pub struct InvoiceItem<'i> {
-    pub amount: f64,
+    pub amount: Amount,
     pub item_name: &'i str,
 }
 
+#[cfg(feature = "bill_manager")]
 impl<'i> Display for InvoiceItem<'i> {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         write!(f, "amount: {}, item_name: {}", self.amount, self.item_name)
@@ -159,6 +388,23 @@ pub enum TransactionType {
     SendBusiness,
 }
 
+impl TransactionType {
+    /// Validates the credit party identifier, the party receiving a payment of this type
+    #[cfg(feature = "dynamic_qr")]
+    pub(crate) fn validate_credit_party(self, credit_party_identifier: &str) -> MpesaResult<()> {
+        match self {
+            TransactionType::SendMoney | TransactionType::SendBusiness => {
+                Msisdn::parse(credit_party_identifier).map(drop)
+            }
+            TransactionType::Withdraw | TransactionType::BG => {
+                TillNumber::parse(credit_party_identifier).map(drop)
+            }
+            TransactionType::PayBill => ShortCode::parse(credit_party_identifier).map(drop),
+        }
+        .map_err(|e| e.for_field("credit_party_identifier"))
+    }
+}
+
 impl Display for TransactionType {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         write!(f, "{self:?}")
@@ -175,7 +421,87 @@ impl TryFrom<&str> for TransactionType {
             "pb" => Ok(TransactionType::PayBill),
             "sm" => Ok(TransactionType::SendMoney),
             "sb" => Ok(TransactionType::SendBusiness),
-            _ => Err(MpesaError::Message("Invalid transaction type")),
+            _ => Err(MpesaError::Message("Invalid transaction type".into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_response_code() {
+        for json in ["0", "\"0\"", "\"00\"", "\" 0 \""] {
+            assert_eq!(
+                serde_json::from_str::<MpesaResponseCode>(json).unwrap(),
+                MpesaResponseCode::Success
+            );
+        }
+        for (json, code) in [
+            ("1032", MpesaResponseCode::CancelledByUser),
+            ("\"1037\"", MpesaResponseCode::SubscriberUnreachable),
+            ("2001", MpesaResponseCode::InvalidInitiatorInformation),
+            ("17", MpesaResponseCode::InternalFailure),
+            (
+                "\"C2B00012\"",
+                MpesaResponseCode::C2bValidation(C2bValidationResultCode::InvalidAccountNumber),
+            ),
+            ("4242", MpesaResponseCode::Unknown("4242".to_owned())),
+            (
+                "\"SFC_IC0003\"",
+                MpesaResponseCode::Unknown("SFC_IC0003".to_owned()),
+            ),
+        ] {
+            assert_eq!(
+                serde_json::from_str::<MpesaResponseCode>(json).unwrap(),
+                code
+            );
+        }
+        assert!(serde_json::from_str::<MpesaResponseCode>("null").is_err());
+    }
+
+    #[test]
+    fn test_serialize_response_code() {
+        assert_eq!(
+            serde_json::to_string(&MpesaResponseCode::CancelledByUser).unwrap(),
+            "\"1032\""
+        );
+        assert_eq!(
+            serde_json::to_string(&MpesaResponseCode::from(
+                C2bValidationResultCode::OtherError
+            ))
+            .unwrap(),
+            "\"C2B00016\""
+        );
+        assert_eq!(MpesaResponseCode::from("4242").to_string(), "4242");
+    }
+
+    #[test]
+    fn test_response_code_helpers() {
+        assert!(MpesaResponseCode::Success.is_success());
+        assert!(!MpesaResponseCode::Success.is_retryable());
+        assert!(MpesaResponseCode::from("200").is_success());
+        for code in ["26", "17"] {
+            let code = MpesaResponseCode::from(code);
+            assert!(
+                code.is_retryable() && !code.is_transient_callback_result(),
+                "{code}"
+            );
+        }
+        for code in ["1037", "1019", "1001", "1025", "9999"] {
+            let code = MpesaResponseCode::from(code);
+            assert!(
+                code.is_transient_callback_result() && !code.is_retryable(),
+                "{code}"
+            );
+        }
+        for code in ["1", "1032", "2001", "C2B00011", "4242"] {
+            let code = MpesaResponseCode::from(code);
+            assert!(
+                !code.is_success() && !code.is_retryable() && !code.is_transient_callback_result(),
+                "{code}"
+            );
         }
     }
 }
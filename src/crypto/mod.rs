@@ -0,0 +1,102 @@
+//! Crypto backends used to parse M-Pesa's certificates and generate security credentials.
+//!
+//! The backend is selected by cargo feature: `openssl` (the default) or `rustls`, which only
+//! depends on pure Rust crates. OpenSSL is used if both features are enabled.
+
+use std::time::SystemTime;
+
+#[cfg(not(any(feature = "openssl", feature = "rustls")))]
+compile_error!("a crypto backend is required, enable either the `openssl` or `rustls` feature");
+
+#[cfg(feature = "openssl")]
+mod openssl;
+#[cfg(feature = "rustls")]
+#[cfg_attr(feature = "openssl", allow(dead_code))]
+mod rustcrypto;
+
+#[cfg(feature = "openssl")]
+pub(crate) use self::openssl::{parse_der, parse_pem, PublicKey};
+#[cfg(all(feature = "rustls", not(feature = "openssl")))]
+pub(crate) use self::rustcrypto::{parse_der, parse_pem, PublicKey};
+
+/// A X509 certificate parsed by the crypto backend
+#[derive(Debug)]
+pub(crate) struct ParsedCertificate<K = PublicKey> {
+    pub(crate) pem: String,
+    pub(crate) public_key: K,
+    pub(crate) expires_at: SystemTime,
+    /// SHA-256 digest of the DER encoded certificate
+    pub(crate) fingerprint: Vec<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERTIFICATE: &str = include_str!("../../tests/fixtures/test");
+    const PRIVATE_KEY: &str = include_str!("../../tests/fixtures/test.key");
+    const PASSWORD: &[u8] = b"Safcom496!";
+
+    #[cfg(feature = "openssl")]
+    fn decrypt(encrypted: &[u8]) -> Vec<u8> {
+        use ::openssl::pkey::PKey;
+        use ::openssl::rsa::Padding;
+
+        let key = PKey::private_key_from_pem(PRIVATE_KEY.as_bytes())
+            .unwrap()
+            .rsa()
+            .unwrap();
+        let mut buffer = vec![0; key.size() as usize];
+        let len = key
+            .private_decrypt(encrypted, &mut buffer, Padding::PKCS1)
+            .unwrap();
+        buffer.truncate(len);
+        buffer
+    }
+
+    #[cfg(not(feature = "openssl"))]
+    fn decrypt(encrypted: &[u8]) -> Vec<u8> {
+        use rsa::pkcs8::DecodePrivateKey;
+        use rsa::{Pkcs1v15Encrypt, RsaPrivateKey};
+
+        RsaPrivateKey::from_pkcs8_pem(PRIVATE_KEY)
+            .unwrap()
+            .decrypt(Pkcs1v15Encrypt, encrypted)
+            .unwrap()
+    }
+
+    fn assert_certificate<K>(certificate: &ParsedCertificate<K>) {
+        assert_eq!(
+            certificate
+                .expires_at
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            // Sep 21 17:34:19 2126 GMT
+            4_945_685_659
+        );
+        assert_eq!(certificate.fingerprint[..4], [0x93, 0x97, 0xF8, 0x62]);
+    }
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    fn test_openssl_backend() {
+        let certificate = self::openssl::parse_pem(CERTIFICATE).unwrap();
+        assert_certificate(&certificate);
+
+        let encrypted = certificate.public_key.encrypt(PASSWORD).unwrap();
+        assert_eq!(encrypted.len(), 256);
+        assert_eq!(decrypt(&encrypted), PASSWORD);
+    }
+
+    #[cfg(feature = "rustls")]
+    #[test]
+    fn test_rustcrypto_backend() {
+        let certificate = rustcrypto::parse_pem(CERTIFICATE).unwrap();
+        assert_certificate(&certificate);
+
+        let encrypted = certificate.public_key.encrypt(PASSWORD).unwrap();
+        assert_eq!(encrypted.len(), 256);
+        assert_eq!(decrypt(&encrypted), PASSWORD);
+    }
+}
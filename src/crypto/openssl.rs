@@ -0,0 +1,67 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use openssl::asn1::Asn1Time;
+use openssl::error::ErrorStack;
+use openssl::hash::MessageDigest;
+use openssl::pkey::Public;
+use openssl::rsa::{Padding, Rsa};
+use openssl::x509::X509;
+
+use super::ParsedCertificate;
+use crate::{MpesaError, MpesaResult};
+
+/// RSA public key used to encrypt initiator passwords
+#[derive(Debug, Clone)]
+pub(crate) struct PublicKey(Rsa<Public>);
+
+impl PublicKey {
+    /// Encrypts `data` with PKCS #1 v1.5 padding
+    pub(crate) fn encrypt(&self, data: &[u8]) -> MpesaResult<Vec<u8>> {
+        let mut buffer = vec![0; self.0.size() as usize];
+        let len = self
+            .0
+            .public_encrypt(data, &mut buffer, Padding::PKCS1)
+            .map_err(|e| MpesaError::EncryptionError(Box::new(e)))?;
+        buffer.truncate(len);
+        Ok(buffer)
+    }
+}
+
+pub(crate) fn parse_pem(pem: &str) -> MpesaResult<ParsedCertificate<PublicKey>> {
+    let cert = X509::from_pem(pem.as_bytes()).map_err(certificate_error)?;
+    parse(&cert, pem.to_owned())
+}
+
+pub(crate) fn parse_der(der: &[u8]) -> MpesaResult<ParsedCertificate<PublicKey>> {
+    let cert = X509::from_der(der).map_err(certificate_error)?;
+    let pem = cert.to_pem().map_err(certificate_error)?;
+    parse(&cert, String::from_utf8_lossy(&pem).into_owned())
+}
+
+fn parse(cert: &X509, pem: String) -> MpesaResult<ParsedCertificate<PublicKey>> {
+    let public_key = cert
+        .public_key()
+        .and_then(|key| key.rsa())
+        .map_err(certificate_error)?;
+
+    let epoch = Asn1Time::from_unix(0).map_err(certificate_error)?;
+    let diff = epoch.diff(cert.not_after()).map_err(certificate_error)?;
+    let seconds = i64::from(diff.days) * 86_400 + i64::from(diff.secs);
+    let expires_at = UNIX_EPOCH + Duration::from_secs(seconds.max(0) as u64);
+
+    let fingerprint = cert
+        .digest(MessageDigest::sha256())
+        .map_err(certificate_error)?
+        .to_vec();
+
+    Ok(ParsedCertificate {
+        pem,
+        public_key: PublicKey(public_key),
+        expires_at,
+        fingerprint,
+    })
+}
+
+fn certificate_error(e: ErrorStack) -> MpesaError {
+    MpesaError::CertificateError(Box::new(e))
+}
@@ -0,0 +1,61 @@
+use std::time::SystemTime;
+
+use rsa::pkcs8::DecodePublicKey;
+use rsa::{Pkcs1v15Encrypt, RsaPublicKey};
+use sha2::{Digest, Sha256};
+use x509_cert::der::pem::LineEnding;
+use x509_cert::der::{Decode, DecodePem, Encode, EncodePem};
+use x509_cert::Certificate;
+
+use super::ParsedCertificate;
+use crate::{MpesaError, MpesaResult};
+
+/// RSA public key used to encrypt initiator passwords
+#[derive(Debug, Clone)]
+pub(crate) struct PublicKey(RsaPublicKey);
+
+impl PublicKey {
+    /// Encrypts `data` with PKCS #1 v1.5 padding
+    pub(crate) fn encrypt(&self, data: &[u8]) -> MpesaResult<Vec<u8>> {
+        self.0
+            .encrypt(&mut rand::thread_rng(), Pkcs1v15Encrypt, data)
+            .map_err(|e| MpesaError::EncryptionError(Box::new(e)))
+    }
+}
+
+pub(crate) fn parse_pem(pem: &str) -> MpesaResult<ParsedCertificate<PublicKey>> {
+    let cert = Certificate::from_pem(pem).map_err(certificate_error)?;
+    parse(&cert, pem.to_owned())
+}
+
+pub(crate) fn parse_der(der: &[u8]) -> MpesaResult<ParsedCertificate<PublicKey>> {
+    let cert = Certificate::from_der(der).map_err(certificate_error)?;
+    let pem = cert.to_pem(LineEnding::LF).map_err(certificate_error)?;
+    parse(&cert, pem)
+}
+
+fn parse(cert: &Certificate, pem: String) -> MpesaResult<ParsedCertificate<PublicKey>> {
+    let spki = cert
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(certificate_error)?;
+    let public_key = RsaPublicKey::from_public_key_der(&spki)
+        .map_err(|e| MpesaError::CertificateError(Box::new(e)))?;
+
+    let expires_at: SystemTime = cert.tbs_certificate.validity.not_after.to_system_time();
+
+    let der = cert.to_der().map_err(certificate_error)?;
+    let fingerprint = Sha256::digest(der).to_vec();
+
+    Ok(ParsedCertificate {
+        pem,
+        public_key: PublicKey(public_key),
+        expires_at,
+        fingerprint,
+    })
+}
+
+fn certificate_error(e: x509_cert::der::Error) -> MpesaError {
+    MpesaError::CertificateError(Box::new(e))
+}
@@ -9,13 +9,22 @@
 //! Based on selected environment. You are able to access environment specific data such as the `base_url`
 //! and the `public key` an X509 certificate used for encrypting initiator passwords. You can read more about that from
 //! the Safaricom API [docs](https://developer.safaricom.co.ke/docs?javascript#security-credentials).
+//!
+//! The certificates of `Environment` are embedded in the crate. Use a [`RuntimeEnvironment`] to
+//! provide a certificate loaded at runtime instead, e.g. after Safaricom rotates its public key.
 
 use std::convert::TryFrom;
+use std::path::Path;
 use std::str::FromStr;
+use std::time::SystemTime;
 
-use crate::MpesaError;
+use serde::Deserialize;
 
-#[derive(Debug, Clone)]
+use crate::crypto::{self, ParsedCertificate, PublicKey};
+use crate::{MpesaError, MpesaResult};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
 /// Enum to map to desired environment so as to access certificate
 /// and the base url
 /// Required to construct a new `Mpesa` struct
@@ -31,8 +40,19 @@ pub enum Environment {
 pub trait ApiEnvironment: Clone {
     fn base_url(&self) -> &str;
     fn get_certificate(&self) -> &str;
+
+    /// Whether the environment targets the production API, in which case the client refuses to
+    /// fall back to the sandbox initiator password and pass key.
+    ///
+    /// Defaults to `true` if `base_url` is Safaricom's production API. Override it to opt in,
+    /// e.g. when going through a proxy, or out of the check.
+    fn is_production(&self) -> bool {
+        self.base_url() == PRODUCTION_BASE_URL
+    }
 }
 
+const PRODUCTION_BASE_URL: &str = "https://api.safaricom.co.ke";
+
 impl FromStr for Environment {
     type Err = MpesaError;
 
@@ -50,7 +70,7 @@ impl TryFrom<&str> for Environment {
             "production" => Ok(Self::Production),
             "sandbox" => Ok(Self::Sandbox),
             _ => Err(MpesaError::Message(
-                "Could not parse the provided environment name",
+                "Could not parse the provided environment name".into(),
             )),
         }
     }
@@ -68,7 +88,7 @@ impl ApiEnvironment for Environment {
     /// Matches to base_url based on `Environment` variant
     fn base_url(&self) -> &str {
         match self {
-            Environment::Production => "https://api.safaricom.co.ke",
+            Environment::Production => PRODUCTION_BASE_URL,
             Environment::Sandbox => "https://sandbox.safaricom.co.ke",
         }
     }
@@ -80,11 +100,169 @@ impl ApiEnvironment for Environment {
             Environment::Sandbox => include_str!("./certificates/sandbox"),
         }
     }
+
+    fn is_production(&self) -> bool {
+        matches!(self, Environment::Production)
+    }
+}
+
+/// A X509 certificate holding M-Pesa's public key, used to encrypt initiator passwords
+///
+/// Certificates are validated when they are loaded, they must hold an RSA public key.
+#[derive(Debug, Clone)]
+pub struct Certificate {
+    pem: String,
+    public_key: PublicKey,
+    expires_at: SystemTime,
+    fingerprint: String,
+}
+
+impl Certificate {
+    /// Loads a PEM encoded certificate
+    ///
+    /// # Errors
+    /// Returns a `CertificateError` if the certificate is invalid
+    pub fn from_pem<S: Into<String>>(pem: S) -> MpesaResult<Self> {
+        crypto::parse_pem(&pem.into()).map(Self::new)
+    }
+
+    /// Loads a DER encoded certificate
+    ///
+    /// # Errors
+    /// Returns a `CertificateError` if the certificate is invalid
+    pub fn from_der(der: &[u8]) -> MpesaResult<Self> {
+        crypto::parse_der(der).map(Self::new)
+    }
+
+    /// Loads a PEM or DER encoded certificate
+    ///
+    /// # Errors
+    /// Returns a `CertificateError` if the certificate is invalid
+    pub fn from_bytes(bytes: &[u8]) -> MpesaResult<Self> {
+        match std::str::from_utf8(bytes) {
+            Ok(pem) if pem.contains("-----BEGIN CERTIFICATE-----") => Self::from_pem(pem),
+            _ => Self::from_der(bytes),
+        }
+    }
+
+    /// Loads a PEM or DER encoded certificate from a file
+    ///
+    /// # Errors
+    /// Returns a `CertificateError` if the file cannot be read or the certificate is invalid
+    pub fn from_file<P: AsRef<Path>>(path: P) -> MpesaResult<Self> {
+        let bytes = std::fs::read(path).map_err(|e| MpesaError::CertificateError(Box::new(e)))?;
+        Self::from_bytes(&bytes)
+    }
+
+    fn new(cert: ParsedCertificate) -> Self {
+        let fingerprint = cert
+            .fingerprint
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect::<Vec<_>>()
+            .join(":");
+
+        Certificate {
+            pem: cert.pem,
+            public_key: cert.public_key,
+            expires_at: cert.expires_at,
+            fingerprint,
+        }
+    }
+
+    /// The PEM encoded certificate
+    pub fn as_pem(&self) -> &str {
+        &self.pem
+    }
+
+    pub(crate) fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// Time after which the certificate is no longer valid
+    pub fn expires_at(&self) -> SystemTime {
+        self.expires_at
+    }
+
+    /// Returns `true` if the certificate has expired.
+    ///
+    /// **Note:** M-Pesa keeps accepting credentials encrypted with expired certificates, the
+    /// certificates shipped with this crate have expired.
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= SystemTime::now()
+    }
+
+    /// SHA-256 fingerprint of the certificate, as colon separated hex, e.g. `12:2A:C9:...`
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+}
+
+/// An environment with a base url and a certificate provided at runtime, e.g. after Safaricom
+/// rotates its public key
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use mpesa::environment::{Certificate, RuntimeEnvironment};
+/// use mpesa::Mpesa;
+///
+/// let certificate = Certificate::from_file("/etc/mpesa/production.cer").unwrap();
+/// println!("certificate {} expires at {:?}", certificate.fingerprint(), certificate.expires_at());
+///
+/// let environment = RuntimeEnvironment::new("https://api.safaricom.co.ke", certificate);
+/// let client = Mpesa::new("client_key", "client_secret", environment);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RuntimeEnvironment {
+    base_url: String,
+    certificate: Certificate,
+    production: Option<bool>,
+}
+
+impl RuntimeEnvironment {
+    pub fn new<S: Into<String>>(base_url: S, certificate: Certificate) -> Self {
+        RuntimeEnvironment {
+            base_url: base_url.into(),
+            certificate,
+            production: None,
+        }
+    }
+
+    /// Overrides whether the environment targets the production API, see
+    /// [`ApiEnvironment::is_production`]
+    pub fn production(mut self, production: bool) -> Self {
+        self.production = Some(production);
+        self
+    }
+
+    pub fn certificate(&self) -> &Certificate {
+        &self.certificate
+    }
+}
+
+impl ApiEnvironment for RuntimeEnvironment {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn get_certificate(&self) -> &str {
+        self.certificate.as_pem()
+    }
+
+    fn is_production(&self) -> bool {
+        self.production
+            .unwrap_or(self.base_url == PRODUCTION_BASE_URL)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::convert::TryInto;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
 
     use super::*;
 
@@ -116,4 +294,56 @@ mod tests {
     fn test_invalid_string_panics() {
         let _: Environment = "foo_bar".try_into().unwrap();
     }
+
+    #[test]
+    fn test_certificate_from_pem() {
+        let certificate = Certificate::from_pem(include_str!("./certificates/sandbox")).unwrap();
+        assert_eq!(
+            certificate.fingerprint(),
+            "12:2A:C9:04:7C:BC:99:C8:C9:41:69:7E:62:EE:DA:97:04:CF:1A:45:AA:BC:C9:79:BD:03:34:CF:D0:CB:18:F7"
+        );
+        // Apr 4 12:00:00 2019 GMT
+        assert_eq!(
+            certificate.expires_at(),
+            UNIX_EPOCH + Duration::from_secs(1_554_379_200)
+        );
+        assert!(certificate.is_expired());
+    }
+
+    #[test]
+    fn test_certificate_from_der() {
+        let base64: String = include_str!("./certificates/production")
+            .lines()
+            .filter(|line| !line.starts_with("-----"))
+            .collect();
+        let der = STANDARD.decode(base64).unwrap();
+        let from_der = Certificate::from_bytes(&der).unwrap();
+        let from_pem =
+            Certificate::from_bytes(include_bytes!("./certificates/production")).unwrap();
+        assert_eq!(from_der.fingerprint(), from_pem.fingerprint());
+        assert!(Certificate::from_pem(from_der.as_pem()).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_certificate_is_rejected() {
+        let err = Certificate::from_pem("not a certificate").unwrap_err();
+        assert!(matches!(err, MpesaError::CertificateError(_)));
+
+        let err = Certificate::from_file("/nonexistent/certificate.cer").unwrap_err();
+        assert!(matches!(err, MpesaError::CertificateError(_)));
+    }
+
+    #[test]
+    fn test_runtime_environment() {
+        let certificate = Certificate::from_pem(include_str!("./certificates/production")).unwrap();
+        let environment = RuntimeEnvironment::new(PRODUCTION_BASE_URL, certificate);
+        assert!(environment.is_production());
+        assert_eq!(
+            environment.get_certificate(),
+            include_str!("./certificates/production")
+        );
+
+        let environment = environment.production(false);
+        assert!(!environment.is_production());
+    }
 }
@@ -1,24 +1,61 @@
+use std::borrow::Cow;
+use std::convert::Infallible;
 use std::env::VarError;
 use std::fmt;
 
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use thiserror::Error;
 
+/// Response bodies kept on errors are truncated to this many characters
+const MAX_BODY_LEN: usize = 512;
+
+/// Fragments of JSON keys whose values are redacted from response bodies kept on errors
+const REDACTED_KEYS: [&str; 6] = [
+    "token",
+    "password",
+    "credential",
+    "phone",
+    "msisdn",
+    "publicname",
+];
+
+/// Runs of at least this many digits in bodies that are not JSON are redacted as phone numbers
+const MIN_PHONE_DIGITS: usize = 9;
+
+pub(crate) const REDACTED: &str = "[REDACTED]";
+
 /// Mpesa error stack
 #[derive(Error, Debug)]
 pub enum MpesaError {
     #[error("Service error: {0}")]
     Service(ResponseError),
+    #[error("Unexpected response from M-Pesa: {0}")]
+    UnexpectedResponse(Box<HttpContext>),
+    #[error("An error has occurred while decoding the response: {source}, {http}")]
+    DecodeError {
+        http: Box<HttpContext>,
+        source: serde_json::Error,
+    },
     #[error("An error has occurred while performing the http request")]
     NetworkError(#[from] reqwest::Error),
     #[error("An error has occurred while serializing/ deserializing")]
     ParseError(#[from] serde_json::Error),
     #[error("An error has occurred while retrieving an environmental variable")]
     EnvironmentalVariableError(#[from] VarError),
-    #[error("An error has occurred while generating security credentials")]
-    EncryptionError(#[from] openssl::error::ErrorStack),
+    #[error("An error has occurred while generating security credentials: {0}")]
+    EncryptionError(Box<dyn std::error::Error + Send + Sync>),
     #[error("{0}")]
-    Message(&'static str),
+    Message(Cow<'static, str>),
+    #[error("{0} is required in production, the sandbox default cannot be used")]
+    SandboxDefaultInProduction(&'static str),
+    #[error("An error has occurred while accessing the token store: {0}")]
+    TokenStoreError(Box<dyn std::error::Error + Send + Sync>),
+    #[error("Invalid certificate: {0}")]
+    CertificateError(Box<dyn std::error::Error + Send + Sync>),
+    #[error("An error has occurred while loading the configuration: {0}")]
+    ConfigError(Box<dyn std::error::Error + Send + Sync>),
     #[error("An error has occurred while building the request: {0}")]
     BuilderError(BuilderError),
 }
@@ -29,9 +66,251 @@ pub type MpesaResult<T> = Result<T, MpesaError>;
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all(deserialize = "camelCase"))]
 pub struct ResponseError {
+    #[serde(default)]
     pub request_id: String,
     pub error_code: String,
     pub error_message: String,
+    /// HTTP context of the response the error was returned in
+    #[serde(skip)]
+    pub http: Option<Box<HttpContext>>,
+}
+
+impl ResponseError {
+    /// Error code returned by M-Pesa when the access token is invalid or has expired
+    pub const INVALID_ACCESS_TOKEN: &'static str = "404.001.03";
+
+    /// Returns `true` if M-Pesa rejected the access token used for the request
+    pub fn is_invalid_access_token(&self) -> bool {
+        self.error_code == Self::INVALID_ACCESS_TOKEN
+    }
+
+    /// Classifies the error code, see `ServiceErrorKind`
+    pub fn kind(&self) -> ServiceErrorKind {
+        let message = self.error_message.to_lowercase();
+        match self.error_code.as_str() {
+            Self::INVALID_ACCESS_TOKEN | "400.003.01" | "401.002.01" => {
+                ServiceErrorKind::InvalidAccessToken
+            }
+            "400.008.01" | "400.008.02" => ServiceErrorKind::InvalidCredentials,
+            _ if message.contains("duplicate") || message.contains("already in process") => {
+                ServiceErrorKind::Duplicate
+            }
+            _ if message.contains("merchant does not exist") => {
+                ServiceErrorKind::MerchantDoesNotExist
+            }
+            "400.002.02" => ServiceErrorKind::BadRequest {
+                field: invalid_field(&self.error_message),
+            },
+            "500.003.02" | "500.003.03" => ServiceErrorKind::Throttled,
+            "404.001.01" => ServiceErrorKind::NotFound,
+            code if code.starts_with("400.") => ServiceErrorKind::BadRequest { field: None },
+            code if code.starts_with("429.") => ServiceErrorKind::Throttled,
+            code if code.starts_with("500.") || code.starts_with("503.") => {
+                ServiceErrorKind::InternalError
+            }
+            _ => ServiceErrorKind::Unknown,
+        }
+    }
+}
+
+/// HTTP context of a response that failed or could not be decoded
+///
+/// The body is truncated and credentials, tokens and phone numbers are redacted, so the context
+/// can be logged. Values are redacted by key in JSON bodies, including `Name`/ `Key` and `Value`
+/// parameter pairs. The other JSON strings, and bodies that are not JSON, are scrubbed of digit
+/// runs that may be phone numbers and of the values following sensitive keys, e.g.
+/// `access_token=...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpContext {
+    /// Status of the response
+    pub status: StatusCode,
+    /// Path of the request, e.g. `/mpesa/stkpush/v1/processrequest`
+    pub path: String,
+    /// Request id reported by M-Pesa, if any
+    pub request_id: Option<String>,
+    /// Redacted body of the response
+    pub body: String,
+}
+
+impl HttpContext {
+    pub(crate) fn new(status: StatusCode, path: &str, body: &str) -> Self {
+        let (request_id, body) = match serde_json::from_str::<Value>(body) {
+            Ok(mut value) => {
+                let request_id = value
+                    .as_object()
+                    .and_then(|object| {
+                        object
+                            .iter()
+                            .find(|(key, _)| key.eq_ignore_ascii_case("requestId"))
+                    })
+                    .and_then(|(_, id)| id.as_str())
+                    .filter(|id| !id.is_empty())
+                    .map(String::from);
+                redact(&mut value);
+                (request_id, value.to_string())
+            }
+            Err(_) => (None, scrub(body.trim())),
+        };
+
+        HttpContext {
+            status,
+            path: path.into(),
+            request_id,
+            body: body.chars().take(MAX_BODY_LEN).collect(),
+        }
+    }
+}
+
+impl fmt::Display for HttpContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "status {} from {}", self.status, self.path)?;
+        if let Some(request_id) = &self.request_id {
+            write!(f, ", requestID: {request_id}")?;
+        }
+        write!(f, ", body: {}", self.body)
+    }
+}
+
+/// Returns `true` if values of `key` must be redacted
+fn is_sensitive(key: &str) -> bool {
+    let key = key.to_lowercase();
+    REDACTED_KEYS.iter().any(|redacted| key.contains(redacted))
+}
+
+/// Replaces the values of sensitive keys in `value` and scrubs the other strings, e.g. a phone
+/// number quoted in an `errorMessage`
+///
+/// Parameters sent as `{"Name": "PhoneNumber", "Value": ...}` or `{"Key": ..., "Value": ...}`
+/// have their `Value` redacted when the name is sensitive.
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            let sensitive_parameter = object.iter().any(|(key, name)| {
+                (key.eq_ignore_ascii_case("name") || key.eq_ignore_ascii_case("key"))
+                    && name.as_str().is_some_and(is_sensitive)
+            });
+
+            for (key, value) in object.iter_mut() {
+                if is_sensitive(key) || (sensitive_parameter && key.eq_ignore_ascii_case("value")) {
+                    *value = Value::String(REDACTED.into());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact),
+        Value::String(string) => *string = scrub(string),
+        _ => {}
+    }
+}
+
+/// Redacts digit runs that may be phone numbers and the values following sensitive keys,
+/// e.g. `access_token=...` or `"PhoneNumber": ...`, from a body that is not JSON or a JSON string
+fn scrub(body: &str) -> String {
+    let mut scrubbed = String::with_capacity(body.len());
+    let mut rest = body;
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_ascii_digit() {
+            let len = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            if len >= MIN_PHONE_DIGITS {
+                scrubbed.push_str(REDACTED);
+            } else {
+                scrubbed.push_str(&rest[..len]);
+            }
+            rest = &rest[len..];
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphabetic() || c == '_' || c == '-'))
+                .unwrap_or(rest.len());
+            let (word, after) = rest.split_at(len);
+            scrubbed.push_str(word);
+            rest = after;
+
+            if is_sensitive(word) {
+                let separator_len = rest
+                    .find(|c: char| !(c.is_whitespace() || "\"':=".contains(c)))
+                    .unwrap_or(rest.len());
+                let (separator, after) = rest.split_at(separator_len);
+                if separator.contains([':', '=']) {
+                    let value_len = after
+                        .find(|c: char| c.is_whitespace() || "\"'&,;<}".contains(c))
+                        .unwrap_or(after.len());
+                    scrubbed.push_str(separator);
+                    if value_len > 0 {
+                        scrubbed.push_str(REDACTED);
+                    }
+                    rest = &after[value_len..];
+                }
+            }
+        } else {
+            scrubbed.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    scrubbed
+}
+
+/// Extracts the field named in messages like `Bad Request - Invalid PhoneNumber`
+fn invalid_field(message: &str) -> Option<String> {
+    const INVALID: &str = "invalid ";
+
+    // Searched in place rather than in a lowercased copy, whose byte offsets may differ
+    let start = message.char_indices().find_map(|(i, _)| {
+        message
+            .get(i..i + INVALID.len())
+            .filter(|window| window.eq_ignore_ascii_case(INVALID))
+            .map(|_| i + INVALID.len())
+    })?;
+    let field: String = message[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+    (!field.is_empty()).then_some(field)
+}
+
+/// Kind of error returned by M-Pesa, classified from `ResponseError::error_code`
+///
+/// # Example
+///
+/// ```rust
+/// use mpesa::{ResponseError, ServiceErrorKind};
+///
+/// let error = ResponseError {
+///     request_id: "11728-2929992-1".into(),
+///     error_code: "400.002.02".into(),
+///     error_message: "Bad Request - Invalid PhoneNumber".into(),
+///     http: None,
+/// };
+/// assert_eq!(
+///     error.kind(),
+///     ServiceErrorKind::BadRequest {
+///         field: Some("PhoneNumber".into())
+///     }
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ServiceErrorKind {
+    /// The access token is invalid or has expired
+    InvalidAccessToken,
+    /// The client key or secret was rejected while generating an access token
+    InvalidCredentials,
+    /// The request was malformed, `field` names the offending field when M-Pesa reports it
+    BadRequest { field: Option<String> },
+    /// Too many requests were sent, e.g. a spike arrest or quota violation
+    Throttled,
+    /// The request duplicates one that is already being processed
+    Duplicate,
+    /// The short code is not set up for the API, e.g. for M-Pesa Express
+    MerchantDoesNotExist,
+    /// The requested resource does not exist
+    NotFound,
+    /// M-Pesa failed to process the request
+    InternalError,
+    /// An error code this crate does not recognize
+    Unknown,
 }
 
 impl fmt::Display for ResponseError {
@@ -48,18 +327,205 @@ impl fmt::Display for ResponseError {
 pub enum BuilderError {
     #[error("Field [{0}] is required")]
     UninitializedField(&'static str),
-    #[error("Field [{0}] is invalid")]
+    #[error("{0}")]
     ValidationError(String),
 }
 
+impl MpesaError {
+    /// Builds the error for a failed response to the request to `path` from its `body`
+    ///
+    /// Bodies that are not an M-Pesa error, e.g. HTML pages returned by a gateway, yield
+    /// `MpesaError::UnexpectedResponse`.
+    pub(crate) fn from_response(status: StatusCode, path: &str, body: &str) -> Self {
+        let http = Box::new(HttpContext::new(status, path, body));
+        match serde_json::from_str::<ResponseError>(body) {
+            Ok(error) => Self::Service(ResponseError {
+                http: Some(http),
+                ..error
+            }),
+            Err(_) => Self::UnexpectedResponse(http),
+        }
+    }
+
+    /// HTTP context of the response that caused the error, if any
+    pub fn http_context(&self) -> Option<&HttpContext> {
+        match self {
+            Self::Service(error) => error.http.as_deref(),
+            Self::UnexpectedResponse(http) | Self::DecodeError { http, .. } => Some(http),
+            _ => None,
+        }
+    }
+
+    /// Names the builder `field` that failed validation
+    pub(crate) fn for_field(self, field: &str) -> Self {
+        match self {
+            Self::BuilderError(BuilderError::ValidationError(reason)) => Self::BuilderError(
+                BuilderError::ValidationError(format!("{field} is invalid: {reason}")),
+            ),
+            e => e,
+        }
+    }
+}
+
 impl From<String> for BuilderError {
     fn from(s: String) -> Self {
         Self::ValidationError(s)
     }
 }
 
+impl From<Infallible> for MpesaError {
+    fn from(e: Infallible) -> Self {
+        match e {}
+    }
+}
+
 impl From<derive_builder::UninitializedFieldError> for MpesaError {
     fn from(e: derive_builder::UninitializedFieldError) -> Self {
         Self::BuilderError(BuilderError::UninitializedField(e.field_name()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response_error(error_code: &str, error_message: &str) -> ResponseError {
+        ResponseError {
+            request_id: "11728-2929992-1".into(),
+            error_code: error_code.into(),
+            error_message: error_message.into(),
+            http: None,
+        }
+    }
+
+    #[test]
+    fn test_service_error_kind() {
+        let cases = [
+            ("404.001.03", "Invalid Access Token", ServiceErrorKind::InvalidAccessToken),
+            ("400.008.02", "Invalid grant type passed", ServiceErrorKind::InvalidCredentials),
+            (
+                "400.002.02",
+                "Bad Request - Invalid PhoneNumber",
+                ServiceErrorKind::BadRequest {
+                    field: Some("PhoneNumber".into()),
+                },
+            ),
+            (
+                "400.002.05",
+                "Invalid Request Payload",
+                ServiceErrorKind::BadRequest { field: None },
+            ),
+            (
+                "500.003.02",
+                "System is busy. Please try again in few minutes.",
+                ServiceErrorKind::Throttled,
+            ),
+            (
+                "500.001.1001",
+                "Unable to lock subscriber, a transaction is already in process for the current subscriber",
+                ServiceErrorKind::Duplicate,
+            ),
+            ("500.001.1001", "Merchant does not exist", ServiceErrorKind::MerchantDoesNotExist),
+            ("404.001.01", "Resource not found", ServiceErrorKind::NotFound),
+            ("500.003.01", "Internal Server Error", ServiceErrorKind::InternalError),
+            ("999.999.99", "Something new", ServiceErrorKind::Unknown),
+        ];
+        for (code, message, kind) in cases {
+            assert_eq!(
+                response_error(code, message).kind(),
+                kind,
+                "{code}: {message}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_field_with_non_ascii_message() {
+        assert_eq!(
+            invalid_field("İİİ Bad Request - Invalid PhoneNumber").as_deref(),
+            Some("PhoneNumber")
+        );
+        assert_eq!(invalid_field("İnvalid").as_deref(), None);
+        assert_eq!(
+            invalid_field("Ünïcödé INVALID Amount").as_deref(),
+            Some("Amount")
+        );
+    }
+
+    #[test]
+    fn test_error_from_response_body() {
+        let error = MpesaError::from_response(
+            StatusCode::BAD_REQUEST,
+            "/mpesa/stkpush/v1/processrequest",
+            r#"{"requestId":"11728-2929992-1","errorCode":"400.002.02","errorMessage":"Bad Request - Invalid Amount"}"#,
+        );
+        let http = error.http_context().unwrap();
+        assert_eq!(http.status, StatusCode::BAD_REQUEST);
+        assert_eq!(http.path, "/mpesa/stkpush/v1/processrequest");
+        assert_eq!(http.request_id.as_deref(), Some("11728-2929992-1"));
+        assert!(matches!(error, MpesaError::Service(e) if e.error_code == "400.002.02"));
+
+        let html = format!("<html>{}</html>", "x".repeat(1000));
+        match MpesaError::from_response(StatusCode::BAD_GATEWAY, "/oauth/v1/generate", &html) {
+            MpesaError::UnexpectedResponse(http) => {
+                assert_eq!(http.status, StatusCode::BAD_GATEWAY);
+                assert_eq!(http.request_id, None);
+                assert_eq!(http.body.chars().count(), MAX_BODY_LEN);
+            }
+            e => panic!("expected an unexpected response error, got {e:?}"),
+        }
+    }
+
+    #[test]
+    fn test_http_context_redacts_body() {
+        let http = HttpContext::new(
+            StatusCode::OK,
+            "/oauth/v1/generate",
+            r#"{"access_token":"secret","expires_in":"3599","Items":[{"PhoneNumber":"254712345678","Amount":1}]}"#,
+        );
+        assert_eq!(
+            http.body,
+            r#"{"Items":[{"Amount":1,"PhoneNumber":"[REDACTED]"}],"access_token":"[REDACTED]","expires_in":"3599"}"#
+        );
+    }
+
+    #[test]
+    fn test_http_context_redacts_parameter_values() {
+        let http = HttpContext::new(
+            StatusCode::OK,
+            "/mpesa/stkpush/v1/processrequest",
+            r#"{"Item":[{"Name":"Amount","Value":1},{"Name":"PhoneNumber","Value":254712345678}],"Parameter":[{"Key":"ReceiverPartyPublicName","Value":"254712345678 - John Doe"}]}"#,
+        );
+        assert_eq!(
+            http.body,
+            r#"{"Item":[{"Name":"Amount","Value":1},{"Name":"PhoneNumber","Value":"[REDACTED]"}],"Parameter":[{"Key":"ReceiverPartyPublicName","Value":"[REDACTED]"}]}"#
+        );
+    }
+
+    #[test]
+    fn test_http_context_scrubs_json_strings() {
+        let http = HttpContext::new(
+            StatusCode::BAD_REQUEST,
+            "/mpesa/stkpush/v1/processrequest",
+            r#"{"requestId":"11728-2929992-1","errorCode":"400.002.02","errorMessage":"Bad Request - Invalid PhoneNumber 254712345678","Items":["0712345678"]}"#,
+        );
+        assert_eq!(
+            http.body,
+            r#"{"Items":["[REDACTED]"],"errorCode":"400.002.02","errorMessage":"Bad Request - Invalid PhoneNumber [REDACTED]","requestId":"11728-2929992-1"}"#
+        );
+    }
+
+    #[test]
+    fn test_http_context_scrubs_non_json_body() {
+        let http = HttpContext::new(
+            StatusCode::BAD_GATEWAY,
+            "/oauth/v1/generate",
+            "<p>access_token=abc123&expires_in=3599 PhoneNumber: 254712345678, Msisdn=0712345678 code 404</p>",
+        );
+        assert_eq!(
+            http.body,
+            "<p>access_token=[REDACTED]&expires_in=3599 PhoneNumber: [REDACTED], Msisdn=[REDACTED] code 404</p>"
+        );
+        assert_eq!(scrub("call 254712345678 now"), "call [REDACTED] now");
+    }
+}
@@ -1,17 +1,69 @@
 #![doc = include_str!("../README.md")]
 
+mod amount;
 mod auth;
+pub mod callbacks;
 mod client;
+pub mod config;
 mod constants;
+mod crypto;
 pub mod environment;
 mod errors;
+pub mod middleware;
+mod msisdn;
+#[cfg(any(
+    feature = "account_balance",
+    feature = "b2b",
+    feature = "b2c",
+    feature = "bill_manager",
+    feature = "c2b_register",
+    feature = "c2b_simulate",
+    feature = "dynamic_qr",
+    feature = "express_query",
+    feature = "express_request",
+    feature = "transaction_reversal",
+    feature = "transaction_status"
+))]
+mod rate_limit;
+mod retry;
 pub mod services;
+mod short_code;
+pub mod token_store;
+mod url;
+#[cfg(feature = "webhooks")]
+pub mod webhooks;
 
-pub use client::Mpesa;
+pub use amount::Amount;
+pub use client::{Mpesa, MpesaBuilder};
+pub use config::MpesaConfig;
 pub use constants::{
-    CommandId, IdentifierTypes, Invoice, InvoiceItem, ResponseType, SendRemindersTypes,
+    CommandId, IdentifierTypes, MpesaResponseCode, ResponseType, SendRemindersTypes,
     TransactionType,
 };
-pub use environment::ApiEnvironment;
+#[cfg(feature = "bill_manager")]
+pub use constants::{Invoice, InvoiceItem};
 pub use environment::Environment::{self, Production, Sandbox};
-pub use errors::{MpesaError, MpesaResult, ResponseError};
+pub use environment::{ApiEnvironment, Certificate, RuntimeEnvironment};
+pub use errors::{
+    BuilderError, HttpContext, MpesaError, MpesaResult, ResponseError, ServiceErrorKind,
+};
+pub use middleware::Middleware;
+pub use msisdn::Msisdn;
+#[cfg(any(
+    feature = "account_balance",
+    feature = "b2b",
+    feature = "b2c",
+    feature = "bill_manager",
+    feature = "c2b_register",
+    feature = "c2b_simulate",
+    feature = "dynamic_qr",
+    feature = "express_query",
+    feature = "express_request",
+    feature = "transaction_reversal",
+    feature = "transaction_status"
+))]
+pub use rate_limit::RateLimit;
+pub use retry::RetryPolicy;
+pub use short_code::{ShortCode, TillNumber};
+pub use token_store::{FileTokenStore, TokenCache, TokenStore};
+pub use url::Url;
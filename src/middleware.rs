@@ -0,0 +1,72 @@
+//!# Middleware
+//!
+//! Hooks into every http request sent by the `Mpesa` client, including authentication.
+//! Middleware can be used for logging, metrics, injecting headers such as correlation ids,
+//! signing requests or injecting faults in tests.
+//!
+//! Middleware are added with [`Mpesa::with_middleware`](crate::Mpesa::with_middleware) and run
+//! in the order they were added. Retried and replayed requests go through the middleware again.
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+pub use reqwest::{Request, Response};
+
+use crate::{MpesaError, MpesaResult};
+
+/// A hook into the requests sent by the `Mpesa` client
+///
+/// Every method has a default implementation that does nothing, so only the hooks you are
+/// interested in need to be implemented.
+///
+/// # Example
+///
+/// ```rust
+/// use mpesa::middleware::{Middleware, Request, Response};
+/// use mpesa::{Environment, Mpesa, MpesaError, MpesaResult};
+///
+/// #[derive(Debug)]
+/// struct CorrelationId;
+///
+/// #[async_trait::async_trait]
+/// impl Middleware for CorrelationId {
+///     async fn before_send(&self, request: &mut Request) -> MpesaResult<()> {
+///         request
+///             .headers_mut()
+///             .insert("X-Correlation-ID", "b6f2e0d1".parse().unwrap());
+///         Ok(())
+///     }
+///
+///     async fn after_response(&self, request: &Request, response: &Response) -> MpesaResult<()> {
+///         println!("{} {} -> {}", request.method(), request.url(), response.status());
+///         Ok(())
+///     }
+///
+///     async fn on_error(&self, error: &MpesaError) {
+///         eprintln!("M-Pesa request failed: {error}");
+///     }
+/// }
+///
+/// let client = Mpesa::new("client_key", "client_secret", Environment::Sandbox)
+///     .with_middleware(CorrelationId);
+/// ```
+#[async_trait]
+pub trait Middleware: Debug + Send + Sync {
+    /// Called before a request is sent, the request can be modified.
+    ///
+    /// Returning an error aborts the request, the error is returned to the caller.
+    async fn before_send(&self, _request: &mut Request) -> MpesaResult<()> {
+        Ok(())
+    }
+
+    /// Called once the response headers have been received, whatever the status code.
+    ///
+    /// Returning an error aborts the request, the error is returned to the caller.
+    async fn after_response(&self, _request: &Request, _response: &Response) -> MpesaResult<()> {
+        Ok(())
+    }
+
+    /// Called when a request sent by one of the service builders fails, with the error
+    /// returned to the caller
+    async fn on_error(&self, _error: &MpesaError) {}
+}
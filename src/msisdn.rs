@@ -0,0 +1,231 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{BuilderError, MpesaError, MpesaResult};
+
+/// A Kenyan mobile phone number, normalized to the `2547XXXXXXXX`/ `2541XXXXXXXX` format
+/// expected by the M-Pesa APIs
+///
+/// Numbers are parsed from the local (`0712345678`), international (`+254712345678`,
+/// `254712345678`) and short (`712345678`) forms. Spaces, dashes and dots are ignored, e.g.
+/// `0712 345-678`. Only Safaricom's mobile prefixes `7` and `1` are accepted.
+///
+/// # Example
+///
+/// ```rust
+/// use mpesa::Msisdn;
+///
+/// let msisdn: Msisdn = "0712 345 678".parse().unwrap();
+/// assert_eq!(msisdn.as_str(), "254712345678");
+/// assert_eq!(Msisdn::parse("+254 110 345678").unwrap(), "254110345678");
+///
+/// assert!(Msisdn::parse("0201234567").is_err());
+/// assert!(Msisdn::parse("07123456").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Msisdn(String);
+
+impl Msisdn {
+    /// Parses and normalizes a phone number
+    ///
+    /// # Errors
+    /// Returns a `BuilderError::ValidationError` if `phone_number` is not a Kenyan mobile number
+    pub fn parse(phone_number: &str) -> MpesaResult<Self> {
+        let digits = phone_number
+            .trim()
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '.'))
+            .collect::<String>();
+
+        let subscriber = digits
+            .strip_prefix("+254")
+            .or_else(|| digits.strip_prefix("254"))
+            .or_else(|| digits.strip_prefix('0'))
+            .unwrap_or(&digits);
+
+        let valid = subscriber.len() == 9
+            && subscriber.bytes().all(|b| b.is_ascii_digit())
+            && matches!(subscriber.as_bytes()[0], b'7' | b'1');
+        if !valid {
+            return Err(MpesaError::BuilderError(BuilderError::ValidationError(
+                "a phone number must be a Kenyan mobile number, e.g. 0712345678".into(),
+            )));
+        }
+
+        Ok(Msisdn(format!("254{subscriber}")))
+    }
+
+    /// The phone number in the `2547XXXXXXXX` format
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The phone number in the local `07XXXXXXXX` format, used by the Bill Manager APIs
+    pub fn to_local(&self) -> String {
+        format!("0{}", &self.0[3..])
+    }
+
+    /// Serializes the phone number in the local format
+    #[cfg(feature = "bill_manager")]
+    pub(crate) fn serialize_local<S: Serializer>(
+        msisdn: &Msisdn,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&msisdn.to_local())
+    }
+}
+
+impl Display for Msisdn {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for Msisdn {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<&str> for Msisdn {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl From<Msisdn> for String {
+    fn from(msisdn: Msisdn) -> Self {
+        msisdn.0
+    }
+}
+
+impl FromStr for Msisdn {
+    type Err = MpesaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Msisdn::parse(s)
+    }
+}
+
+impl TryFrom<&str> for Msisdn {
+    type Error = MpesaError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        Msisdn::parse(s)
+    }
+}
+
+impl TryFrom<String> for Msisdn {
+    type Error = MpesaError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Msisdn::parse(&s)
+    }
+}
+
+impl TryFrom<&String> for Msisdn {
+    type Error = MpesaError;
+
+    fn try_from(s: &String) -> Result<Self, Self::Error> {
+        Msisdn::parse(s)
+    }
+}
+
+impl TryFrom<u64> for Msisdn {
+    type Error = MpesaError;
+
+    fn try_from(n: u64) -> Result<Self, Self::Error> {
+        Msisdn::parse(&n.to_string())
+    }
+}
+
+impl Serialize for Msisdn {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Msisdn {
+    /// Deserializes a JSON string or number holding a phone number
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Number(u64),
+            String(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Number(n) => Msisdn::try_from(n),
+            Repr::String(s) => Msisdn::parse(&s),
+        }
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_msisdn_is_normalized() {
+        for phone_number in [
+            "0712345678",
+            "712345678",
+            "254712345678",
+            "+254712345678",
+            " +254 712 345 678 ",
+            "0712-345-678",
+            "0712.345.678",
+        ] {
+            assert_eq!(Msisdn::parse(phone_number).unwrap(), "254712345678");
+        }
+        assert_eq!(Msisdn::parse("0110345678").unwrap(), "254110345678");
+        assert_eq!(Msisdn::parse("+254110345678").unwrap(), "254110345678");
+        assert_eq!(
+            Msisdn::parse("254110345678").unwrap().to_local(),
+            "0110345678"
+        );
+    }
+
+    #[test]
+    fn test_invalid_msisdn_is_rejected() {
+        for phone_number in [
+            "",
+            "07123456",
+            "07123456789",
+            "0201234567",
+            "254201234567",
+            "+255712345678",
+            "0812345678",
+            "07123a5678",
+            "+0712345678",
+            "2540712345678",
+        ] {
+            assert!(
+                matches!(
+                    Msisdn::parse(phone_number),
+                    Err(MpesaError::BuilderError(BuilderError::ValidationError(_)))
+                ),
+                "{phone_number} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_msisdn_serde() {
+        let msisdn = Msisdn::parse("0712345678").unwrap();
+        assert_eq!(serde_json::to_string(&msisdn).unwrap(), "\"254712345678\"");
+        assert_eq!(
+            serde_json::from_str::<Msisdn>("254712345678").unwrap(),
+            msisdn
+        );
+        assert_eq!(
+            serde_json::from_str::<Msisdn>("\"0712 345 678\"").unwrap(),
+            msisdn
+        );
+        assert!(serde_json::from_str::<Msisdn>("\"0201234567\"").is_err());
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+/// Limits the rate and concurrency of requests sent by a `Mpesa` client
+///
+/// Requests over the limit wait locally until they are allowed through, rather than being
+/// rejected by Safaricom's spike arrest. The rate is enforced with a token bucket holding up
+/// to `burst` requests, refilled at the configured rate. Waiting requests are let through in
+/// the order they arrived.
+///
+/// Limits can be set for every request sent by the client with
+/// [`Mpesa::with_rate_limit`](crate::Mpesa::with_rate_limit) and for a single endpoint with
+/// [`Mpesa::with_endpoint_rate_limit`](crate::Mpesa::with_endpoint_rate_limit); a request
+/// must satisfy both. Clones of a client share its limits.
+///
+/// # Example
+///
+/// ```rust
+/// use mpesa::services::B2C_URL;
+/// use mpesa::{Environment, Mpesa, RateLimit};
+///
+/// let client = Mpesa::new("client_key", "client_secret", Environment::Sandbox)
+///     .with_rate_limit(RateLimit::per_second(20).max_in_flight(50))
+///     .with_endpoint_rate_limit(B2C_URL, RateLimit::per_second(5).burst(5));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RateLimit {
+    interval: Option<Duration>,
+    burst: u32,
+    max_in_flight: Option<usize>,
+}
+
+impl RateLimit {
+    /// No limit on the rate of requests, use with [`RateLimit::max_in_flight`] to only limit
+    /// the number of concurrent requests
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Allows `requests` requests per second
+    pub fn per_second(requests: u32) -> Self {
+        Self::per_period(requests, Duration::from_secs(1))
+    }
+
+    /// Allows `requests` requests per `period`
+    pub fn per_period(requests: u32, period: Duration) -> Self {
+        RateLimit {
+            interval: Some(period / requests.max(1)),
+            burst: 1,
+            max_in_flight: None,
+        }
+    }
+
+    /// Number of requests that can be sent at once after a quiet period. Defaults to 1, which
+    /// evenly spaces out requests
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    /// Maximum number of requests waiting for a response at any time
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = Some(max_in_flight.max(1));
+        self
+    }
+}
+
+/// Shared state enforcing a [`RateLimit`]
+#[derive(Debug)]
+struct Limiter {
+    interval: Option<Duration>,
+    burst: u32,
+    /// Time at which the bucket will be full if no more requests are sent
+    full_at: Mutex<Instant>,
+    in_flight: Option<Arc<Semaphore>>,
+}
+
+impl Limiter {
+    fn new(limit: RateLimit) -> Self {
+        Limiter {
+            interval: limit.interval,
+            burst: limit.burst,
+            full_at: Mutex::new(Instant::now()),
+            in_flight: limit.max_in_flight.map(|n| Arc::new(Semaphore::new(n))),
+        }
+    }
+
+    /// Waits until a request can be sent, the returned permit must be held until the
+    /// response has been received
+    async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        if let Some(interval) = self.interval {
+            // Reserve a token, requests are let through in the order of their reservations
+            let ready_at = {
+                let mut full_at = self.full_at.lock().unwrap();
+                let now = Instant::now();
+                let capacity = interval * self.burst;
+                let ready_at = (*full_at).max(now) + interval;
+                *full_at = ready_at;
+                ready_at.checked_sub(capacity).unwrap_or(now)
+            };
+            tokio::time::sleep_until(ready_at).await;
+        }
+
+        match &self.in_flight {
+            Some(semaphore) => Arc::clone(semaphore).acquire_owned().await.ok(),
+            None => None,
+        }
+    }
+}
+
+/// Client and endpoint rate limits of a `Mpesa` client
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RateLimits {
+    client: Option<Arc<Limiter>>,
+    endpoints: HashMap<&'static str, Arc<Limiter>>,
+}
+
+/// Held while a request is in flight
+pub(crate) struct Permit {
+    _client: Option<OwnedSemaphorePermit>,
+    _endpoint: Option<OwnedSemaphorePermit>,
+}
+
+impl RateLimits {
+    pub(crate) fn set_client_limit(&mut self, limit: RateLimit) {
+        self.client = Some(Arc::new(Limiter::new(limit)));
+    }
+
+    pub(crate) fn set_endpoint_limit(&mut self, path: &'static str, limit: RateLimit) {
+        self.endpoints.insert(path, Arc::new(Limiter::new(limit)));
+    }
+
+    /// Waits until a request to `path` is allowed by both the client and endpoint limits
+    pub(crate) async fn acquire(&self, path: &str) -> Permit {
+        let endpoint = match self.endpoints.get(path) {
+            Some(limiter) => limiter.acquire().await,
+            None => None,
+        };
+        let client = match &self.client {
+            Some(limiter) => limiter.acquire().await,
+            None => None,
+        };
+
+        Permit {
+            _client: client,
+            _endpoint: endpoint,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requests_are_spaced_out() {
+        let limiter = Limiter::new(RateLimit::per_second(10));
+        let start = Instant::now();
+
+        for _ in 0..5 {
+            limiter.acquire().await;
+        }
+
+        assert_eq!(start.elapsed(), Duration::from_millis(400));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_burst_is_let_through_at_once() {
+        let limiter = Limiter::new(RateLimit::per_second(10).burst(3));
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        limiter.acquire().await;
+        assert_eq!(start.elapsed(), Duration::from_millis(100));
+
+        // The bucket refills while idle
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_max_in_flight() {
+        let limiter = Limiter::new(RateLimit::unlimited().max_in_flight(2));
+
+        let first = limiter.acquire().await;
+        let _second = limiter.acquire().await;
+        assert!(
+            tokio::time::timeout(Duration::from_secs(1), limiter.acquire())
+                .await
+                .is_err()
+        );
+
+        drop(first);
+        assert!(
+            tokio::time::timeout(Duration::from_secs(1), limiter.acquire())
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_endpoint_limits_only_apply_to_their_endpoint() {
+        let mut limits = RateLimits::default();
+        limits.set_endpoint_limit("mpesa/b2c/v1/paymentrequest", RateLimit::per_second(1));
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            limits.acquire("mpesa/c2b/v1/registerurl").await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        for _ in 0..3 {
+            limits.acquire("mpesa/b2c/v1/paymentrequest").await;
+        }
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+    }
+}
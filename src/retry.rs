@@ -0,0 +1,285 @@
+use std::future::Future;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+
+use crate::{MpesaError, MpesaResponseCode, MpesaResult};
+
+/// M-Pesa error code returned when the system is busy, e.g. while traffic is being throttled
+const SYSTEM_BUSY: &str = "500.003.02";
+
+/// Controls how requests that fail with a transient error are retried
+///
+/// Retries only apply to authentication and to operations that are safe to repeat, i.e.
+/// `express_query` and `dynamic_qr`, whose results are returned synchronously. Requests that
+/// move money are never retried, nor are `transaction_status` and `account_balance`: their
+/// results are delivered to the result url, so a repeated request would start a second query
+/// and trigger a duplicate callback.
+///
+/// Besides the configured HTTP status and error codes, responses whose `ResponseCode` is
+/// transient according to [`MpesaResponseCode::is_retryable`], e.g. `26` traffic blocking, are
+/// retried. Once the attempts are exhausted the last response is returned.
+///
+/// The delay before the `n`th retry is `initial_backoff * 2^(n - 1)`, capped at `max_backoff`.
+/// With jitter enabled, a random delay between zero and that value is used instead so that
+/// several clients do not retry in lockstep.
+///
+/// By default `Mpesa` clients do not retry.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use mpesa::{Environment, Mpesa, RetryPolicy};
+///
+/// let client = Mpesa::new("client_key", "client_secret", Environment::Sandbox)
+///     .with_retry_policy(
+///         RetryPolicy::new()
+///             .max_attempts(5)
+///             .initial_backoff(Duration::from_millis(100)),
+///     );
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+    retry_network_errors: bool,
+    retryable_status_codes: Vec<u16>,
+    retryable_error_codes: Vec<String>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+            jitter: true,
+            retry_network_errors: true,
+            retryable_status_codes: vec![429, 500, 502, 503, 504],
+            retryable_error_codes: vec![SYSTEM_BUSY.to_owned()],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a policy making up to 3 attempts, retrying connection errors, timeouts,
+    /// `429`, `500`, `502`, `503` and `504` responses and the `500.003.02` system busy error
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A policy that never retries, this is the default policy of `Mpesa` clients
+    pub fn none() -> Self {
+        Self::default().max_attempts(1)
+    }
+
+    /// Maximum number of attempts, including the first one. Defaults to 3
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry. Defaults to 200 milliseconds
+    pub fn initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Upper bound of the delay between attempts. Defaults to 5 seconds
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Whether to randomize the delay between attempts. Defaults to `true`
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Whether to retry connection errors and timeouts. Defaults to `true`
+    pub fn retry_network_errors(mut self, retry_network_errors: bool) -> Self {
+        self.retry_network_errors = retry_network_errors;
+        self
+    }
+
+    /// HTTP status codes, e.g. `503`, that are retried, replacing the defaults
+    pub fn retryable_status_codes<I: IntoIterator<Item = u16>>(mut self, status_codes: I) -> Self {
+        self.retryable_status_codes = status_codes.into_iter().collect();
+        self
+    }
+
+    /// M-Pesa error codes, e.g. `500.003.02`, that are retried whatever the HTTP status code,
+    /// replacing the defaults
+    pub fn retryable_error_codes<I, S>(mut self, error_codes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.retryable_error_codes = error_codes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    fn is_retryable(&self, failure: &Failure) -> bool {
+        if let Some(status) = failure.status {
+            if self.retryable_status_codes.contains(&status.as_u16()) {
+                return true;
+            }
+        }
+
+        match &failure.error {
+            MpesaError::NetworkError(e) => {
+                self.retry_network_errors && (e.is_connect() || e.is_timeout() || e.is_request())
+            }
+            MpesaError::Service(e) => {
+                self.retryable_error_codes.contains(&e.error_code)
+                    || MpesaResponseCode::from(e.error_code.as_str()).is_retryable()
+            }
+            _ => false,
+        }
+    }
+
+    /// Delay before the `retry`th retry, starting from 1
+    fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_backoff);
+
+        if self.jitter {
+            backoff.mul_f64(rand::random::<f64>())
+        } else {
+            backoff
+        }
+    }
+}
+
+/// A failed attempt, along with the HTTP status code of the response if one was received
+#[derive(Debug)]
+pub(crate) struct Failure {
+    pub status: Option<StatusCode>,
+    pub error: MpesaError,
+}
+
+impl From<MpesaError> for Failure {
+    fn from(error: MpesaError) -> Self {
+        Failure {
+            status: None,
+            error,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Failure {
+    fn from(error: reqwest::Error) -> Self {
+        MpesaError::from(error).into()
+    }
+}
+
+/// Runs `attempt` until it succeeds, fails with an error that is not retryable or the
+/// policy's maximum number of attempts is reached
+///
+/// Successful attempts for which `retry_value` returns `true` are retried as well, the last
+/// value is returned once the attempts are exhausted.
+pub(crate) async fn retry<T, F, Fut, P>(
+    policy: &RetryPolicy,
+    mut attempt: F,
+    retry_value: P,
+) -> MpesaResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Failure>>,
+    P: Fn(&T) -> bool,
+{
+    let mut attempts = 1;
+
+    loop {
+        match attempt().await {
+            Ok(value) if attempts < policy.max_attempts && retry_value(&value) => {
+                tokio::time::sleep(policy.backoff(attempts)).await;
+                attempts += 1;
+            }
+            Ok(value) => return Ok(value),
+            Err(failure) if attempts < policy.max_attempts && policy.is_retryable(&failure) => {
+                tokio::time::sleep(policy.backoff(attempts)).await;
+                attempts += 1;
+            }
+            Err(failure) => return Err(failure.error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ResponseError;
+
+    fn service_failure(status: StatusCode, error_code: &str) -> Failure {
+        Failure {
+            status: Some(status),
+            error: MpesaError::Service(ResponseError {
+                request_id: "11728-2929992-1".to_owned(),
+                error_code: error_code.to_owned(),
+                error_message: "error".to_owned(),
+                http: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        let policy = RetryPolicy::new()
+            .jitter(false)
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(500));
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(100), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_jitter_stays_within_backoff() {
+        let policy = RetryPolicy::new().initial_backoff(Duration::from_millis(100));
+
+        for _ in 0..100 {
+            assert!(policy.backoff(1) <= Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn test_retryable_failures() {
+        let policy = RetryPolicy::new();
+
+        assert!(policy.is_retryable(&service_failure(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "503.001.01"
+        )));
+        assert!(policy.is_retryable(&service_failure(StatusCode::BAD_REQUEST, SYSTEM_BUSY)));
+        assert!(!policy.is_retryable(&service_failure(StatusCode::BAD_REQUEST, "400.002.02")));
+        assert!(!policy.is_retryable(&MpesaError::Message("error".into()).into()));
+        // Transient M-Pesa response codes, e.g. traffic blocking
+        assert!(policy.is_retryable(&service_failure(StatusCode::BAD_REQUEST, "26")));
+
+        let policy = policy
+            .retryable_status_codes([])
+            .retryable_error_codes(["400.002.02"]);
+        assert!(!policy.is_retryable(&service_failure(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "503.001.01"
+        )));
+        assert!(policy.is_retryable(&service_failure(StatusCode::BAD_REQUEST, "400.002.02")));
+    }
+
+    #[test]
+    fn test_none_makes_a_single_attempt() {
+        assert_eq!(RetryPolicy::none().max_attempts, 1);
+        assert_eq!(RetryPolicy::new().max_attempts(0).max_attempts, 1);
+    }
+}
@@ -2,10 +2,10 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::constants::{CommandId, IdentifierTypes};
-use crate::{Mpesa, MpesaError, MpesaResult};
+use crate::constants::{CommandId, IdentifierTypes, MpesaResponseCode};
+use crate::{Mpesa, MpesaError, MpesaResult, Url};
 
-const ACCOUNT_BALANCE_URL: &str = "mpesa/accountbalance/v1/query";
+pub const ACCOUNT_BALANCE_URL: &str = "mpesa/accountbalance/v1/query";
 
 #[derive(Debug, Serialize)]
 /// Account Balance payload
@@ -17,15 +17,15 @@ struct AccountBalancePayload<'mpesa> {
     #[serde(rename(serialize = "CommandID"))]
     command_id: CommandId,
     #[serde(rename(serialize = "PartyA"))]
-    party_a: &'mpesa str,
+    party_a: String,
     #[serde(rename(serialize = "IdentifierType"))]
     identifier_type: &'mpesa str,
     #[serde(rename(serialize = "Remarks"))]
     remarks: &'mpesa str,
     #[serde(rename(serialize = "QueueTimeOutURL"))]
-    queue_time_out_url: &'mpesa str,
+    queue_time_out_url: Url,
     #[serde(rename(serialize = "ResultURL"))]
-    result_url: &'mpesa str,
+    result_url: Url,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -35,7 +35,7 @@ pub struct AccountBalanceResponse {
     #[serde(rename(deserialize = "OriginatorConversationID"))]
     pub originator_conversation_id: String,
     #[serde(rename(deserialize = "ResponseCode"))]
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     #[serde(rename(deserialize = "ResponseDescription"))]
     pub response_description: String,
 }
@@ -152,25 +152,30 @@ impl<'mpesa> AccountBalanceBuilder<'mpesa> {
     /// # Errors
     /// Returns a `MpesaError` on failure
     pub async fn send(self) -> MpesaResult<AccountBalanceResponse> {
-        let credentials = self.client.gen_security_credentials()?;
+        let credentials = self.client.security_credential()?;
+
+        let identifier_type = self.identifier_type.unwrap_or(IdentifierTypes::ShortCode);
 
         let payload = AccountBalancePayload {
             command_id: self.command_id.unwrap_or(CommandId::AccountBalance),
-            party_a: self
-                .party_a
-                .ok_or(MpesaError::Message("party_a is required"))?,
-            identifier_type: &self
-                .identifier_type
-                .unwrap_or(IdentifierTypes::ShortCode)
-                .to_string(),
-            remarks: self.remarks.unwrap_or_else(|| stringify!(None)),
+            party_a: identifier_type.validate_party(
+                "party_a",
+                self.party_a
+                    .ok_or(MpesaError::Message("party_a is required".into()))?,
+            )?,
+            identifier_type: &identifier_type.to_string(),
+            remarks: self.remarks.unwrap_or(stringify!(None)),
             initiator: self.initiator_name,
-            queue_time_out_url: self
-                .queue_timeout_url
-                .ok_or(MpesaError::Message("queue_timeout_url is required"))?,
-            result_url: self
-                .result_url
-                .ok_or(MpesaError::Message("result_url is required"))?,
+            queue_time_out_url: self.client.callback_url(
+                "queue_timeout_url",
+                self.queue_timeout_url
+                    .ok_or(MpesaError::Message("queue_timeout_url is required".into()))?,
+            )?,
+            result_url: self.client.callback_url(
+                "result_url",
+                self.result_url
+                    .ok_or(MpesaError::Message("result_url is required".into()))?,
+            )?,
             security_credential: &credentials,
         };
 
@@ -179,6 +184,9 @@ impl<'mpesa> AccountBalanceBuilder<'mpesa> {
                 method: reqwest::Method::POST,
                 path: ACCOUNT_BALANCE_URL,
                 body: payload,
+                // Not retried, the balance is delivered to the result url and a repeated request
+                // would start a second query and a duplicate callback
+                idempotent: false,
             })
             .await
     }
@@ -2,11 +2,13 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::amount::Amount;
 use crate::client::Mpesa;
-use crate::constants::{CommandId, IdentifierTypes};
+use crate::constants::{CommandId, IdentifierTypes, MpesaResponseCode};
 use crate::errors::{MpesaError, MpesaResult};
+use crate::url::Url;
 
-const B2B_URL: &str = "mpesa/b2b/v1/paymentrequest";
+pub const B2B_URL: &str = "mpesa/b2b/v1/paymentrequest";
 
 #[derive(Debug, Serialize)]
 struct B2bPayload<'mpesa> {
@@ -17,13 +19,13 @@ struct B2bPayload<'mpesa> {
     #[serde(rename(serialize = "CommandID"))]
     command_id: CommandId,
     #[serde(rename(serialize = "Amount"))]
-    amount: f64,
+    amount: Amount,
     #[serde(rename(serialize = "PartyA"))]
-    party_a: &'mpesa str,
+    party_a: String,
     #[serde(rename(serialize = "SenderIdentifierType"))]
     sender_identifier_type: &'mpesa str,
     #[serde(rename(serialize = "PartyB"))]
-    party_b: &'mpesa str,
+    party_b: String,
     #[serde(rename(serialize = "RecieverIdentifierType"))]
     reciever_identifier_type: &'mpesa str,
     #[serde(rename(serialize = "Remarks"))]
@@ -32,12 +34,12 @@ struct B2bPayload<'mpesa> {
         rename(serialize = "QueueTimeOutURL"),
         skip_serializing_if = "Option::is_none"
     )]
-    queue_time_out_url: Option<&'mpesa str>,
+    queue_time_out_url: Option<Url>,
     #[serde(
         rename(serialize = "ResultURL"),
         skip_serializing_if = "Option::is_none"
     )]
-    result_url: Option<&'mpesa str>,
+    result_url: Option<Url>,
     #[serde(
         rename(serialize = "AccountReference"),
         skip_serializing_if = "Option::is_none"
@@ -52,7 +54,7 @@ pub struct B2bResponse {
     #[serde(rename(deserialize = "OriginatorConversationID"))]
     pub originator_conversation_id: String,
     #[serde(rename(deserialize = "ResponseCode"))]
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     #[serde(rename(deserialize = "ResponseDescription"))]
     pub response_description: String,
 }
@@ -63,7 +65,7 @@ pub struct B2bBuilder<'mpesa> {
     initiator_name: &'mpesa str,
     client: &'mpesa Mpesa,
     command_id: Option<CommandId>,
-    amount: Option<f64>,
+    amount: Option<MpesaResult<Amount>>,
     party_a: Option<&'mpesa str>,
     sender_id: Option<IdentifierTypes>,
     party_b: Option<&'mpesa str>,
@@ -114,7 +116,7 @@ impl<'mpesa> B2bBuilder<'mpesa> {
     }
 
     /// Adds `Party B` which is a required field
-    /// `Party B` should be a mobile number.
+    /// `Party B` should be a short code, or a till number if `receiver_id` is `IdentifierTypes::TillNumber`.
     ///
     /// # Errors
     /// If `Party B` is invalid or not provided
@@ -186,8 +188,12 @@ impl<'mpesa> B2bBuilder<'mpesa> {
 
     /// Adds an `amount` to the request
     /// This is a required field
-    pub fn amount<Number: Into<f64>>(mut self, amount: Number) -> B2bBuilder<'mpesa> {
-        self.amount = Some(amount.into());
+    pub fn amount<A>(mut self, amount: A) -> B2bBuilder<'mpesa>
+    where
+        A: TryInto<Amount>,
+        MpesaError: From<A::Error>,
+    {
+        self.amount = Some(amount.try_into().map_err(MpesaError::from));
         self
     }
 
@@ -211,7 +217,10 @@ impl<'mpesa> B2bBuilder<'mpesa> {
     /// # Errors
     /// Returns a `MpesaError` on failure
     pub async fn send(self) -> MpesaResult<B2bResponse> {
-        let credentials = self.client.gen_security_credentials()?;
+        let credentials = self.client.security_credential()?;
+
+        let sender_id = self.sender_id.unwrap_or(IdentifierTypes::ShortCode);
+        let receiver_id = self.receiver_id.unwrap_or(IdentifierTypes::ShortCode);
 
         let payload = B2bPayload {
             initiator: self.initiator_name,
@@ -221,24 +230,28 @@ impl<'mpesa> B2bBuilder<'mpesa> {
                 .unwrap_or(CommandId::BusinessToBusinessTransfer),
             amount: self
                 .amount
-                .ok_or(MpesaError::Message("amount is required"))?,
-            party_a: self
-                .party_a
-                .ok_or(MpesaError::Message("party_a is required"))?,
-            sender_identifier_type: &self
-                .sender_id
-                .unwrap_or(IdentifierTypes::ShortCode)
-                .to_string(),
-            party_b: self
-                .party_b
-                .ok_or(MpesaError::Message("party_b is required"))?,
-            reciever_identifier_type: &self
-                .receiver_id
-                .unwrap_or(IdentifierTypes::ShortCode)
-                .to_string(),
-            remarks: self.remarks.unwrap_or_else(|| stringify!(None)),
-            queue_time_out_url: self.queue_timeout_url,
-            result_url: self.result_url,
+                .ok_or(MpesaError::Message("amount is required".into()))??,
+            party_a: sender_id.validate_party(
+                "party_a",
+                self.party_a
+                    .ok_or(MpesaError::Message("party_a is required".into()))?,
+            )?,
+            sender_identifier_type: &sender_id.to_string(),
+            party_b: receiver_id.validate_party(
+                "party_b",
+                self.party_b
+                    .ok_or(MpesaError::Message("party_b is required".into()))?,
+            )?,
+            reciever_identifier_type: &receiver_id.to_string(),
+            remarks: self.remarks.unwrap_or(stringify!(None)),
+            queue_time_out_url: self
+                .queue_timeout_url
+                .map(|url| self.client.callback_url("queue_timeout_url", url))
+                .transpose()?,
+            result_url: self
+                .result_url
+                .map(|url| self.client.callback_url("result_url", url))
+                .transpose()?,
             account_reference: self.account_ref,
         };
 
@@ -247,6 +260,7 @@ impl<'mpesa> B2bBuilder<'mpesa> {
                 method: reqwest::Method::POST,
                 path: B2B_URL,
                 body: payload,
+                idempotent: false,
             })
             .await
     }
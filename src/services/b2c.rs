@@ -1,10 +1,15 @@
 #![doc = include_str!("../../docs/client/b2c.md")]
 
+use std::marker::PhantomData;
+
 use serde::{Deserialize, Serialize};
 
-use crate::{CommandId, Mpesa, MpesaError, MpesaResult};
+use crate::services::{Set, Unset};
+use crate::{
+    Amount, CommandId, Mpesa, MpesaError, MpesaResponseCode, MpesaResult, Msisdn, ShortCode, Url,
+};
 
-const B2C_URL: &str = "mpesa/b2c/v1/paymentrequest";
+pub const B2C_URL: &str = "mpesa/b2c/v1/paymentrequest";
 
 #[derive(Debug, Serialize)]
 /// Payload to allow for b2c transactions:
@@ -16,17 +21,17 @@ struct B2cPayload<'mpesa> {
     #[serde(rename(serialize = "CommandID"))]
     command_id: CommandId,
     #[serde(rename(serialize = "Amount"))]
-    amount: f64,
+    amount: Amount,
     #[serde(rename(serialize = "PartyA"))]
-    party_a: &'mpesa str,
+    party_a: ShortCode,
     #[serde(rename(serialize = "PartyB"))]
-    party_b: &'mpesa str,
+    party_b: Msisdn,
     #[serde(rename(serialize = "Remarks"))]
     remarks: &'mpesa str,
     #[serde(rename(serialize = "QueueTimeOutURL"))]
-    queue_time_out_url: &'mpesa str,
+    queue_time_out_url: Url,
     #[serde(rename(serialize = "ResultURL"))]
-    result_url: &'mpesa str,
+    result_url: Url,
     #[serde(rename(serialize = "Occasion"))]
     occasion: &'mpesa str,
 }
@@ -38,7 +43,7 @@ pub struct B2cResponse {
     #[serde(rename(deserialize = "OriginatorConversationID"))]
     pub originator_conversation_id: String,
     #[serde(rename(deserialize = "ResponseCode"))]
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     #[serde(rename(deserialize = "ResponseDescription"))]
     pub response_description: String,
 }
@@ -49,9 +54,9 @@ pub struct B2cBuilder<'mpesa> {
     initiator_name: &'mpesa str,
     client: &'mpesa Mpesa,
     command_id: Option<CommandId>,
-    amount: Option<f64>,
+    amount: Option<MpesaResult<Amount>>,
     party_a: Option<&'mpesa str>,
-    party_b: Option<&'mpesa str>,
+    party_b: Option<MpesaResult<Msisdn>>,
     remarks: Option<&'mpesa str>,
     queue_timeout_url: Option<&'mpesa str>,
     result_url: Option<&'mpesa str>,
@@ -97,8 +102,16 @@ impl<'mpesa> B2cBuilder<'mpesa> {
     ///
     /// # Errors
     /// If `Party B` is invalid or not provided
-    pub fn party_b(mut self, party_b: &'mpesa str) -> B2cBuilder<'mpesa> {
-        self.party_b = Some(party_b);
+    pub fn party_b<P>(mut self, party_b: P) -> B2cBuilder<'mpesa>
+    where
+        P: TryInto<Msisdn>,
+        MpesaError: From<P::Error>,
+    {
+        self.party_b = Some(
+            party_b
+                .try_into()
+                .map_err(|e| MpesaError::from(e).for_field("party_b")),
+        );
         self
     }
 
@@ -109,9 +122,8 @@ impl<'mpesa> B2cBuilder<'mpesa> {
     /// If either `Party A` or `Party B` is invalid or not provided
     #[deprecated]
     pub fn parties(mut self, party_a: &'mpesa str, party_b: &'mpesa str) -> B2cBuilder<'mpesa> {
-        // TODO: add validation
         self.party_a = Some(party_a);
-        self.party_b = Some(party_b);
+        self.party_b = Some(Msisdn::parse(party_b).map_err(|e| e.for_field("party_b")));
         self
     }
 
@@ -129,8 +141,12 @@ impl<'mpesa> B2cBuilder<'mpesa> {
 
     /// Adds an `amount` to the request
     /// This is a required field
-    pub fn amount<Number: Into<f64>>(mut self, amount: Number) -> B2cBuilder<'mpesa> {
-        self.amount = Some(amount.into());
+    pub fn amount<A>(mut self, amount: A) -> B2cBuilder<'mpesa>
+    where
+        A: TryInto<Amount>,
+        MpesaError: From<A::Error>,
+    {
+        self.amount = Some(amount.try_into().map_err(MpesaError::from));
         self
     }
 
@@ -158,7 +174,6 @@ impl<'mpesa> B2cBuilder<'mpesa> {
     /// If either `QueueTimeoutUrl` and `ResultUrl` is invalid or not provided
     #[deprecated]
     pub fn urls(mut self, timeout_url: &'mpesa str, result_url: &'mpesa str) -> B2cBuilder<'mpesa> {
-        // TODO: validate urls; will probably return a `Result` from this
         self.queue_timeout_url = Some(timeout_url);
         self.result_url = Some(result_url);
         self
@@ -178,7 +193,7 @@ impl<'mpesa> B2cBuilder<'mpesa> {
     /// # Errors
     /// Returns a `MpesaError` on failure.
     pub async fn send(self) -> MpesaResult<B2cResponse> {
-        let credentials = self.client.gen_security_credentials()?;
+        let credentials = self.client.security_credential()?;
 
         let payload = B2cPayload {
             initiator_name: self.initiator_name,
@@ -186,21 +201,27 @@ impl<'mpesa> B2cBuilder<'mpesa> {
             command_id: self.command_id.unwrap_or(CommandId::BusinessPayment),
             amount: self
                 .amount
-                .ok_or(MpesaError::Message("amount is required"))?,
-            party_a: self
-                .party_a
-                .ok_or(MpesaError::Message("party_a is required"))?,
+                .ok_or(MpesaError::Message("amount is required".into()))??,
+            party_a: ShortCode::parse(
+                self.party_a
+                    .ok_or(MpesaError::Message("party_a is required".into()))?,
+            )
+            .map_err(|e| e.for_field("party_a"))?,
             party_b: self
                 .party_b
-                .ok_or(MpesaError::Message("party_b is required"))?,
-            remarks: self.remarks.unwrap_or_else(|| stringify!(None)),
-            queue_time_out_url: self
-                .queue_timeout_url
-                .ok_or(MpesaError::Message("queue_timeout_url is required"))?,
-            result_url: self
-                .result_url
-                .ok_or(MpesaError::Message("result_url is required"))?,
-            occasion: self.occasion.unwrap_or_else(|| stringify!(None)),
+                .ok_or(MpesaError::Message("party_b is required".into()))??,
+            remarks: self.remarks.unwrap_or(stringify!(None)),
+            queue_time_out_url: self.client.callback_url(
+                "queue_timeout_url",
+                self.queue_timeout_url
+                    .ok_or(MpesaError::Message("queue_timeout_url is required".into()))?,
+            )?,
+            result_url: self.client.callback_url(
+                "result_url",
+                self.result_url
+                    .ok_or(MpesaError::Message("result_url is required".into()))?,
+            )?,
+            occasion: self.occasion.unwrap_or(stringify!(None)),
         };
 
         self.client
@@ -208,7 +229,152 @@ impl<'mpesa> B2cBuilder<'mpesa> {
                 method: reqwest::Method::POST,
                 path: B2C_URL,
                 body: payload,
+                idempotent: false,
             })
             .await
     }
 }
+
+/// B2C transaction builder whose `send` method only exists once every required field is set
+///
+/// Each type parameter tracks a required field, in order `amount`, `party_a`, `party_b`,
+/// `timeout_url` and `result_url`, and is either [`Set`] or [`Unset`]. Leaving a required field
+/// out is a compiler error rather than a `MpesaError::Message`, the values themselves are still
+/// validated when the request is sent. Use [`B2cBuilder`] when the fields are only known at
+/// runtime.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # async fn run(client: mpesa::Mpesa) -> mpesa::MpesaResult<()> {
+/// let response = client
+///     .b2c_typed("testapi496")
+///     .party_a("600496")
+///     .party_b("254708374149")
+///     .result_url("https://testdomain.com/ok")
+///     .timeout_url("https://testdomain.com/err")
+///     .amount(1000)
+///     .send()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Forgetting `result_url` does not compile:
+///
+/// ```rust,compile_fail
+/// # async fn run(client: mpesa::Mpesa) -> mpesa::MpesaResult<()> {
+/// let response = client
+///     .b2c_typed("testapi496")
+///     .party_a("600496")
+///     .party_b("254708374149")
+///     .timeout_url("https://testdomain.com/err")
+///     .amount(1000)
+///     .send()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TypedB2cBuilder<'mpesa, A = Unset, PA = Unset, PB = Unset, T = Unset, R = Unset> {
+    inner: B2cBuilder<'mpesa>,
+    state: PhantomData<(A, PA, PB, T, R)>,
+}
+
+impl<'mpesa> TypedB2cBuilder<'mpesa> {
+    /// Create a new typed B2C builder.
+    /// Requires an `initiator_name`, the credential/ username used to authenticate the transaction request
+    pub fn new(client: &'mpesa Mpesa, initiator_name: &'mpesa str) -> TypedB2cBuilder<'mpesa> {
+        TypedB2cBuilder {
+            inner: B2cBuilder::new(client, initiator_name),
+            state: PhantomData,
+        }
+    }
+}
+
+impl<'mpesa, A, PA, PB, T, R> TypedB2cBuilder<'mpesa, A, PA, PB, T, R> {
+    /// Adds the `CommandId`. Defaults to `CommandId::BusinessPayment` if not explicitly provided.
+    pub fn command_id(self, command_id: CommandId) -> Self {
+        TypedB2cBuilder {
+            inner: self.inner.command_id(command_id),
+            state: PhantomData,
+        }
+    }
+
+    /// Adds `Remarks`. This is an optional field, will default to "None" if not explicitly provided
+    pub fn remarks(self, remarks: &'mpesa str) -> Self {
+        TypedB2cBuilder {
+            inner: self.inner.remarks(remarks),
+            state: PhantomData,
+        }
+    }
+
+    /// Adds `Occasion`. This is an optional field, will default to an empty string
+    pub fn occasion(self, occasion: &'mpesa str) -> Self {
+        TypedB2cBuilder {
+            inner: self.inner.occasion(occasion),
+            state: PhantomData,
+        }
+    }
+
+    /// Adds an `amount` to the request. This is a required field
+    pub fn amount<V>(self, amount: V) -> TypedB2cBuilder<'mpesa, Set, PA, PB, T, R>
+    where
+        V: TryInto<Amount>,
+        MpesaError: From<V::Error>,
+    {
+        TypedB2cBuilder {
+            inner: self.inner.amount(amount),
+            state: PhantomData,
+        }
+    }
+
+    /// Adds `Party A`, a paybill number. This is a required field
+    pub fn party_a(self, party_a: &'mpesa str) -> TypedB2cBuilder<'mpesa, A, Set, PB, T, R> {
+        TypedB2cBuilder {
+            inner: self.inner.party_a(party_a),
+            state: PhantomData,
+        }
+    }
+
+    /// Adds `Party B`, a mobile number. This is a required field
+    pub fn party_b<V>(self, party_b: V) -> TypedB2cBuilder<'mpesa, A, PA, Set, T, R>
+    where
+        V: TryInto<Msisdn>,
+        MpesaError: From<V::Error>,
+    {
+        TypedB2cBuilder {
+            inner: self.inner.party_b(party_b),
+            state: PhantomData,
+        }
+    }
+
+    /// Adds `QueueTimeoutUrl`. This is a required field
+    pub fn timeout_url(
+        self,
+        timeout_url: &'mpesa str,
+    ) -> TypedB2cBuilder<'mpesa, A, PA, PB, Set, R> {
+        TypedB2cBuilder {
+            inner: self.inner.timeout_url(timeout_url),
+            state: PhantomData,
+        }
+    }
+
+    /// Adds `ResultUrl`. This is a required field
+    pub fn result_url(self, result_url: &'mpesa str) -> TypedB2cBuilder<'mpesa, A, PA, PB, T, Set> {
+        TypedB2cBuilder {
+            inner: self.inner.result_url(result_url),
+            state: PhantomData,
+        }
+    }
+}
+
+impl<'mpesa> TypedB2cBuilder<'mpesa, Set, Set, Set, Set, Set> {
+    /// Sends the b2c payment request, see [`B2cBuilder::send`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError` on failure.
+    pub async fn send(self) -> MpesaResult<B2cResponse> {
+        self.inner.send().await
+    }
+}
@@ -3,15 +3,15 @@
 use serde::Deserialize;
 
 use crate::client::Mpesa;
-use crate::constants::Invoice;
+use crate::constants::{Invoice, MpesaResponseCode};
 use crate::errors::{MpesaError, MpesaResult};
 
-const BILL_MANAGER_BULK_INVOICE_API_URL: &str = "v1/billmanager-invoice/bulk-invoicing";
+pub const BILL_MANAGER_BULK_INVOICE_API_URL: &str = "v1/billmanager-invoice/bulk-invoicing";
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct BulkInvoiceResponse {
     #[serde(rename(deserialize = "rescode"))]
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     #[serde(rename(deserialize = "resmsg"))]
     pub response_message: String,
     #[serde(rename(deserialize = "Status_Message"))]
@@ -53,7 +53,7 @@ impl<'mpesa> BulkInvoiceBuilder<'mpesa> {
     /// Returns an `MpesaError` on failure.
     pub async fn send(self) -> MpesaResult<BulkInvoiceResponse> {
         if self.invoices.is_empty() {
-            return Err(MpesaError::Message("invoices cannot be empty"));
+            return Err(MpesaError::Message("invoices cannot be empty".into()));
         }
 
         self.client
@@ -61,6 +61,7 @@ impl<'mpesa> BulkInvoiceBuilder<'mpesa> {
                 method: reqwest::Method::POST,
                 path: BILL_MANAGER_BULK_INVOICE_API_URL,
                 body: self.invoices,
+                idempotent: false,
             })
             .await
     }
@@ -3,9 +3,11 @@
 use serde::{Deserialize, Serialize};
 
 use crate::client::Mpesa;
+use crate::constants::MpesaResponseCode;
 use crate::errors::MpesaResult;
 
-const BILL_MANAGER_CANCEL_INVOICE_API_URL: &str = "v1/billmanager-invoice/cancel-single-invoice";
+pub const BILL_MANAGER_CANCEL_INVOICE_API_URL: &str =
+    "v1/billmanager-invoice/cancel-single-invoice";
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -16,7 +18,7 @@ struct CancelInvoicePayload<'mpesa> {
 #[derive(Clone, Debug, Deserialize)]
 pub struct CancelInvoiceResponse {
     #[serde(rename(deserialize = "rescode"))]
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     #[serde(rename(deserialize = "resmsg"))]
     pub response_message: String,
     #[serde(rename(deserialize = "Status_Message"))]
@@ -76,6 +78,7 @@ impl<'mpesa> CancelInvoiceBuilder<'mpesa> {
                 method: reqwest::Method::POST,
                 path: BILL_MANAGER_CANCEL_INVOICE_API_URL,
                 body: self.external_references,
+                idempotent: false,
             })
             .await
     }
@@ -5,9 +5,20 @@ mod onboard_modify;
 mod reconciliation;
 mod single_invoice;
 
-pub use bulk_invoice::{BulkInvoiceBuilder, BulkInvoiceResponse};
-pub use cancel_invoice::{CancelInvoiceBuilder, CancelInvoiceResponse};
-pub use onboard::{OnboardBuilder, OnboardResponse};
-pub use onboard_modify::{OnboardModifyBuilder, OnboardModifyResponse};
-pub use reconciliation::{ReconciliationBuilder, ReconciliationResponse};
-pub use single_invoice::{SingleInvoiceBuilder, SingleInvoiceResponse};
+pub use bulk_invoice::{
+    BulkInvoiceBuilder, BulkInvoiceResponse, BILL_MANAGER_BULK_INVOICE_API_URL,
+};
+pub use cancel_invoice::{
+    CancelInvoiceBuilder, CancelInvoiceResponse, BILL_MANAGER_CANCEL_INVOICE_API_URL,
+};
+pub use onboard::{OnboardBuilder, OnboardResponse, BILL_MANAGER_ONBOARD_API_URL};
+pub use onboard_modify::{
+    OnboardModifyBuilder, OnboardModifyResponse, BILL_MANAGER_ONBOARD_MODIFY_API_URL,
+};
+pub use reconciliation::{
+    ReconciliationBuilder, ReconciliationResponse, TypedReconciliationBuilder,
+    BILL_MANAGER_RECONCILIATION_API_URL,
+};
+pub use single_invoice::{
+    SingleInvoiceBuilder, SingleInvoiceResponse, BILL_MANAGER_SINGLE_INVOICE_API_URL,
+};
@@ -3,16 +3,18 @@
 use serde::{Deserialize, Serialize};
 
 use crate::client::Mpesa;
-use crate::constants::SendRemindersTypes;
+use crate::constants::{MpesaResponseCode, SendRemindersTypes};
 use crate::errors::{MpesaError, MpesaResult};
+use crate::short_code::ShortCode;
+use crate::url::Url;
 
-const BILL_MANAGER_ONBOARD_API_URL: &str = "v1/billmanager-invoice/optin";
+pub const BILL_MANAGER_ONBOARD_API_URL: &str = "v1/billmanager-invoice/optin";
 
 #[derive(Debug, Serialize)]
 /// Payload to opt you in as a biller to the bill manager features.
 struct OnboardPayload<'mpesa> {
     #[serde(rename(serialize = "callbackUrl"))]
-    callback_url: &'mpesa str,
+    callback_url: Url,
     email: &'mpesa str,
     logo: &'mpesa str,
     #[serde(rename(serialize = "officialContact"))]
@@ -20,7 +22,7 @@ struct OnboardPayload<'mpesa> {
     #[serde(rename(serialize = "sendReminders"))]
     send_reminders: SendRemindersTypes,
     #[serde(rename(serialize = "shortcode"))]
-    short_code: &'mpesa str,
+    short_code: ShortCode,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -28,7 +30,7 @@ pub struct OnboardResponse {
     #[serde(rename(deserialize = "app_key"))]
     pub app_key: String,
     #[serde(rename(deserialize = "rescode"))]
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     #[serde(rename(deserialize = "resmsg"))]
     pub response_message: String,
 }
@@ -122,18 +124,26 @@ impl<'mpesa> OnboardBuilder<'mpesa> {
     /// Returns an `MpesaError` on failure
     pub async fn send(self) -> MpesaResult<OnboardResponse> {
         let payload = OnboardPayload {
-            callback_url: self
-                .callback_url
-                .ok_or(MpesaError::Message("callback_url is required"))?,
-            email: self.email.ok_or(MpesaError::Message("email is required"))?,
-            logo: self.logo.ok_or(MpesaError::Message("logo is required"))?,
+            callback_url: self.client.callback_url(
+                "callback_url",
+                self.callback_url
+                    .ok_or(MpesaError::Message("callback_url is required".into()))?,
+            )?,
+            email: self
+                .email
+                .ok_or(MpesaError::Message("email is required".into()))?,
+            logo: self
+                .logo
+                .ok_or(MpesaError::Message("logo is required".into()))?,
             official_contact: self
                 .official_contact
-                .ok_or(MpesaError::Message("official_contact is required"))?,
+                .ok_or(MpesaError::Message("official_contact is required".into()))?,
             send_reminders: self.send_reminders.unwrap_or(SendRemindersTypes::Disable),
-            short_code: self
-                .short_code
-                .ok_or(MpesaError::Message("short_code is required"))?,
+            short_code: ShortCode::parse(
+                self.short_code
+                    .ok_or(MpesaError::Message("short_code is required".into()))?,
+            )
+            .map_err(|e| e.for_field("short_code"))?,
         };
 
         self.client
@@ -141,6 +151,7 @@ impl<'mpesa> OnboardBuilder<'mpesa> {
                 method: reqwest::Method::POST,
                 path: BILL_MANAGER_ONBOARD_API_URL,
                 body: payload,
+                idempotent: false,
             })
             .await
     }
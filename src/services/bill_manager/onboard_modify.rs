@@ -3,10 +3,12 @@
 use serde::{Deserialize, Serialize};
 
 use crate::client::Mpesa;
-use crate::constants::SendRemindersTypes;
+use crate::constants::{MpesaResponseCode, SendRemindersTypes};
 use crate::errors::MpesaResult;
+use crate::short_code::ShortCode;
+use crate::url::Url;
 
-const BILL_MANAGER_ONBOARD_MODIFY_API_URL: &str = "v1/billmanager-invoice/change-optin-details";
+pub const BILL_MANAGER_ONBOARD_MODIFY_API_URL: &str = "v1/billmanager-invoice/change-optin-details";
 
 #[derive(Debug, Serialize)]
 /// Payload to modify opt-in details to the bill manager api.
@@ -15,7 +17,7 @@ struct OnboardModifyPayload<'mpesa> {
         rename(serialize = "callbackUrl"),
         skip_serializing_if = "Option::is_none"
     )]
-    callback_url: Option<&'mpesa str>,
+    callback_url: Option<Url>,
     #[serde(rename(serialize = "email"), skip_serializing_if = "Option::is_none")]
     email: Option<&'mpesa str>,
     #[serde(rename(serialize = "logo"), skip_serializing_if = "Option::is_none")]
@@ -34,13 +36,13 @@ struct OnboardModifyPayload<'mpesa> {
         rename(serialize = "shortcode"),
         skip_serializing_if = "Option::is_none"
     )]
-    short_code: Option<&'mpesa str>,
+    short_code: Option<ShortCode>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct OnboardModifyResponse {
     #[serde(rename(deserialize = "rescode"))]
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     #[serde(rename(deserialize = "resmsg"))]
     pub response_message: String,
 }
@@ -122,12 +124,20 @@ impl<'mpesa> OnboardModifyBuilder<'mpesa> {
     /// Returns an `MpesaError` on failure
     pub async fn send(self) -> MpesaResult<OnboardModifyResponse> {
         let payload = OnboardModifyPayload {
-            callback_url: self.callback_url,
+            callback_url: self
+                .callback_url
+                .map(|url| self.client.callback_url("callback_url", url))
+                .transpose()?,
             email: self.email,
             logo: self.logo,
             official_contact: self.official_contact,
             send_reminders: self.send_reminders,
-            short_code: self.short_code,
+            short_code: self
+                .short_code
+                .map(|short_code| {
+                    ShortCode::parse(short_code).map_err(|e| e.for_field("short_code"))
+                })
+                .transpose()?,
         };
 
         self.client
@@ -135,6 +145,7 @@ impl<'mpesa> OnboardModifyBuilder<'mpesa> {
                 method: reqwest::Method::POST,
                 path: BILL_MANAGER_ONBOARD_MODIFY_API_URL,
                 body: payload,
+                idempotent: false,
             })
             .await
     }
@@ -1,12 +1,18 @@
 #![doc = include_str!("../../../docs/client/bill_manager/reconciliation.md")]
 
+use std::marker::PhantomData;
+
 use chrono::prelude::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::amount::Amount;
 use crate::client::Mpesa;
+use crate::constants::MpesaResponseCode;
 use crate::errors::{MpesaError, MpesaResult};
+use crate::msisdn::Msisdn;
+use crate::services::{Set, Unset};
 
-const BILL_MANAGER_RECONCILIATION_API_URL: &str = "v1/billmanager-invoice/reconciliation";
+pub const BILL_MANAGER_RECONCILIATION_API_URL: &str = "v1/billmanager-invoice/reconciliation";
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -15,16 +21,17 @@ struct ReconciliationPayload<'mpesa> {
     external_reference: &'mpesa str,
     full_name: &'mpesa str,
     invoice_name: &'mpesa str,
-    paid_amount: f64,
+    paid_amount: Amount,
     payment_date: DateTime<Utc>,
-    phone_number: &'mpesa str,
+    #[serde(serialize_with = "Msisdn::serialize_local")]
+    phone_number: Msisdn,
     transaction_id: &'mpesa str,
 }
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct ReconciliationResponse {
     #[serde(rename(deserialize = "rescode"))]
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     #[serde(rename(deserialize = "resmsg"))]
     pub response_message: String,
 }
@@ -36,9 +43,9 @@ pub struct ReconciliationBuilder<'mpesa> {
     external_reference: Option<&'mpesa str>,
     full_name: Option<&'mpesa str>,
     invoice_name: Option<&'mpesa str>,
-    paid_amount: Option<f64>,
+    paid_amount: Option<MpesaResult<Amount>>,
     payment_date: Option<DateTime<Utc>>,
-    phone_number: Option<&'mpesa str>,
+    phone_number: Option<MpesaResult<Msisdn>>,
     transaction_id: Option<&'mpesa str>,
 }
 
@@ -89,11 +96,12 @@ impl<'mpesa> ReconciliationBuilder<'mpesa> {
     }
 
     /// Adds `paid_amount`
-    pub fn paid_amount<Number: Into<f64>>(
-        mut self,
-        paid_amount: Number,
-    ) -> ReconciliationBuilder<'mpesa> {
-        self.paid_amount = Some(paid_amount.into());
+    pub fn paid_amount<A>(mut self, paid_amount: A) -> ReconciliationBuilder<'mpesa>
+    where
+        A: TryInto<Amount>,
+        MpesaError: From<A::Error>,
+    {
+        self.paid_amount = Some(paid_amount.try_into().map_err(MpesaError::from));
         self
     }
 
@@ -104,8 +112,16 @@ impl<'mpesa> ReconciliationBuilder<'mpesa> {
     }
 
     /// Adds `phone_number`
-    pub fn phone_number(mut self, phone_number: &'mpesa str) -> ReconciliationBuilder<'mpesa> {
-        self.phone_number = Some(phone_number);
+    pub fn phone_number<P>(mut self, phone_number: P) -> ReconciliationBuilder<'mpesa>
+    where
+        P: TryInto<Msisdn>,
+        MpesaError: From<P::Error>,
+    {
+        self.phone_number = Some(
+            phone_number
+                .try_into()
+                .map_err(|e| MpesaError::from(e).for_field("phone_number")),
+        );
         self
     }
 
@@ -127,28 +143,28 @@ impl<'mpesa> ReconciliationBuilder<'mpesa> {
         let payload = ReconciliationPayload {
             account_reference: self
                 .account_reference
-                .ok_or(MpesaError::Message("account_reference is required"))?,
+                .ok_or(MpesaError::Message("account_reference is required".into()))?,
             external_reference: self
                 .external_reference
-                .ok_or(MpesaError::Message("external_reference is required"))?,
+                .ok_or(MpesaError::Message("external_reference is required".into()))?,
             full_name: self
                 .full_name
-                .ok_or(MpesaError::Message("full_name is required"))?,
+                .ok_or(MpesaError::Message("full_name is required".into()))?,
             invoice_name: self
                 .invoice_name
-                .ok_or(MpesaError::Message("invoice_name is required"))?,
+                .ok_or(MpesaError::Message("invoice_name is required".into()))?,
             paid_amount: self
                 .paid_amount
-                .ok_or(MpesaError::Message("paid_amount is required"))?,
+                .ok_or(MpesaError::Message("paid_amount is required".into()))??,
             payment_date: self
                 .payment_date
-                .ok_or(MpesaError::Message("payment_date is required"))?,
+                .ok_or(MpesaError::Message("payment_date is required".into()))?,
             phone_number: self
                 .phone_number
-                .ok_or(MpesaError::Message("phone_number is required"))?,
+                .ok_or(MpesaError::Message("phone_number is required".into()))??,
             transaction_id: self
                 .transaction_id
-                .ok_or(MpesaError::Message("transaction_id is required"))?,
+                .ok_or(MpesaError::Message("transaction_id is required".into()))?,
         };
 
         self.client
@@ -156,7 +172,171 @@ impl<'mpesa> ReconciliationBuilder<'mpesa> {
                 method: reqwest::Method::POST,
                 path: BILL_MANAGER_RECONCILIATION_API_URL,
                 body: payload,
+                idempotent: false,
             })
             .await
     }
 }
+
+/// Bill Manager reconciliation builder whose `send` method only exists once every field is set
+///
+/// Each type parameter tracks a required field, in the order of the setters below, and is either
+/// [`Set`] or [`Unset`]. Leaving a field out is a compiler error rather than a
+/// `MpesaError::Message`, the values themselves are still validated when the request is sent. Use
+/// [`ReconciliationBuilder`] when the fields are only known at runtime.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # async fn run(client: mpesa::Mpesa) -> mpesa::MpesaResult<()> {
+/// let response = client
+///     .reconciliation_typed()
+///     .account_reference("John Doe")
+///     .external_reference("INV2345")
+///     .full_name("John Doe")
+///     .invoice_name("Invoice 01")
+///     .paid_amount(1000)
+///     .payment_date(chrono::Utc::now())
+///     .phone_number("0712345678")
+///     .transaction_id("TRANSACTION_ID")
+///     .send()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TypedReconciliationBuilder<
+    'mpesa,
+    AR = Unset,
+    ER = Unset,
+    FN = Unset,
+    IN = Unset,
+    PA = Unset,
+    PD = Unset,
+    PN = Unset,
+    TI = Unset,
+> {
+    inner: ReconciliationBuilder<'mpesa>,
+    #[allow(clippy::type_complexity)]
+    state: PhantomData<(AR, ER, FN, IN, PA, PD, PN, TI)>,
+}
+
+impl<'mpesa> TypedReconciliationBuilder<'mpesa> {
+    /// Creates a new typed Bill Manager Reconciliation Builder
+    pub fn new(client: &'mpesa Mpesa) -> TypedReconciliationBuilder<'mpesa> {
+        TypedReconciliationBuilder {
+            inner: ReconciliationBuilder::new(client),
+            state: PhantomData,
+        }
+    }
+}
+
+impl<'mpesa, AR, ER, FN, IN, PA, PD, PN, TI>
+    TypedReconciliationBuilder<'mpesa, AR, ER, FN, IN, PA, PD, PN, TI>
+{
+    /// Adds `account_reference`
+    pub fn account_reference(
+        self,
+        account_reference: &'mpesa str,
+    ) -> TypedReconciliationBuilder<'mpesa, Set, ER, FN, IN, PA, PD, PN, TI> {
+        TypedReconciliationBuilder {
+            inner: self.inner.account_reference(account_reference),
+            state: PhantomData,
+        }
+    }
+
+    /// Adds `external_reference`
+    pub fn external_reference(
+        self,
+        external_reference: &'mpesa str,
+    ) -> TypedReconciliationBuilder<'mpesa, AR, Set, FN, IN, PA, PD, PN, TI> {
+        TypedReconciliationBuilder {
+            inner: self.inner.external_reference(external_reference),
+            state: PhantomData,
+        }
+    }
+
+    /// Adds `full_name`
+    pub fn full_name(
+        self,
+        full_name: &'mpesa str,
+    ) -> TypedReconciliationBuilder<'mpesa, AR, ER, Set, IN, PA, PD, PN, TI> {
+        TypedReconciliationBuilder {
+            inner: self.inner.full_name(full_name),
+            state: PhantomData,
+        }
+    }
+
+    /// Adds `invoice_name`
+    pub fn invoice_name(
+        self,
+        invoice_name: &'mpesa str,
+    ) -> TypedReconciliationBuilder<'mpesa, AR, ER, FN, Set, PA, PD, PN, TI> {
+        TypedReconciliationBuilder {
+            inner: self.inner.invoice_name(invoice_name),
+            state: PhantomData,
+        }
+    }
+
+    /// Adds `paid_amount`
+    pub fn paid_amount<V>(
+        self,
+        paid_amount: V,
+    ) -> TypedReconciliationBuilder<'mpesa, AR, ER, FN, IN, Set, PD, PN, TI>
+    where
+        V: TryInto<Amount>,
+        MpesaError: From<V::Error>,
+    {
+        TypedReconciliationBuilder {
+            inner: self.inner.paid_amount(paid_amount),
+            state: PhantomData,
+        }
+    }
+
+    /// Adds `payment_date`
+    pub fn payment_date(
+        self,
+        payment_date: DateTime<Utc>,
+    ) -> TypedReconciliationBuilder<'mpesa, AR, ER, FN, IN, PA, Set, PN, TI> {
+        TypedReconciliationBuilder {
+            inner: self.inner.payment_date(payment_date),
+            state: PhantomData,
+        }
+    }
+
+    /// Adds `phone_number`
+    pub fn phone_number<V>(
+        self,
+        phone_number: V,
+    ) -> TypedReconciliationBuilder<'mpesa, AR, ER, FN, IN, PA, PD, Set, TI>
+    where
+        V: TryInto<Msisdn>,
+        MpesaError: From<V::Error>,
+    {
+        TypedReconciliationBuilder {
+            inner: self.inner.phone_number(phone_number),
+            state: PhantomData,
+        }
+    }
+
+    /// Adds `transaction_id`
+    pub fn transaction_id(
+        self,
+        transaction_id: &'mpesa str,
+    ) -> TypedReconciliationBuilder<'mpesa, AR, ER, FN, IN, PA, PD, PN, Set> {
+        TypedReconciliationBuilder {
+            inner: self.inner.transaction_id(transaction_id),
+            state: PhantomData,
+        }
+    }
+}
+
+impl<'mpesa> TypedReconciliationBuilder<'mpesa, Set, Set, Set, Set, Set, Set, Set, Set> {
+    /// Bill Manager Reconciliation API, see [`ReconciliationBuilder::send`]
+    ///
+    /// # Errors
+    /// Returns an `MpesaError` on failure.
+    pub async fn send(self) -> MpesaResult<ReconciliationResponse> {
+        self.inner.send().await
+    }
+}
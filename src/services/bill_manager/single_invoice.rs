@@ -3,16 +3,18 @@
 use chrono::prelude::{DateTime, Utc};
 use serde::Deserialize;
 
+use crate::amount::Amount;
 use crate::client::Mpesa;
-use crate::constants::{Invoice, InvoiceItem};
+use crate::constants::{Invoice, InvoiceItem, MpesaResponseCode};
 use crate::errors::{MpesaError, MpesaResult};
+use crate::msisdn::Msisdn;
 
-const BILL_MANAGER_SINGLE_INVOICE_API_URL: &str = "v1/billmanager-invoice/single-invoicing";
+pub const BILL_MANAGER_SINGLE_INVOICE_API_URL: &str = "v1/billmanager-invoice/single-invoicing";
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct SingleInvoiceResponse {
     #[serde(rename(deserialize = "rescode"))]
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     #[serde(rename(deserialize = "resmsg"))]
     pub response_message: String,
     #[serde(rename(deserialize = "Status_Message"))]
@@ -22,11 +24,11 @@ pub struct SingleInvoiceResponse {
 #[derive(Debug)]
 pub struct SingleInvoiceBuilder<'mpesa> {
     client: &'mpesa Mpesa,
-    amount: Option<f64>,
+    amount: Option<MpesaResult<Amount>>,
     account_reference: Option<&'mpesa str>,
     billed_full_name: Option<&'mpesa str>,
     billed_period: Option<&'mpesa str>,
-    billed_phone_number: Option<&'mpesa str>,
+    billed_phone_number: Option<MpesaResult<Msisdn>>,
     due_date: Option<DateTime<Utc>>,
     external_reference: Option<&'mpesa str>,
     invoice_items: Option<Vec<InvoiceItem<'mpesa>>>,
@@ -51,8 +53,12 @@ impl<'mpesa> SingleInvoiceBuilder<'mpesa> {
     }
 
     /// Adds `amount`
-    pub fn amount<Number: Into<f64>>(mut self, amount: Number) -> SingleInvoiceBuilder<'mpesa> {
-        self.amount = Some(amount.into());
+    pub fn amount<A>(mut self, amount: A) -> SingleInvoiceBuilder<'mpesa>
+    where
+        A: TryInto<Amount>,
+        MpesaError: From<A::Error>,
+    {
+        self.amount = Some(amount.try_into().map_err(MpesaError::from));
         self
     }
 
@@ -80,12 +86,17 @@ impl<'mpesa> SingleInvoiceBuilder<'mpesa> {
         self
     }
 
-    /// Adds `billed_phone_number`; must be in the format `0722XXXXXX`
-    pub fn billed_phone_number(
-        mut self,
-        billed_phone_number: &'mpesa str,
-    ) -> SingleInvoiceBuilder<'mpesa> {
-        self.billed_phone_number = Some(billed_phone_number);
+    /// Adds `billed_phone_number`, it is sent in the format `0722XXXXXX`
+    pub fn billed_phone_number<P>(mut self, billed_phone_number: P) -> SingleInvoiceBuilder<'mpesa>
+    where
+        P: TryInto<Msisdn>,
+        MpesaError: From<P::Error>,
+    {
+        self.billed_phone_number = Some(
+            billed_phone_number
+                .try_into()
+                .map_err(|e| MpesaError::from(e).for_field("billed_phone_number")),
+        );
         self
     }
 
@@ -131,29 +142,29 @@ impl<'mpesa> SingleInvoiceBuilder<'mpesa> {
         let payload = Invoice {
             amount: self
                 .amount
-                .ok_or(MpesaError::Message("amount is required"))?,
+                .ok_or(MpesaError::Message("amount is required".into()))??,
             account_reference: self
                 .account_reference
-                .ok_or(MpesaError::Message("account_reference is required"))?,
+                .ok_or(MpesaError::Message("account_reference is required".into()))?,
             billed_full_name: self
                 .billed_full_name
-                .ok_or(MpesaError::Message("billed_full_name is required"))?,
+                .ok_or(MpesaError::Message("billed_full_name is required".into()))?,
             billed_period: self
                 .billed_period
-                .ok_or(MpesaError::Message("billed_period is required"))?,
-            billed_phone_number: self
-                .billed_phone_number
-                .ok_or(MpesaError::Message("billed_phone_number is required"))?,
+                .ok_or(MpesaError::Message("billed_period is required".into()))?,
+            billed_phone_number: self.billed_phone_number.ok_or(MpesaError::Message(
+                "billed_phone_number is required".into(),
+            ))??,
             due_date: self
                 .due_date
-                .ok_or(MpesaError::Message("due_date is required"))?,
+                .ok_or(MpesaError::Message("due_date is required".into()))?,
             external_reference: self
                 .external_reference
-                .ok_or(MpesaError::Message("external_reference is required"))?,
+                .ok_or(MpesaError::Message("external_reference is required".into()))?,
             invoice_items: self.invoice_items,
             invoice_name: self
                 .invoice_name
-                .ok_or(MpesaError::Message("invoice_name is required"))?,
+                .ok_or(MpesaError::Message("invoice_name is required".into()))?,
         };
 
         self.client
@@ -161,6 +172,7 @@ impl<'mpesa> SingleInvoiceBuilder<'mpesa> {
                 method: reqwest::Method::POST,
                 path: BILL_MANAGER_SINGLE_INVOICE_API_URL,
                 body: payload,
+                idempotent: false,
             })
             .await
     }
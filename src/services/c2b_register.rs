@@ -3,22 +3,24 @@
 use serde::{Deserialize, Serialize};
 
 use crate::client::Mpesa;
-use crate::constants::ResponseType;
+use crate::constants::{MpesaResponseCode, ResponseType};
 use crate::errors::{MpesaError, MpesaResult};
+use crate::short_code::ShortCode;
+use crate::url::Url;
 
-const C2B_REGISTER_URL: &str = "mpesa/c2b/v1/registerurl";
+pub const C2B_REGISTER_URL: &str = "mpesa/c2b/v1/registerurl";
 
 #[derive(Debug, Serialize)]
 /// Payload to register the 3rd party’s confirmation and validation URLs to M-Pesa
-struct C2bRegisterPayload<'mpesa> {
+struct C2bRegisterPayload {
     #[serde(rename(serialize = "ValidationURL"))]
-    validation_url: &'mpesa str,
+    validation_url: Url,
     #[serde(rename(serialize = "ConfirmationURL"))]
-    confirmation_url: &'mpesa str,
+    confirmation_url: Url,
     #[serde(rename(serialize = "ResponseType"))]
     response_type: ResponseType,
     #[serde(rename(serialize = "ShortCode"))]
-    short_code: &'mpesa str,
+    short_code: ShortCode,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -26,7 +28,7 @@ pub struct C2bRegisterResponse {
     #[serde(rename(deserialize = "OriginatorCoversationID"))]
     pub originator_conversation_id: String,
     #[serde(rename(deserialize = "ResponseCode"))]
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     #[serde(rename(deserialize = "ResponseDescription"))]
     pub response_description: String,
 }
@@ -102,19 +104,24 @@ impl<'mpesa> C2bRegisterBuilder<'mpesa> {
     ///
     /// # Errors
     /// Returns a `MpesaError` on failure
-
     pub async fn send(self) -> MpesaResult<C2bRegisterResponse> {
         let payload = C2bRegisterPayload {
-            validation_url: self
-                .validation_url
-                .ok_or(MpesaError::Message("validation_url is required"))?,
-            confirmation_url: self
-                .confirmation_url
-                .ok_or(MpesaError::Message("confirmation_url is required"))?,
+            validation_url: self.client.callback_url(
+                "validation_url",
+                self.validation_url
+                    .ok_or(MpesaError::Message("validation_url is required".into()))?,
+            )?,
+            confirmation_url: self.client.callback_url(
+                "confirmation_url",
+                self.confirmation_url
+                    .ok_or(MpesaError::Message("confirmation_url is required".into()))?,
+            )?,
             response_type: self.response_type.unwrap_or(ResponseType::Completed),
-            short_code: self
-                .short_code
-                .ok_or(MpesaError::Message("short_code is required"))?,
+            short_code: ShortCode::parse(
+                self.short_code
+                    .ok_or(MpesaError::Message("short_code is required".into()))?,
+            )
+            .map_err(|e| e.for_field("short_code"))?,
         };
 
         self.client
@@ -122,6 +129,7 @@ impl<'mpesa> C2bRegisterBuilder<'mpesa> {
                 method: reqwest::Method::POST,
                 path: C2B_REGISTER_URL,
                 body: payload,
+                idempotent: false,
             })
             .await
     }
@@ -2,11 +2,14 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::amount::Amount;
 use crate::client::Mpesa;
-use crate::constants::CommandId;
+use crate::constants::{CommandId, MpesaResponseCode};
 use crate::errors::{MpesaError, MpesaResult};
+use crate::msisdn::Msisdn;
+use crate::short_code::ShortCode;
 
-const C2B_SIMULATE_URL: &str = "mpesa/c2b/v1/simulate";
+pub const C2B_SIMULATE_URL: &str = "mpesa/c2b/v1/simulate";
 
 #[derive(Debug, Serialize)]
 /// Payload to make payment requests from C2B.
@@ -15,13 +18,13 @@ struct C2bSimulatePayload<'mpesa> {
     #[serde(rename(serialize = "CommandID"))]
     command_id: CommandId,
     #[serde(rename(serialize = "Amount"))]
-    amount: f64,
+    amount: Amount,
     #[serde(rename(serialize = "Msisdn"))]
-    msisdn: &'mpesa str,
+    msisdn: Msisdn,
     #[serde(rename(serialize = "BillRefNumber"))]
     bill_ref_number: &'mpesa str,
     #[serde(rename(serialize = "ShortCode"))]
-    short_code: &'mpesa str,
+    short_code: ShortCode,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -34,7 +37,7 @@ pub struct C2bSimulateResponse {
     #[serde(rename(deserialize = "OriginatorCoversationID"))]
     pub originator_conversation_id: String,
     #[serde(rename(deserialize = "ResponseCode"))]
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     #[serde(rename(deserialize = "ResponseDescription"))]
     pub response_description: String,
 }
@@ -43,8 +46,8 @@ pub struct C2bSimulateResponse {
 pub struct C2bSimulateBuilder<'mpesa> {
     client: &'mpesa Mpesa,
     command_id: Option<CommandId>,
-    amount: Option<f64>,
-    msisdn: Option<&'mpesa str>,
+    amount: Option<MpesaResult<Amount>>,
+    msisdn: Option<MpesaResult<Msisdn>>,
     bill_ref_number: Option<&'mpesa str>,
     short_code: Option<&'mpesa str>,
 }
@@ -75,8 +78,12 @@ impl<'mpesa> C2bSimulateBuilder<'mpesa> {
     ///
     /// # Errors
     /// If `Amount` is not provided
-    pub fn amount<Number: Into<f64>>(mut self, amount: Number) -> C2bSimulateBuilder<'mpesa> {
-        self.amount = Some(amount.into());
+    pub fn amount<A>(mut self, amount: A) -> C2bSimulateBuilder<'mpesa>
+    where
+        A: TryInto<Amount>,
+        MpesaError: From<A::Error>,
+    {
+        self.amount = Some(amount.try_into().map_err(MpesaError::from));
         self
     }
 
@@ -85,8 +92,16 @@ impl<'mpesa> C2bSimulateBuilder<'mpesa> {
     ///
     /// # Errors
     /// If `MSISDN` is invalid or not provided
-    pub fn msisdn(mut self, msisdn: &'mpesa str) -> C2bSimulateBuilder<'mpesa> {
-        self.msisdn = Some(msisdn);
+    pub fn msisdn<P>(mut self, msisdn: P) -> C2bSimulateBuilder<'mpesa>
+    where
+        P: TryInto<Msisdn>,
+        MpesaError: From<P::Error>,
+    {
+        self.msisdn = Some(
+            msisdn
+                .try_into()
+                .map_err(|e| MpesaError::from(e).for_field("msisdn")),
+        );
         self
     }
 
@@ -124,16 +139,18 @@ impl<'mpesa> C2bSimulateBuilder<'mpesa> {
             command_id: self.command_id.unwrap_or(CommandId::CustomerPayBillOnline),
             amount: self
                 .amount
-                .ok_or(MpesaError::Message("amount is required"))?,
+                .ok_or(MpesaError::Message("amount is required".into()))??,
             msisdn: self
                 .msisdn
-                .ok_or(MpesaError::Message("msisdn is required"))?,
+                .ok_or(MpesaError::Message("msisdn is required".into()))??,
             bill_ref_number: self
                 .bill_ref_number
-                .ok_or(MpesaError::Message("bill_ref_number is required"))?,
-            short_code: self
-                .short_code
-                .ok_or(MpesaError::Message("short_code is required"))?,
+                .ok_or(MpesaError::Message("bill_ref_number is required".into()))?,
+            short_code: ShortCode::parse(
+                self.short_code
+                    .ok_or(MpesaError::Message("short_code is required".into()))?,
+            )
+            .map_err(|e| e.for_field("short_code"))?,
         };
 
         self.client
@@ -141,6 +158,7 @@ impl<'mpesa> C2bSimulateBuilder<'mpesa> {
                 method: reqwest::Method::POST,
                 path: C2B_SIMULATE_URL,
                 body: payload,
+                idempotent: false,
             })
             .await
     }
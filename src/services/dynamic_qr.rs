@@ -3,11 +3,12 @@
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 
+use crate::amount::Amount;
 use crate::client::Mpesa;
-use crate::constants::TransactionType;
+use crate::constants::{MpesaResponseCode, TransactionType};
 use crate::errors::{MpesaError, MpesaResult};
 
-const DYNAMIC_QR_URL: &str = "mpesa/qrcode/v1/generate";
+pub const DYNAMIC_QR_URL: &str = "mpesa/qrcode/v1/generate";
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all(serialize = "PascalCase"))]
@@ -17,7 +18,7 @@ pub struct DynamicQRRequest<'mpesa> {
     /// Transaction Reference Number
     pub ref_no: &'mpesa str,
     /// The total amount of the transaction
-    pub amount: u32,
+    pub amount: Amount,
     /// Transaction Type
     ///
     /// This can be a `TransactionType` or a `&str`
@@ -46,7 +47,7 @@ pub struct DynamicQRRequest<'mpesa> {
 pub struct DynamicQRResponse {
     #[serde(rename(deserialize = "QRCode"))]
     pub qr_code: String,
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     pub response_description: String,
 }
 
@@ -59,9 +60,12 @@ pub struct DynamicQR<'mpesa> {
     /// Name of the Company/M-Pesa Merchant Name
     #[builder(setter(into))]
     merchant_name: &'mpesa str,
-    /// Transaction Reference Number
-    amount: u32,
     /// The total amount of the transaction
+    ///
+    /// Set with `try_amount` to convert from an integer, e.g. `try_amount(2000)`
+    #[builder(try_setter, setter(into))]
+    amount: Amount,
+    /// Transaction Reference Number
     ref_no: &'mpesa str,
     /// Transaction Type
     ///
@@ -136,11 +140,16 @@ impl<'mpesa> DynamicQR<'mpesa> {
     /// # Errors
     /// Returns a `MpesaError` on failure
     pub async fn send(self) -> MpesaResult<DynamicQRResponse> {
+        self.transaction_type
+            .validate_credit_party(self.credit_party_identifier)
+            .map_err(|e| e.for_field("credit_party_identifier"))?;
+
         self.client
             .send::<DynamicQRRequest, _>(crate::client::Request {
                 method: reqwest::Method::POST,
                 path: DYNAMIC_QR_URL,
                 body: self.into(),
+                idempotent: true,
             })
             .await
     }
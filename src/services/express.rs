@@ -0,0 +1,21 @@
+//! Password generation shared by Mpesa Express requests and queries
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::prelude::Local;
+
+/// Source: [test credentials](https://developer.safaricom.co.ke/test_credentials)
+pub(crate) static DEFAULT_PASSKEY: &str =
+    "bfb279f9aa9bdbcf158e97dd71a467cd2e0c893059b10f78e6b72ada1ed2c919";
+
+/// Utility function to generate base64 encoded password as per Safaricom's [specifications](https://developer.safaricom.co.ke/docs#lipa-na-m-pesa-online-payment)
+/// Returns the encoded password and a timestamp string
+pub(crate) fn generate_password_and_timestamp(
+    business_short_code: &str,
+    pass_key: &str,
+) -> (String, String) {
+    let timestamp = Local::now().format("%Y%m%d%H%M%S").to_string();
+    let encoded_password =
+        BASE64.encode(format!("{}{}{}", business_short_code, pass_key, timestamp));
+    (encoded_password, timestamp)
+}
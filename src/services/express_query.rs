@@ -0,0 +1,121 @@
+#![doc = include_str!("../../docs/client/express_query.md")]
+
+use serde::{Deserialize, Serialize};
+
+use crate::client::Mpesa;
+use crate::constants::MpesaResponseCode;
+use crate::errors::{MpesaError, MpesaResult};
+use crate::services::express::{generate_password_and_timestamp, DEFAULT_PASSKEY};
+use crate::short_code::ShortCode;
+
+pub const EXPRESS_QUERY_URL: &str = "mpesa/stkpushquery/v1/query";
+
+#[derive(Debug, Serialize)]
+struct MpesaExpressQueryPayload<'mpesa> {
+    #[serde(rename(serialize = "BusinessShortCode"))]
+    business_short_code: ShortCode,
+    #[serde(rename(serialize = "Password"))]
+    password: &'mpesa str,
+    #[serde(rename(serialize = "Timestamp"))]
+    timestamp: &'mpesa str,
+    #[serde(rename(serialize = "CheckoutRequestID"))]
+    checkout_request_id: &'mpesa str,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MpesaExpressQueryResponse {
+    #[serde(rename(deserialize = "CheckoutRequestID"))]
+    pub checkout_request_id: String,
+    #[serde(rename(deserialize = "MerchantRequestID"))]
+    pub merchant_request_id: String,
+    #[serde(rename(deserialize = "ResponseCode"))]
+    pub response_code: MpesaResponseCode,
+    #[serde(rename(deserialize = "ResponseDescription"))]
+    pub response_description: String,
+    /// The status of the STK push transaction, e.g. `MpesaResponseCode::CancelledByUser`
+    #[serde(rename(deserialize = "ResultCode"))]
+    pub result_code: MpesaResponseCode,
+    #[serde(rename(deserialize = "ResultDesc"))]
+    pub result_desc: String,
+}
+
+#[derive(Debug)]
+pub struct MpesaExpressQueryBuilder<'mpesa> {
+    business_short_code: &'mpesa str,
+    client: &'mpesa Mpesa,
+    checkout_request_id: Option<&'mpesa str>,
+    pass_key: Option<&'mpesa str>,
+}
+
+impl<'mpesa> MpesaExpressQueryBuilder<'mpesa> {
+    pub fn new(
+        client: &'mpesa Mpesa,
+        business_short_code: &'mpesa str,
+    ) -> MpesaExpressQueryBuilder<'mpesa> {
+        MpesaExpressQueryBuilder {
+            client,
+            business_short_code,
+            checkout_request_id: None,
+            pass_key: None,
+        }
+    }
+
+    /// Retrieves the production passkey if present, then the client's, or defaults to the key provided in Safaricom's [test credentials](https://developer.safaricom.co.ke/test_credentials) outside of production
+    fn get_pass_key(&'mpesa self) -> MpesaResult<&'mpesa str> {
+        if let Some(key) = self.pass_key.or(self.client.pass_key()) {
+            return Ok(key);
+        }
+        self.client.sandbox_default("pass_key", DEFAULT_PASSKEY)
+    }
+
+    /// Your passkey.
+    /// Optional in sandbox, will default to key provided in Safaricom's [test credentials](https://developer.safaricom.co.ke/test_credentials)
+    /// Required in production
+    pub fn pass_key(mut self, pass_key: &'mpesa str) -> MpesaExpressQueryBuilder<'mpesa> {
+        self.pass_key = Some(pass_key);
+        self
+    }
+
+    /// Adds the `CheckoutRequestID` returned in a `MpesaExpressRequestResponse`
+    /// This is a required field
+    pub fn checkout_request_id(
+        mut self,
+        checkout_request_id: &'mpesa str,
+    ) -> MpesaExpressQueryBuilder<'mpesa> {
+        self.checkout_request_id = Some(checkout_request_id);
+        self
+    }
+
+    /// # Lipa na M-Pesa Online Query / Mpesa Express Query
+    ///
+    /// Checks the status of a Lipa Na M-Pesa Online Payment
+    ///
+    /// A successful request returns a `MpesaExpressQueryResponse` type
+    ///
+    /// # Errors
+    /// Returns a `MpesaError` on failure
+    pub async fn send(self) -> MpesaResult<MpesaExpressQueryResponse> {
+        let business_short_code = ShortCode::parse(self.business_short_code)
+            .map_err(|e| e.for_field("business_short_code"))?;
+        let (password, timestamp) =
+            generate_password_and_timestamp(business_short_code.as_str(), self.get_pass_key()?);
+
+        let payload = MpesaExpressQueryPayload {
+            business_short_code,
+            password: &password,
+            timestamp: &timestamp,
+            checkout_request_id: self.checkout_request_id.ok_or(MpesaError::Message(
+                "checkout_request_id is required".into(),
+            ))?,
+        };
+
+        self.client
+            .send(crate::client::Request {
+                method: reqwest::Method::POST,
+                path: EXPRESS_QUERY_URL,
+                body: payload,
+                idempotent: true,
+            })
+            .await
+    }
+}
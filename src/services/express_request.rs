@@ -1,22 +1,25 @@
 #![doc = include_str!("../../docs/client/express_request.md")]
 
-use chrono::prelude::Local;
-use openssl::base64;
+use std::marker::PhantomData;
+
 use serde::{Deserialize, Serialize};
 
+use crate::amount::Amount;
 use crate::client::Mpesa;
-use crate::constants::CommandId;
+use crate::constants::{CommandId, MpesaResponseCode};
 use crate::errors::{MpesaError, MpesaResult};
+use crate::msisdn::Msisdn;
+use crate::services::express::{generate_password_and_timestamp, DEFAULT_PASSKEY};
+use crate::services::{Set, Unset};
+use crate::short_code::ShortCode;
+use crate::url::Url;
 
-const EXPRESS_REQUEST_URL: &str = "mpesa/stkpush/v1/processrequest";
-
-/// Source: [test credentials](https://developer.safaricom.co.ke/test_credentials)
-static DEFAULT_PASSKEY: &str = "bfb279f9aa9bdbcf158e97dd71a467cd2e0c893059b10f78e6b72ada1ed2c919";
+pub const EXPRESS_REQUEST_URL: &str = "mpesa/stkpush/v1/processrequest";
 
 #[derive(Debug, Serialize)]
 struct MpesaExpressRequestPayload<'mpesa> {
     #[serde(rename(serialize = "BusinessShortCode"))]
-    business_short_code: &'mpesa str,
+    business_short_code: ShortCode,
     #[serde(rename(serialize = "Password"))]
     password: &'mpesa str,
     #[serde(rename(serialize = "Timestamp"))]
@@ -24,15 +27,15 @@ struct MpesaExpressRequestPayload<'mpesa> {
     #[serde(rename(serialize = "TransactionType"))]
     transaction_type: CommandId,
     #[serde(rename(serialize = "Amount"))]
-    amount: f64,
+    amount: Amount,
     #[serde(rename(serialize = "PartyA"), skip_serializing_if = "Option::is_none")]
-    party_a: Option<&'mpesa str>,
-    #[serde(rename(serialize = "PartyB"), skip_serializing_if = "Option::is_none")]
-    party_b: Option<&'mpesa str>,
+    party_a: Option<Msisdn>,
+    #[serde(rename(serialize = "PartyB"))]
+    party_b: ShortCode,
     #[serde(rename(serialize = "PhoneNumber"))]
-    phone_number: &'mpesa str,
+    phone_number: Msisdn,
     #[serde(rename(serialize = "CallBackURL"))]
-    call_back_url: &'mpesa str,
+    call_back_url: Url,
     #[serde(rename(serialize = "AccountReference"))]
     account_reference: &'mpesa str,
     #[serde(rename(serialize = "TransactionDesc"))]
@@ -48,7 +51,7 @@ pub struct MpesaExpressRequestResponse {
     #[serde(rename(deserialize = "MerchantRequestID"))]
     pub merchant_request_id: String,
     #[serde(rename(deserialize = "ResponseCode"))]
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     #[serde(rename(deserialize = "ResponseDescription"))]
     pub response_description: String,
 }
@@ -57,10 +60,10 @@ pub struct MpesaExpressRequestBuilder<'mpesa> {
     business_short_code: &'mpesa str,
     client: &'mpesa Mpesa,
     transaction_type: Option<CommandId>,
-    amount: Option<f64>,
-    party_a: Option<&'mpesa str>,
+    amount: Option<MpesaResult<Amount>>,
+    party_a: Option<MpesaResult<Msisdn>>,
     party_b: Option<&'mpesa str>,
-    phone_number: Option<&'mpesa str>,
+    phone_number: Option<MpesaResult<Msisdn>>,
     callback_url: Option<&'mpesa str>,
     account_ref: Option<&'mpesa str>,
     transaction_desc: Option<&'mpesa str>,
@@ -92,28 +95,12 @@ impl<'mpesa> MpesaExpressRequestBuilder<'mpesa> {
         self.business_short_code
     }
 
-    /// Retrieves the production passkey if present or defaults to the key provided in Safaricom's [test credentials](https://developer.safaricom.co.ke/test_credentials)
-    fn get_pass_key(&'mpesa self) -> &'mpesa str {
-        if let Some(key) = self.pass_key {
-            return key;
+    /// Retrieves the production passkey if present, then the client's, or defaults to the key provided in Safaricom's [test credentials](https://developer.safaricom.co.ke/test_credentials) outside of production
+    fn get_pass_key(&'mpesa self) -> MpesaResult<&'mpesa str> {
+        if let Some(key) = self.pass_key.or(self.client.pass_key()) {
+            return Ok(key);
         }
-        DEFAULT_PASSKEY
-    }
-
-    /// Utility method to generate base64 encoded password as per Safaricom's [specifications](https://developer.safaricom.co.ke/docs#lipa-na-m-pesa-online-payment)
-    /// Returns the encoded password and a timestamp string
-    fn generate_password_and_timestamp(&self) -> (String, String) {
-        let timestamp = Local::now().format("%Y%m%d%H%M%S").to_string();
-        let encoded_password = base64::encode_block(
-            format!(
-                "{}{}{}",
-                self.business_short_code(),
-                self.get_pass_key(),
-                timestamp
-            )
-            .as_bytes(),
-        );
-        (encoded_password, timestamp)
+        self.client.sandbox_default("pass_key", DEFAULT_PASSKEY)
     }
 
     /// Your passkey.
@@ -129,11 +116,12 @@ impl<'mpesa> MpesaExpressRequestBuilder<'mpesa> {
 
     /// Adds an `amount` to the request
     /// This is a required field
-    pub fn amount<Number: Into<f64>>(
-        mut self,
-        amount: Number,
-    ) -> MpesaExpressRequestBuilder<'mpesa> {
-        self.amount = Some(amount.into());
+    pub fn amount<A>(mut self, amount: A) -> MpesaExpressRequestBuilder<'mpesa>
+    where
+        A: TryInto<Amount>,
+        MpesaError: From<A::Error>,
+    {
+        self.amount = Some(amount.try_into().map_err(MpesaError::from));
         self
     }
 
@@ -141,8 +129,16 @@ impl<'mpesa> MpesaExpressRequestBuilder<'mpesa> {
     ///
     /// # Errors
     /// If `phone_number` is invalid
-    pub fn phone_number(mut self, phone_number: &'mpesa str) -> MpesaExpressRequestBuilder<'mpesa> {
-        self.phone_number = Some(phone_number);
+    pub fn phone_number<P>(mut self, phone_number: P) -> MpesaExpressRequestBuilder<'mpesa>
+    where
+        P: TryInto<Msisdn>,
+        MpesaError: From<P::Error>,
+    {
+        self.phone_number = Some(
+            phone_number
+                .try_into()
+                .map_err(|e| MpesaError::from(e).for_field("phone_number")),
+        );
         self
     }
 
@@ -159,8 +155,16 @@ impl<'mpesa> MpesaExpressRequestBuilder<'mpesa> {
     ///
     /// # Errors
     /// If `party_a` is invalid
-    pub fn party_a(mut self, party_a: &'mpesa str) -> MpesaExpressRequestBuilder<'mpesa> {
-        self.party_a = Some(party_a);
+    pub fn party_a<P>(mut self, party_a: P) -> MpesaExpressRequestBuilder<'mpesa>
+    where
+        P: TryInto<Msisdn>,
+        MpesaError: From<P::Error>,
+    {
+        self.party_a = Some(
+            party_a
+                .try_into()
+                .map_err(|e| MpesaError::from(e).for_field("party_a")),
+        );
         self
     }
 
@@ -207,36 +211,38 @@ impl<'mpesa> MpesaExpressRequestBuilder<'mpesa> {
     /// # Errors
     /// Returns a `MpesaError` on failure
     pub async fn send(self) -> MpesaResult<MpesaExpressRequestResponse> {
-        let (password, timestamp) = self.generate_password_and_timestamp();
+        let business_short_code = ShortCode::parse(self.business_short_code)
+            .map_err(|e| e.for_field("business_short_code"))?;
+        let (password, timestamp) =
+            generate_password_and_timestamp(business_short_code.as_str(), self.get_pass_key()?);
+
+        let phone_number = self
+            .phone_number
+            .ok_or(MpesaError::Message("phone_number is required".into()))??;
 
         let payload = MpesaExpressRequestPayload {
-            business_short_code: self.business_short_code,
+            party_b: match self.party_b {
+                Some(party_b) => ShortCode::parse(party_b).map_err(|e| e.for_field("party_b"))?,
+                None => business_short_code.clone(),
+            },
+            business_short_code,
             password: &password,
             timestamp: &timestamp,
             amount: self
                 .amount
-                .ok_or(MpesaError::Message("amount is required"))?,
-            party_a: if self.party_a.is_some() {
-                self.party_a
-            } else {
-                self.phone_number
-            },
-            party_b: if self.party_b.is_some() {
-                self.party_b
-            } else {
-                Some(self.business_short_code)
-            },
-            phone_number: self
-                .phone_number
-                .ok_or(MpesaError::Message("phone_number is required"))?,
-            call_back_url: self
-                .callback_url
-                .ok_or(MpesaError::Message("callback_url is required"))?,
-            account_reference: self.account_ref.unwrap_or_else(|| stringify!(None)),
+                .ok_or(MpesaError::Message("amount is required".into()))??,
+            party_a: Some(self.party_a.transpose()?.unwrap_or(phone_number.clone())),
+            phone_number,
+            call_back_url: self.client.callback_url(
+                "callback_url",
+                self.callback_url
+                    .ok_or(MpesaError::Message("callback_url is required".into()))?,
+            )?,
+            account_reference: self.account_ref.unwrap_or(stringify!(None)),
             transaction_type: self
                 .transaction_type
                 .unwrap_or(CommandId::CustomerPayBillOnline),
-            transaction_desc: self.transaction_desc.unwrap_or_else(|| stringify!(None)),
+            transaction_desc: self.transaction_desc.unwrap_or(stringify!(None)),
         };
 
         self.client
@@ -244,7 +250,170 @@ impl<'mpesa> MpesaExpressRequestBuilder<'mpesa> {
                 method: reqwest::Method::POST,
                 path: EXPRESS_REQUEST_URL,
                 body: payload,
+                idempotent: false,
             })
             .await
     }
 }
+
+/// M-Pesa Express request builder whose `send` method only exists once every required field is
+/// set
+///
+/// Each type parameter tracks a required field, in order `amount`, `phone_number` and
+/// `callback_url`, and is either [`Set`] or [`Unset`]. Leaving a required field out is a compiler
+/// error rather than a `MpesaError::Message`, the values themselves are still validated when the
+/// request is sent. Use [`MpesaExpressRequestBuilder`] when the fields are only known at runtime.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # async fn run(client: mpesa::Mpesa) -> mpesa::MpesaResult<()> {
+/// let response = client
+///     .express_request_typed("174379")
+///     .phone_number("254708374149")
+///     .amount(500)
+///     .callback_url("https://test.example.com/api")
+///     .send()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Forgetting `amount` does not compile:
+///
+/// ```rust,compile_fail
+/// # async fn run(client: mpesa::Mpesa) -> mpesa::MpesaResult<()> {
+/// let response = client
+///     .express_request_typed("174379")
+///     .phone_number("254708374149")
+///     .callback_url("https://test.example.com/api")
+///     .send()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct TypedMpesaExpressRequestBuilder<'mpesa, A = Unset, P = Unset, C = Unset> {
+    inner: MpesaExpressRequestBuilder<'mpesa>,
+    state: PhantomData<(A, P, C)>,
+}
+
+impl<'mpesa> TypedMpesaExpressRequestBuilder<'mpesa> {
+    /// Creates a new typed M-Pesa Express request builder
+    pub fn new(
+        client: &'mpesa Mpesa,
+        business_short_code: &'mpesa str,
+    ) -> TypedMpesaExpressRequestBuilder<'mpesa> {
+        TypedMpesaExpressRequestBuilder {
+            inner: MpesaExpressRequestBuilder::new(client, business_short_code),
+            state: PhantomData,
+        }
+    }
+}
+
+impl<'mpesa, A, P, C> TypedMpesaExpressRequestBuilder<'mpesa, A, P, C> {
+    /// Public method get the `business_short_code`
+    pub fn business_short_code(&'mpesa self) -> &'mpesa str {
+        self.inner.business_short_code()
+    }
+
+    /// Your passkey. Optional in sandbox, required in production
+    pub fn pass_key(self, pass_key: &'mpesa str) -> Self {
+        TypedMpesaExpressRequestBuilder {
+            inner: self.inner.pass_key(pass_key),
+            state: PhantomData,
+        }
+    }
+
+    /// Adds an `amount` to the request. This is a required field
+    pub fn amount<V>(self, amount: V) -> TypedMpesaExpressRequestBuilder<'mpesa, Set, P, C>
+    where
+        V: TryInto<Amount>,
+        MpesaError: From<V::Error>,
+    {
+        TypedMpesaExpressRequestBuilder {
+            inner: self.inner.amount(amount),
+            state: PhantomData,
+        }
+    }
+
+    /// The MSISDN sending the funds. This is a required field
+    pub fn phone_number<V>(
+        self,
+        phone_number: V,
+    ) -> TypedMpesaExpressRequestBuilder<'mpesa, A, Set, C>
+    where
+        V: TryInto<Msisdn>,
+        MpesaError: From<V::Error>,
+    {
+        TypedMpesaExpressRequestBuilder {
+            inner: self.inner.phone_number(phone_number),
+            state: PhantomData,
+        }
+    }
+
+    /// The url to where responses from M-Pesa will be sent to. This is a required field
+    pub fn callback_url(
+        self,
+        callback_url: &'mpesa str,
+    ) -> TypedMpesaExpressRequestBuilder<'mpesa, A, P, Set> {
+        TypedMpesaExpressRequestBuilder {
+            inner: self.inner.callback_url(callback_url),
+            state: PhantomData,
+        }
+    }
+
+    /// The MSISDN sending the funds, defaults to `phone_number`
+    pub fn party_a<V>(self, party_a: V) -> Self
+    where
+        V: TryInto<Msisdn>,
+        MpesaError: From<V::Error>,
+    {
+        TypedMpesaExpressRequestBuilder {
+            inner: self.inner.party_a(party_a),
+            state: PhantomData,
+        }
+    }
+
+    /// The organization shortcode receiving the funds, defaults to the business short code
+    pub fn party_b(self, party_b: &'mpesa str) -> Self {
+        TypedMpesaExpressRequestBuilder {
+            inner: self.inner.party_b(party_b),
+            state: PhantomData,
+        }
+    }
+
+    /// Optional - Used with M-Pesa PayBills.
+    pub fn account_ref(self, account_ref: &'mpesa str) -> Self {
+        TypedMpesaExpressRequestBuilder {
+            inner: self.inner.account_ref(account_ref),
+            state: PhantomData,
+        }
+    }
+
+    /// Optional, defaults to `CommandId::CustomerPayBillOnline`
+    pub fn transaction_type(self, command_id: CommandId) -> Self {
+        TypedMpesaExpressRequestBuilder {
+            inner: self.inner.transaction_type(command_id),
+            state: PhantomData,
+        }
+    }
+
+    /// A description of the transaction.
+    /// Optional - defaults to "None"
+    pub fn transaction_desc(self, description: &'mpesa str) -> Self {
+        TypedMpesaExpressRequestBuilder {
+            inner: self.inner.transaction_desc(description),
+            state: PhantomData,
+        }
+    }
+}
+
+impl<'mpesa> TypedMpesaExpressRequestBuilder<'mpesa, Set, Set, Set> {
+    /// Sends the M-Pesa Express request, see [`MpesaExpressRequestBuilder::send`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError` on failure.
+    pub async fn send(self) -> MpesaResult<MpesaExpressRequestResponse> {
+        self.inner.send().await
+    }
+}
@@ -5,6 +5,10 @@
 //! Some of the builder methods for certain services are optional with default values standing in
 //! their place when the builder gets consumed
 //!
+//! The B2C, M-Pesa Express and Bill Manager reconciliation services also offer typed builders,
+//! e.g. [`TypedB2cBuilder`], whose `send` method only exists once every required field is set,
+//! so a missing field is a compiler error instead of a `MpesaError::Message`.
+//!
 //! Here are the currently supported services:
 //! 1. [Account Balance](https://developer.safaricom.co.ke/APIs/AccountBalance)
 //! 2. [B2B](https://developer.safaricom.co.ke/APIs/BusinessPayBill)
@@ -16,35 +20,65 @@
 //! 8. [Bill Manager](https://developer.safaricom.co.ke/APIs/BillManager)
 //! 9. [Transaction Status](https://developer.safaricom.co.ke/APIs/TransactionStatus)
 //! 10. [Dynamic QR](https://developer.safaricom.co.ke/APIs/DynamicQRCode)
+//! 11. [Mpesa Express Query](https://developer.safaricom.co.ke/APIs/MpesaExpressQuery)
 
+#[cfg(feature = "account_balance")]
 mod account_balance;
+#[cfg(feature = "b2b")]
 mod b2b;
+#[cfg(feature = "b2c")]
 mod b2c;
+#[cfg(feature = "bill_manager")]
 mod bill_manager;
+#[cfg(feature = "c2b_register")]
 mod c2b_register;
+#[cfg(feature = "c2b_simulate")]
 mod c2b_simulate;
+#[cfg(feature = "dynamic_qr")]
 mod dynamic_qr;
+#[cfg(any(feature = "express_request", feature = "express_query"))]
+mod express;
+#[cfg(feature = "express_query")]
+mod express_query;
+#[cfg(feature = "express_request")]
 mod express_request;
+#[cfg(feature = "transaction_reversal")]
 mod transaction_reversal;
+#[cfg(feature = "transaction_status")]
 mod transaction_status;
+#[cfg(any(feature = "b2c", feature = "bill_manager", feature = "express_request"))]
+mod typestate;
 
 #[cfg(feature = "account_balance")]
-pub use account_balance::{AccountBalanceBuilder, AccountBalanceResponse};
+pub use account_balance::{AccountBalanceBuilder, AccountBalanceResponse, ACCOUNT_BALANCE_URL};
 #[cfg(feature = "b2b")]
-pub use b2b::{B2bBuilder, B2bResponse};
+pub use b2b::{B2bBuilder, B2bResponse, B2B_URL};
 #[cfg(feature = "b2c")]
-pub use b2c::{B2cBuilder, B2cResponse};
+pub use b2c::{B2cBuilder, B2cResponse, TypedB2cBuilder, B2C_URL};
 #[cfg(feature = "bill_manager")]
 pub use bill_manager::*;
 #[cfg(feature = "c2b_register")]
-pub use c2b_register::{C2bRegisterBuilder, C2bRegisterResponse};
+pub use c2b_register::{C2bRegisterBuilder, C2bRegisterResponse, C2B_REGISTER_URL};
 #[cfg(feature = "c2b_simulate")]
-pub use c2b_simulate::{C2bSimulateBuilder, C2bSimulateResponse};
+pub use c2b_simulate::{C2bSimulateBuilder, C2bSimulateResponse, C2B_SIMULATE_URL};
 #[cfg(feature = "dynamic_qr")]
-pub use dynamic_qr::{DynamicQR, DynamicQRBuilder, DynamicQRRequest, DynamicQRResponse};
+pub use dynamic_qr::{
+    DynamicQR, DynamicQRBuilder, DynamicQRRequest, DynamicQRResponse, DYNAMIC_QR_URL,
+};
+#[cfg(feature = "express_query")]
+pub use express_query::{MpesaExpressQueryBuilder, MpesaExpressQueryResponse, EXPRESS_QUERY_URL};
 #[cfg(feature = "express_request")]
-pub use express_request::{MpesaExpressRequestBuilder, MpesaExpressRequestResponse};
+pub use express_request::{
+    MpesaExpressRequestBuilder, MpesaExpressRequestResponse, TypedMpesaExpressRequestBuilder,
+    EXPRESS_REQUEST_URL,
+};
 #[cfg(feature = "transaction_reversal")]
-pub use transaction_reversal::{TransactionReversalBuilder, TransactionReversalResponse};
+pub use transaction_reversal::{
+    TransactionReversalBuilder, TransactionReversalResponse, TRANSACTION_REVERSAL_URL,
+};
 #[cfg(feature = "transaction_status")]
-pub use transaction_status::{TransactionStatusBuilder, TransactionStatusResponse};
+pub use transaction_status::{
+    TransactionStatusBuilder, TransactionStatusResponse, TRANSACTION_STATUS_URL,
+};
+#[cfg(any(feature = "b2c", feature = "bill_manager", feature = "express_request"))]
+pub use typestate::{Set, Unset};
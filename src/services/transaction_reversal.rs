@@ -2,9 +2,9 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::{CommandId, IdentifierTypes, Mpesa, MpesaError, MpesaResult};
+use crate::{Amount, CommandId, IdentifierTypes, Mpesa, MpesaError, MpesaResult, Url};
 
-const TRANSACTION_REVERSAL_URL: &str = "mpesa/reversal/v1/request";
+pub const TRANSACTION_REVERSAL_URL: &str = "mpesa/reversal/v1/request";
 
 #[derive(Debug, Serialize)]
 pub struct TransactionReversalPayload<'mpesa> {
@@ -17,19 +17,19 @@ pub struct TransactionReversalPayload<'mpesa> {
     #[serde(rename(serialize = "TransactionID"))]
     transaction_id: &'mpesa str,
     #[serde(rename(serialize = "ReceiverParty"))]
-    receiver_party: &'mpesa str,
+    receiver_party: String,
     #[serde(rename(serialize = "RecieverIdentifierType"))]
     receiver_identifier_type: IdentifierTypes,
     #[serde(rename(serialize = "ResultURL"))]
-    result_url: &'mpesa str,
+    result_url: Url,
     #[serde(rename(serialize = "QueueTimeOutURL"))]
-    timeout_url: &'mpesa str,
+    timeout_url: Url,
     #[serde(rename(serialize = "Remarks"))]
     remarks: &'mpesa str,
     #[serde(rename(serialize = "Occasion"))]
     occasion: &'mpesa str,
     #[serde(rename(serialize = "Amount"))]
-    amount: f64,
+    amount: Amount,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,7 +54,7 @@ pub struct TransactionReversalBuilder<'mpesa> {
     timeout_url: Option<&'mpesa str>,
     remarks: Option<&'mpesa str>,
     occasion: Option<&'mpesa str>,
-    amount: Option<f64>,
+    amount: Option<MpesaResult<Amount>>,
 }
 
 impl<'mpesa> TransactionReversalBuilder<'mpesa> {
@@ -148,8 +148,12 @@ impl<'mpesa> TransactionReversalBuilder<'mpesa> {
     /// Adds an `amount` to the request
     ///
     /// This is a required field
-    pub fn amount<Number: Into<f64>>(mut self, amount: Number) -> Self {
-        self.amount = Some(amount.into());
+    pub fn amount<A>(mut self, amount: A) -> Self
+    where
+        A: TryInto<Amount>,
+        MpesaError: From<A::Error>,
+    {
+        self.amount = Some(amount.try_into().map_err(MpesaError::from));
         self
     }
 
@@ -162,7 +166,7 @@ impl<'mpesa> TransactionReversalBuilder<'mpesa> {
     ///
     /// `transaction_id`: This is the Mpesa Transaction ID of the transaction which you wish to reverse
     ///
-    /// `amount` : The amount transacted in the transaction to be reversed , down to the cent
+    /// `amount` : The amount transacted in the transaction to be reversed, in whole shillings
     ///
     /// `receiver_party`: Your organization's short code.
     ///
@@ -173,7 +177,11 @@ impl<'mpesa> TransactionReversalBuilder<'mpesa> {
     /// # Errors
     /// Returns a `MpesaError` on failure.
     pub async fn send(self) -> MpesaResult<TransactionReversalResponse> {
-        let credentials = self.client.gen_security_credentials()?;
+        let credentials = self.client.security_credential()?;
+
+        let receiver_identifier_type = self
+            .receiver_identifier_type
+            .unwrap_or(IdentifierTypes::Reversal);
 
         let payload = TransactionReversalPayload {
             initiator: self.initiator,
@@ -181,24 +189,28 @@ impl<'mpesa> TransactionReversalBuilder<'mpesa> {
             command_id: self.command_id.unwrap_or(CommandId::TransactionReversal),
             transaction_id: self
                 .transaction_id
-                .ok_or(MpesaError::Message("transaction_id is required"))?,
-            receiver_party: self
-                .receiver_party
-                .ok_or(MpesaError::Message("receiver_party is required"))?,
-            receiver_identifier_type: self
-                .receiver_identifier_type
-                .unwrap_or(IdentifierTypes::Reversal),
-            result_url: self
-                .result_url
-                .ok_or(MpesaError::Message("result_url is required"))?,
-            timeout_url: self
-                .timeout_url
-                .ok_or(MpesaError::Message("timeout_url is required"))?,
+                .ok_or(MpesaError::Message("transaction_id is required".into()))?,
+            receiver_party: receiver_identifier_type.validate_party(
+                "receiver_party",
+                self.receiver_party
+                    .ok_or(MpesaError::Message("receiver_party is required".into()))?,
+            )?,
+            receiver_identifier_type,
+            result_url: self.client.callback_url(
+                "result_url",
+                self.result_url
+                    .ok_or(MpesaError::Message("result_url is required".into()))?,
+            )?,
+            timeout_url: self.client.callback_url(
+                "timeout_url",
+                self.timeout_url
+                    .ok_or(MpesaError::Message("timeout_url is required".into()))?,
+            )?,
             remarks: self.remarks.unwrap_or(stringify!(None)),
             occasion: self.occasion.unwrap_or(stringify!(None)),
             amount: self
                 .amount
-                .ok_or(MpesaError::Message("amount is required"))?,
+                .ok_or(MpesaError::Message("amount is required".into()))??,
         };
 
         self.client
@@ -206,6 +218,7 @@ impl<'mpesa> TransactionReversalBuilder<'mpesa> {
                 method: reqwest::Method::POST,
                 path: TRANSACTION_REVERSAL_URL,
                 body: payload,
+                idempotent: false,
             })
             .await
     }
@@ -2,9 +2,9 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::{CommandId, IdentifierTypes, Mpesa, MpesaError, MpesaResult};
+use crate::{CommandId, IdentifierTypes, Mpesa, MpesaError, MpesaResult, Url};
 
-const TRANSACTION_STATUS_URL: &str = "mpesa/transactionstatus/v1/query";
+pub const TRANSACTION_STATUS_URL: &str = "mpesa/transactionstatus/v1/query";
 
 #[derive(Debug, Serialize)]
 pub struct TransactionStatusPayload<'mpesa> {
@@ -17,13 +17,13 @@ pub struct TransactionStatusPayload<'mpesa> {
     #[serde(rename(serialize = "TransactionID"))]
     transaction_id: &'mpesa str,
     #[serde(rename = "PartyA")]
-    party_a: &'mpesa str,
+    party_a: String,
     #[serde(rename(serialize = "IdentifierType"))]
     identifier_type: IdentifierTypes,
     #[serde(rename(serialize = "ResultURL"))]
-    result_url: &'mpesa str,
+    result_url: Url,
     #[serde(rename(serialize = "QueueTimeOutURL"))]
-    timeout_url: &'mpesa str,
+    timeout_url: Url,
     #[serde(rename(serialize = "Remarks"))]
     remarks: &'mpesa str,
     #[serde(rename(serialize = "Occasion"))]
@@ -155,7 +155,9 @@ impl<'mpesa> TransactionStatusBuilder<'mpesa> {
     /// # Errors
     /// Returns a `MpesaError` on failure.
     pub async fn send(self) -> MpesaResult<TransactionStatusResponse> {
-        let credentials = self.client.gen_security_credentials()?;
+        let credentials = self.client.security_credential()?;
+
+        let identifier_type = self.identifier_type.unwrap_or(IdentifierTypes::ShortCode);
 
         let payload = TransactionStatusPayload {
             initiator: self.initiator,
@@ -163,17 +165,23 @@ impl<'mpesa> TransactionStatusBuilder<'mpesa> {
             command_id: self.command_id.unwrap_or(CommandId::TransactionStatusQuery),
             transaction_id: self
                 .transaction_id
-                .ok_or(MpesaError::Message("transaction_id is required"))?,
-            party_a: self
-                .party_a
-                .ok_or(MpesaError::Message("party_a is required"))?,
-            identifier_type: self.identifier_type.unwrap_or(IdentifierTypes::ShortCode),
-            result_url: self
-                .result_url
-                .ok_or(MpesaError::Message("result_url is required"))?,
-            timeout_url: self
-                .timeout_url
-                .ok_or(MpesaError::Message("timeout_url is required"))?,
+                .ok_or(MpesaError::Message("transaction_id is required".into()))?,
+            party_a: identifier_type.validate_party(
+                "party_a",
+                self.party_a
+                    .ok_or(MpesaError::Message("party_a is required".into()))?,
+            )?,
+            identifier_type,
+            result_url: self.client.callback_url(
+                "result_url",
+                self.result_url
+                    .ok_or(MpesaError::Message("result_url is required".into()))?,
+            )?,
+            timeout_url: self.client.callback_url(
+                "timeout_url",
+                self.timeout_url
+                    .ok_or(MpesaError::Message("timeout_url is required".into()))?,
+            )?,
             remarks: self.remarks.unwrap_or(stringify!(None)),
             occasion: self.occasion.unwrap_or(stringify!(None)),
         };
@@ -183,6 +191,9 @@ impl<'mpesa> TransactionStatusBuilder<'mpesa> {
                 method: reqwest::Method::POST,
                 path: TRANSACTION_STATUS_URL,
                 body: payload,
+                // Not retried, the status is delivered to the result url and a repeated request
+                // would start a second query and a duplicate callback
+                idempotent: false,
             })
             .await
     }
@@ -0,0 +1,9 @@
+//! Markers tracking at compile time which required fields of a typed builder are set
+
+/// A required field that has been set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Set;
+
+/// A required field that has not been set yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unset;
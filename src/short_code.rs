@@ -0,0 +1,192 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::errors::BuilderError;
+use crate::{MpesaError, MpesaResult};
+
+macro_rules! numeric_code {
+    ($(#[$meta:meta])* $name:ident, $what:literal) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(String);
+
+        impl $name {
+            #[doc = concat!("Parses a ", $what, ", surrounding whitespace is ignored")]
+            ///
+            /// # Errors
+            /// Returns a `BuilderError::ValidationError` if the value is not 5 to 7 digits
+            pub fn parse(code: &str) -> MpesaResult<Self> {
+                let code = code.trim();
+                if !(5..=7).contains(&code.len()) || !code.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(MpesaError::BuilderError(BuilderError::ValidationError(
+                        concat!("a ", $what, " must be 5 to 7 digits").into(),
+                    )));
+                }
+                Ok($name(code.into()))
+            }
+
+            #[doc = concat!("The ", $what)]
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(code: $name) -> Self {
+                code.0
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = MpesaError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                $name::parse(s)
+            }
+        }
+
+        impl TryFrom<&str> for $name {
+            type Error = MpesaError;
+
+            fn try_from(s: &str) -> Result<Self, Self::Error> {
+                $name::parse(s)
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = MpesaError;
+
+            fn try_from(s: String) -> Result<Self, Self::Error> {
+                $name::parse(&s)
+            }
+        }
+
+        impl TryFrom<u32> for $name {
+            type Error = MpesaError;
+
+            fn try_from(n: u32) -> Result<Self, Self::Error> {
+                $name::parse(&n.to_string())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.0)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            #[doc = concat!("Deserializes a JSON string or number holding a ", $what)]
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                #[derive(Deserialize)]
+                #[serde(untagged)]
+                enum Repr {
+                    Number(u32),
+                    String(String),
+                }
+
+                match Repr::deserialize(deserializer)? {
+                    Repr::Number(n) => $name::try_from(n),
+                    Repr::String(s) => $name::parse(&s),
+                }
+                .map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+numeric_code!(
+    /// An organization's M-Pesa short code, e.g. a PayBill number, of 5 to 7 digits
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use mpesa::ShortCode;
+    ///
+    /// let short_code: ShortCode = "600496".parse().unwrap();
+    /// assert_eq!(short_code.as_str(), "600496");
+    ///
+    /// assert!(ShortCode::parse("6004").is_err());
+    /// assert!(ShortCode::parse("60O496").is_err());
+    /// ```
+    ShortCode,
+    "short code"
+);
+
+numeric_code!(
+    /// A Buy Goods till number, of 5 to 7 digits
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use mpesa::TillNumber;
+    ///
+    /// let till_number = TillNumber::parse("174379").unwrap();
+    /// assert_eq!(till_number, "174379");
+    ///
+    /// assert!(TillNumber::parse("17437900").is_err());
+    /// ```
+    TillNumber,
+    "till number"
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_code_is_5_to_7_digits() {
+        for short_code in ["60049", "600496", "6004961", " 600496 "] {
+            assert_eq!(
+                ShortCode::parse(short_code).unwrap().as_str(),
+                short_code.trim()
+            );
+        }
+        for short_code in ["", "6004", "60049612", "60O496", "-60049", "600 496"] {
+            assert!(
+                matches!(
+                    ShortCode::parse(short_code),
+                    Err(MpesaError::BuilderError(BuilderError::ValidationError(_)))
+                ),
+                "{short_code} should be rejected"
+            );
+        }
+        assert!(TillNumber::parse("17408").is_ok());
+        assert!(TillNumber::parse("1740").is_err());
+    }
+
+    #[test]
+    fn test_short_code_serde() {
+        let short_code = ShortCode::parse("600496").unwrap();
+        assert_eq!(serde_json::to_string(&short_code).unwrap(), "\"600496\"");
+        assert_eq!(
+            serde_json::from_str::<ShortCode>("600496").unwrap(),
+            short_code
+        );
+        assert_eq!(
+            serde_json::from_str::<ShortCode>("\"600496\"").unwrap(),
+            short_code
+        );
+        assert!(serde_json::from_str::<TillNumber>("\"abc\"").is_err());
+    }
+}
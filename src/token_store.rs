@@ -0,0 +1,542 @@
+//!# Access token stores
+//!
+//! Every request to the Safaricom API requires an access token generated by the authorization API.
+//! Tokens are valid for the period reported by M-Pesa (usually an hour) so the `Mpesa` client
+//! keeps them in a [`TokenStore`] and only requests new ones once they expire.
+//!
+//! Two stores are provided:
+//! - [`TokenCache`]: the default, in-memory, store. Clones share the same tokens.
+//! - [`FileTokenStore`]: persists tokens to a file so that they survive restarts and can be
+//!   shared by several processes on the same host.
+//!
+//! Deployments running many replicas can share tokens through Redis, a database etc. by
+//! implementing the [`TokenStore`] trait.
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fmt::Debug;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{MpesaError, MpesaResult};
+
+/// Storage for access tokens, keyed by client key
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use mpesa::{Environment, Mpesa, MpesaResult, TokenStore};
+///
+/// #[derive(Debug)]
+/// struct RedisTokenStore;
+///
+/// #[async_trait::async_trait]
+/// impl TokenStore for RedisTokenStore {
+///     async fn get(&self, client_key: &str) -> MpesaResult<Option<String>> {
+///         // GET mpesa:token:{client_key}
+///         Ok(None)
+///     }
+///
+///     async fn set(&self, client_key: &str, access_token: &str, expires_in: Duration) -> MpesaResult<()> {
+///         // SET mpesa:token:{client_key} {access_token} EX {expires_in}
+///         Ok(())
+///     }
+///
+///     async fn remove(&self, client_key: &str) -> MpesaResult<()> {
+///         // DEL mpesa:token:{client_key}
+///         Ok(())
+///     }
+/// }
+///
+/// let client = Mpesa::new("client_key", "client_secret", Environment::Sandbox)
+///     .with_token_store(RedisTokenStore);
+/// ```
+#[async_trait]
+pub trait TokenStore: Debug + Send + Sync {
+    /// Gets the access token stored for `client_key`, `None` if there is no token or it has expired
+    async fn get(&self, client_key: &str) -> MpesaResult<Option<String>>;
+
+    /// Stores the `access_token` of `client_key`, valid for `expires_in`
+    async fn set(
+        &self,
+        client_key: &str,
+        access_token: &str,
+        expires_in: Duration,
+    ) -> MpesaResult<()>;
+
+    /// Removes the access token stored for `client_key`
+    async fn remove(&self, client_key: &str) -> MpesaResult<()>;
+}
+
+#[async_trait]
+impl<T: TokenStore + ?Sized> TokenStore for Arc<T> {
+    async fn get(&self, client_key: &str) -> MpesaResult<Option<String>> {
+        (**self).get(client_key).await
+    }
+
+    async fn set(
+        &self,
+        client_key: &str,
+        access_token: &str,
+        expires_in: Duration,
+    ) -> MpesaResult<()> {
+        (**self).set(client_key, access_token, expires_in).await
+    }
+
+    async fn remove(&self, client_key: &str) -> MpesaResult<()> {
+        (**self).remove(client_key).await
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedToken {
+    access_token: String,
+    expires_at: Instant,
+}
+
+/// In-memory cache of access tokens, keyed by client key
+///
+/// This is the default [`TokenStore`], every `Mpesa` client owns one unless another store is
+/// provided. Cloning the cache is cheap and the clones share the same tokens, so a single cache
+/// can be shared by several clients with different credentials.
+///
+/// # Example
+///
+/// ```rust
+/// use mpesa::{Environment, Mpesa, TokenCache};
+///
+/// let cache = TokenCache::new();
+///
+/// let paybill_a = Mpesa::new("client_key_a", "client_secret_a", Environment::Sandbox)
+///     .with_token_store(cache.clone());
+/// let paybill_b = Mpesa::new("client_key_b", "client_secret_b", Environment::Sandbox)
+///     .with_token_store(cache);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TokenCache {
+    tokens: Arc<Mutex<HashMap<String, CachedToken>>>,
+}
+
+impl TokenCache {
+    /// Creates a new, empty, `TokenCache`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Removes all cached access tokens
+    pub fn clear(&self) {
+        self.tokens.lock().unwrap().clear();
+    }
+
+    /// Number of cached access tokens, including those that have expired but not yet been evicted
+    pub fn len(&self) -> usize {
+        self.tokens.lock().unwrap().len()
+    }
+
+    /// Returns `true` if there are no cached access tokens
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl TokenStore for TokenCache {
+    async fn get(&self, client_key: &str) -> MpesaResult<Option<String>> {
+        let mut tokens = self.tokens.lock().unwrap();
+
+        match tokens.get(client_key) {
+            Some(token) if token.expires_at > Instant::now() => {
+                Ok(Some(token.access_token.clone()))
+            }
+            Some(_) => {
+                tokens.remove(client_key);
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn set(
+        &self,
+        client_key: &str,
+        access_token: &str,
+        expires_in: Duration,
+    ) -> MpesaResult<()> {
+        let token = CachedToken {
+            access_token: access_token.to_owned(),
+            expires_at: Instant::now() + expires_in,
+        };
+
+        self.tokens
+            .lock()
+            .unwrap()
+            .insert(client_key.to_owned(), token);
+        Ok(())
+    }
+
+    async fn remove(&self, client_key: &str) -> MpesaResult<()> {
+        self.tokens.lock().unwrap().remove(client_key);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredToken {
+    access_token: String,
+    /// Seconds since the unix epoch
+    expires_at: u64,
+}
+
+/// Stores access tokens in a JSON file
+///
+/// Several processes on the same host pointing to the same file share their tokens. Every
+/// access holds an advisory lock on a `<path>.lock` file, so concurrent updates from different
+/// processes are not lost, and writes are atomic, the file is replaced rather than modified in
+/// place. File access runs on tokio's blocking thread pool so token lookups do not stall the
+/// runtime.
+///
+/// A file that cannot be parsed, e.g. truncated by a crash, is treated as empty and replaced on
+/// the next write, so that it does not prevent authentication.
+///
+/// Access tokens are stored in plain text, on unix the file is created readable and writable
+/// only by its owner (mode `0600`).
+///
+/// # Example
+///
+/// ```rust
+/// use mpesa::{Environment, FileTokenStore, Mpesa};
+///
+/// let client = Mpesa::new("client_key", "client_secret", Environment::Sandbox)
+///     .with_token_store(FileTokenStore::new("/var/run/my-app/mpesa-tokens.json"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileTokenStore {
+    path: PathBuf,
+    lock: Arc<Mutex<()>>,
+}
+
+impl FileTokenStore {
+    /// Creates a new `FileTokenStore`, the file is created on the first write
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        FileTokenStore {
+            path: path.into(),
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Reads the stored tokens, a missing or unparsable file holds no tokens
+    fn read(&self) -> MpesaResult<HashMap<String, StoredToken>> {
+        match fs::read(&self.path) {
+            Ok(contents) if contents.is_empty() => Ok(HashMap::new()),
+            Ok(contents) => Ok(serde_json::from_slice(&contents).unwrap_or_else(|e| {
+                log::warn!(
+                    "ignoring invalid token store file {}: {e}",
+                    self.path.display()
+                );
+                HashMap::new()
+            })),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(e) => Err(MpesaError::TokenStoreError(Box::new(e))),
+        }
+    }
+
+    fn write(&self, tokens: &HashMap<String, StoredToken>) -> MpesaResult<()> {
+        // Unique per write so that stores sharing a path in one process do not clobber each
+        // other's temporary file
+        static WRITES: AtomicU64 = AtomicU64::new(0);
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            WRITES.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let contents = serde_json::to_vec(tokens)?;
+        create_private(&tmp, true)
+            .and_then(|mut file| file.write_all(&contents))
+            .and_then(|_| fs::rename(&tmp, &self.path))
+            .map_err(|e| {
+                let _ = fs::remove_file(&tmp);
+                MpesaError::TokenStoreError(Box::new(e))
+            })
+    }
+
+    /// Runs `f` on the blocking thread pool, holding the lock of the store and an exclusive
+    /// advisory lock on the lock file, shared with other processes
+    async fn blocking<T, F>(&self, f: F) -> MpesaResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&FileTokenStore) -> MpesaResult<T> + Send + 'static,
+    {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let _lock = store.lock.lock().unwrap();
+
+            let mut lock_path = store.path.clone().into_os_string();
+            lock_path.push(".lock");
+            let lock_file = create_private(&lock_path, false)
+                .map_err(|e| MpesaError::TokenStoreError(Box::new(e)))?;
+            lock_file
+                .lock_exclusive()
+                .map_err(|e| MpesaError::TokenStoreError(Box::new(e)))?;
+
+            // The advisory lock is released when `lock_file` is closed
+            f(&store)
+        })
+        .await
+        .map_err(|e| MpesaError::TokenStoreError(Box::new(e)))?
+    }
+}
+
+/// Opens, or creates, the file at `path` for writing, only accessible by its owner on unix
+fn create_private(path: &OsStr, truncate: bool) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(truncate);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        let file = options.mode(0o600).open(path)?;
+        // The mode is only applied to new files, a stale file keeps its own
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        Ok(file)
+    }
+    #[cfg(not(unix))]
+    options.open(path)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[async_trait]
+impl TokenStore for FileTokenStore {
+    async fn get(&self, client_key: &str) -> MpesaResult<Option<String>> {
+        let client_key = client_key.to_owned();
+
+        self.blocking(move |store| {
+            Ok(store
+                .read()?
+                .remove(&client_key)
+                .filter(|token| token.expires_at > now())
+                .map(|token| token.access_token))
+        })
+        .await
+    }
+
+    async fn set(
+        &self,
+        client_key: &str,
+        access_token: &str,
+        expires_in: Duration,
+    ) -> MpesaResult<()> {
+        let token = (client_key.to_owned(), access_token.to_owned());
+
+        self.blocking(move |store| {
+            let (client_key, access_token) = token;
+            let now = now();
+            let mut tokens = store.read()?;
+            tokens.retain(|_, token| token.expires_at > now);
+            tokens.insert(
+                client_key,
+                StoredToken {
+                    access_token,
+                    expires_at: now + expires_in.as_secs(),
+                },
+            );
+            store.write(&tokens)
+        })
+        .await
+    }
+
+    async fn remove(&self, client_key: &str) -> MpesaResult<()> {
+        let client_key = client_key.to_owned();
+
+        self.blocking(move |store| {
+            let mut tokens = store.read()?;
+            if tokens.remove(&client_key).is_some() {
+                store.write(&tokens)?;
+            }
+            Ok(())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "mpesa-rust-{}-{}-{}.json",
+            name,
+            std::process::id(),
+            now()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn test_token_cache_stores_tokens() {
+        let cache = TokenCache::new();
+        cache
+            .set("client_a", "token_a", Duration::from_secs(3600))
+            .await
+            .unwrap();
+        cache
+            .set("client_b", "token_b", Duration::from_secs(3600))
+            .await
+            .unwrap();
+        assert_eq!(
+            cache.get("client_a").await.unwrap().as_deref(),
+            Some("token_a")
+        );
+
+        cache.remove("client_a").await.unwrap();
+        assert!(cache.get("client_a").await.unwrap().is_none());
+        assert_eq!(cache.len(), 1);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_token_cache_evicts_expired_tokens() {
+        let cache = TokenCache::new();
+        cache
+            .set("client_a", "token_a", Duration::ZERO)
+            .await
+            .unwrap();
+        assert!(cache.get("client_a").await.unwrap().is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_file_token_store_persists_tokens() {
+        let path = temp_file("persists");
+        let store = FileTokenStore::new(&path);
+        assert!(store.get("client_a").await.unwrap().is_none());
+
+        store
+            .set("client_a", "token_a", Duration::from_secs(3600))
+            .await
+            .unwrap();
+        store
+            .set("client_b", "token_b", Duration::from_secs(3600))
+            .await
+            .unwrap();
+
+        // A different store, e.g in another process, reading the same file
+        let other = FileTokenStore::new(&path);
+        assert_eq!(
+            other.get("client_a").await.unwrap().as_deref(),
+            Some("token_a")
+        );
+
+        other.remove("client_a").await.unwrap();
+        assert!(store.get("client_a").await.unwrap().is_none());
+        assert_eq!(
+            store.get("client_b").await.unwrap().as_deref(),
+            Some("token_b")
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_file_token_store_ignores_expired_tokens() {
+        let path = temp_file("expired");
+        let store = FileTokenStore::new(&path);
+        store
+            .set("client_a", "token_a", Duration::ZERO)
+            .await
+            .unwrap();
+        assert!(store.get("client_a").await.unwrap().is_none());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_file_token_store_is_only_readable_by_owner() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = temp_file("permissions");
+        let store = FileTokenStore::new(&path);
+        store
+            .set("client_a", "token_a", Duration::from_secs(3600))
+            .await
+            .unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_file_token_store_recovers_from_invalid_file() {
+        let path = temp_file("invalid");
+        fs::write(&path, "{\"client_a\": {\"access_token\": \"tok").unwrap();
+        let store = FileTokenStore::new(&path);
+
+        assert!(store.get("client_a").await.unwrap().is_none());
+        store
+            .set("client_a", "token_a", Duration::from_secs(3600))
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get("client_a").await.unwrap().as_deref(),
+            Some("token_a")
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_file_token_stores_sharing_a_path_keep_each_others_writes() {
+        let path = temp_file("shared");
+        // Separate stores do not share the in-process lock, as if in different processes
+        let stores: Vec<_> = (0..8).map(|_| FileTokenStore::new(&path)).collect();
+
+        let writes = stores.iter().enumerate().map(|(i, store)| {
+            let store = store.clone();
+            tokio::spawn(async move {
+                store
+                    .set(
+                        &format!("client_{i}"),
+                        &format!("token_{i}"),
+                        Duration::from_secs(3600),
+                    )
+                    .await
+            })
+        });
+        for write in writes.collect::<Vec<_>>() {
+            write.await.unwrap().unwrap();
+        }
+
+        for i in 0..8 {
+            assert_eq!(
+                stores[0].get(&format!("client_{i}")).await.unwrap(),
+                Some(format!("token_{i}"))
+            );
+        }
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::errors::BuilderError;
+use crate::{MpesaError, MpesaResult};
+
+/// Keywords Safaricom rejects in callback URLs, matched case-insensitively
+const FORBIDDEN_KEYWORDS: [&str; 3] = ["m-pesa", "mpesa", "safaricom"];
+
+/// A callback URL M-Pesa sends results to
+///
+/// URLs must be absolute `http` or `https` URLs and must not contain the keywords `M-Pesa`,
+/// `Mpesa` or `Safaricom`, in any case, which Safaricom rejects. Requests sent to production
+/// additionally require `https`.
+///
+/// # Example
+///
+/// ```rust
+/// use mpesa::Url;
+///
+/// let url = Url::parse("https://example.com/callback").unwrap();
+/// assert!(url.is_https());
+///
+/// assert!(Url::parse("/callback").is_err());
+/// assert!(Url::parse("https://example.com/mpesa/callback").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Url(String);
+
+impl Url {
+    /// Parses a callback URL, surrounding whitespace is ignored
+    ///
+    /// # Errors
+    /// Returns a `BuilderError::ValidationError` if `url` is not an absolute `http(s)` URL or
+    /// contains a forbidden keyword
+    pub fn parse(url: &str) -> MpesaResult<Self> {
+        let url = url.trim();
+        let parsed = reqwest::Url::parse(url).map_err(|_| invalid("a URL must be absolute"))?;
+        if !matches!(parsed.scheme(), "http" | "https") || !parsed.has_host() {
+            return Err(invalid("a URL must be an http or https URL"));
+        }
+
+        let lowercase = url.to_lowercase();
+        if let Some(keyword) = FORBIDDEN_KEYWORDS
+            .iter()
+            .find(|keyword| lowercase.contains(*keyword))
+        {
+            return Err(invalid(&format!(
+                "a URL must not contain the keyword \"{keyword}\""
+            )));
+        }
+
+        Ok(Url(url.into()))
+    }
+
+    /// Whether the URL uses `https`, required in production
+    pub fn is_https(&self) -> bool {
+        self.0.to_ascii_lowercase().starts_with("https:")
+    }
+
+    /// The URL
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+fn invalid(reason: &str) -> MpesaError {
+    MpesaError::BuilderError(BuilderError::ValidationError(reason.into()))
+}
+
+impl Display for Url {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for Url {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<Url> for String {
+    fn from(url: Url) -> Self {
+        url.0
+    }
+}
+
+impl FromStr for Url {
+    type Err = MpesaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Url::parse(s)
+    }
+}
+
+impl TryFrom<&str> for Url {
+    type Error = MpesaError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        Url::parse(s)
+    }
+}
+
+impl Serialize for Url {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Url {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Url::parse(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_must_be_absolute_http() {
+        assert!(Url::parse("https://example.com/callback")
+            .unwrap()
+            .is_https());
+        assert!(!Url::parse("http://localhost:8080/callback")
+            .unwrap()
+            .is_https());
+        assert!(Url::parse("HTTPS://EXAMPLE.COM").unwrap().is_https());
+        for url in [
+            "",
+            "/callback",
+            "example.com/callback",
+            "ftp://example.com",
+            "mailto:a@b.c",
+        ] {
+            assert!(
+                matches!(
+                    Url::parse(url),
+                    Err(MpesaError::BuilderError(BuilderError::ValidationError(_)))
+                ),
+                "{url} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_url_must_not_contain_forbidden_keywords() {
+        for url in [
+            "https://example.com/mpesa/callback",
+            "https://example.com/M-Pesa",
+            "https://safaricom.example.com",
+            "https://example.com/callback?source=MPESA",
+        ] {
+            assert!(Url::parse(url).is_err(), "{url} should be rejected");
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//!# MPESA Webhooks
+//!
+//! An embeddable [axum](https://docs.rs/axum) `Router` that listens for the callbacks sent by
+//! M-Pesa, parses them into the typed payloads of the [`callbacks`](crate::callbacks) module and
+//! dispatches them to a user supplied [`WebhookHandler`]. Every route answers M-Pesa with the
+//! acknowledgement body it expects.
+//!
+//! Requires the `webhooks` cargo feature.
+//!
+//! The following `POST` routes are mounted, you can `nest` the router under any prefix:
+//!
+//! | Route                     | Payload                                                                   | Handler method                 |
+//! | ------------------------- | ------------------------------------------------------------------------- | ------------------------------ |
+//! | `/stk`                    | [`StkPushCallback`](crate::callbacks::StkPushCallback)                    | `stk_push`                     |
+//! | `/b2c/result`             | [`ResultCallback`](crate::callbacks::ResultCallback)                      | `b2c_result`                   |
+//! | `/b2b/result`             | [`ResultCallback`](crate::callbacks::ResultCallback)                      | `b2b_result`                   |
+//! | `/reversal/result`        | [`ResultCallback`](crate::callbacks::ResultCallback)                      | `transaction_reversal_result`  |
+//! | `/status/result`          | [`ResultCallback`](crate::callbacks::ResultCallback)                      | `transaction_status_result`    |
+//! | `/balance/result`         | [`ResultCallback`](crate::callbacks::ResultCallback)                      | `account_balance_result`       |
+//! | `/c2b/validation`         | [`C2bValidationRequest`](crate::callbacks::C2bValidationRequest)          | `c2b_validation`               |
+//! | `/c2b/confirmation`       | [`C2bConfirmation`](crate::callbacks::C2bConfirmation)                    | `c2b_confirmation`             |
+//! | `/bill-manager/payment`   | [`BillManagerPaymentNotification`](crate::callbacks::BillManagerPaymentNotification) | `bill_manager_payment` |
+//!
+//! Payloads that cannot be parsed are answered with a `400 Bad Request`.
+//!
+//! # Example
+//! ```rust,no_run
+//! use mpesa::callbacks::{CallbackResult, StkCallback};
+//! use mpesa::webhooks::{self, WebhookHandler};
+//!
+//! struct Handler;
+//!
+//! #[async_trait::async_trait]
+//! impl WebhookHandler for Handler {
+//!     async fn stk_push(&self, callback: StkCallback) {
+//!         println!("{} completed: {}", callback.checkout_request_id, callback.is_success());
+//!     }
+//!
+//!     async fn b2c_result(&self, result: CallbackResult) {
+//!         println!("{} completed: {}", result.conversation_id, result.is_success());
+//!     }
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let app = axum::Router::new().nest("/mpesa", webhooks::router(Handler));
+//!
+//!     axum::Server::bind(&"0.0.0.0:3000".parse().unwrap())
+//!         .serve(app.into_make_service())
+//!         .await
+//!         .unwrap();
+//! }
+//! ```
+
+use std::future::Future;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::{post, MethodRouter};
+use axum::{Json, Router};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use crate::callbacks::{
+    BillManagerPaymentNotification, C2bConfirmation, C2bValidationRequest, CallbackResult,
+    ResultCallback, StkCallback, StkPushCallback, ValidationResponse,
+};
+
+pub const STK_PUSH_PATH: &str = "/stk";
+pub const B2C_RESULT_PATH: &str = "/b2c/result";
+pub const B2B_RESULT_PATH: &str = "/b2b/result";
+pub const TRANSACTION_REVERSAL_RESULT_PATH: &str = "/reversal/result";
+pub const TRANSACTION_STATUS_RESULT_PATH: &str = "/status/result";
+pub const ACCOUNT_BALANCE_RESULT_PATH: &str = "/balance/result";
+pub const C2B_VALIDATION_PATH: &str = "/c2b/validation";
+pub const C2B_CONFIRMATION_PATH: &str = "/c2b/confirmation";
+pub const BILL_MANAGER_PAYMENT_PATH: &str = "/bill-manager/payment";
+
+/// Receives the callbacks parsed by the [`router`]
+///
+/// Every method has a default implementation that ignores the callback, so only the
+/// callbacks you are interested in need to be implemented.
+#[async_trait]
+pub trait WebhookHandler: Send + Sync + 'static {
+    /// Called with the result of an Mpesa Express/ STK push request
+    async fn stk_push(&self, _callback: StkCallback) {}
+
+    /// Called with the result of a B2C request
+    async fn b2c_result(&self, _result: CallbackResult) {}
+
+    /// Called with the result of a B2B request
+    async fn b2b_result(&self, _result: CallbackResult) {}
+
+    /// Called with the result of a Transaction Reversal request
+    async fn transaction_reversal_result(&self, _result: CallbackResult) {}
+
+    /// Called with the result of a Transaction Status request
+    async fn transaction_status_result(&self, _result: CallbackResult) {}
+
+    /// Called with the result of an Account Balance request
+    async fn account_balance_result(&self, _result: CallbackResult) {}
+
+    /// Called when M-Pesa asks whether a C2B payment should be completed.
+    ///
+    /// Return a rejecting [`ValidationResponse`] to cancel the payment. Defaults to accepting
+    /// every payment.
+    async fn c2b_validation(&self, _request: C2bValidationRequest) -> ValidationResponse {
+        ValidationResponse::accept()
+    }
+
+    /// Called when a C2B payment has been completed
+    async fn c2b_confirmation(&self, _confirmation: C2bConfirmation) {}
+
+    /// Called when a customer pays a Bill Manager invoice
+    async fn bill_manager_payment(&self, _notification: BillManagerPaymentNotification) {}
+}
+
+type Response = (StatusCode, Json<Value>);
+
+/// Creates a `Router` with a route for every M-Pesa callback, dispatching to the `handler`
+pub fn router<H: WebhookHandler>(handler: H) -> Router {
+    Router::new()
+        .route(
+            STK_PUSH_PATH,
+            handle(|handler: Arc<H>, callback: StkPushCallback| async move {
+                handler.stk_push(callback.body.stk_callback).await;
+                accepted()
+            }),
+        )
+        .route(
+            B2C_RESULT_PATH,
+            handle(|handler: Arc<H>, callback: ResultCallback| async move {
+                handler.b2c_result(callback.result).await;
+                accepted()
+            }),
+        )
+        .route(
+            B2B_RESULT_PATH,
+            handle(|handler: Arc<H>, callback: ResultCallback| async move {
+                handler.b2b_result(callback.result).await;
+                accepted()
+            }),
+        )
+        .route(
+            TRANSACTION_REVERSAL_RESULT_PATH,
+            handle(|handler: Arc<H>, callback: ResultCallback| async move {
+                handler.transaction_reversal_result(callback.result).await;
+                accepted()
+            }),
+        )
+        .route(
+            TRANSACTION_STATUS_RESULT_PATH,
+            handle(|handler: Arc<H>, callback: ResultCallback| async move {
+                handler.transaction_status_result(callback.result).await;
+                accepted()
+            }),
+        )
+        .route(
+            ACCOUNT_BALANCE_RESULT_PATH,
+            handle(|handler: Arc<H>, callback: ResultCallback| async move {
+                handler.account_balance_result(callback.result).await;
+                accepted()
+            }),
+        )
+        .route(
+            C2B_VALIDATION_PATH,
+            handle(
+                |handler: Arc<H>, request: C2bValidationRequest| async move {
+                    let response = handler.c2b_validation(request).await;
+                    (StatusCode::OK, Json(json!(response)))
+                },
+            ),
+        )
+        .route(
+            C2B_CONFIRMATION_PATH,
+            handle(
+                |handler: Arc<H>, confirmation: C2bConfirmation| async move {
+                    handler.c2b_confirmation(confirmation).await;
+                    accepted()
+                },
+            ),
+        )
+        .route(
+            BILL_MANAGER_PAYMENT_PATH,
+            handle(
+                |handler: Arc<H>, notification: BillManagerPaymentNotification| async move {
+                    handler.bill_manager_payment(notification).await;
+                    (
+                        StatusCode::OK,
+                        Json(json!({ "rescode": "200", "resmsg": "success" })),
+                    )
+                },
+            ),
+        )
+        .with_state(Arc::new(handler))
+}
+
+/// Acknowledgement expected by M-Pesa for STK push, result and C2B confirmation callbacks,
+/// the same body as an accepting [`ValidationResponse`]
+fn accepted() -> Response {
+    (StatusCode::OK, Json(json!(ValidationResponse::accept())))
+}
+
+/// Creates a `POST` route that parses the body into a `T` and passes it to `dispatch`.
+/// Bodies that cannot be parsed are answered with a `400 Bad Request`.
+fn handle<H, T, F, Fut>(dispatch: F) -> MethodRouter<Arc<H>>
+where
+    H: WebhookHandler,
+    T: DeserializeOwned + Send + 'static,
+    F: Fn(Arc<H>, T) -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = Response> + Send,
+{
+    post(
+        move |State(handler): State<Arc<H>>, body: Bytes| async move {
+            match serde_json::from_slice::<T>(&body) {
+                Ok(payload) => dispatch(handler, payload).await,
+                Err(e) => (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "ResultCode": 1, "ResultDesc": e.to_string() })),
+                ),
+            }
+        },
+    )
+}
@@ -1,4 +1,4 @@
-use mpesa::MpesaError;
+use mpesa::{BuilderError, MpesaError};
 use serde_json::json;
 use wiremock::matchers::{method, path};
 use wiremock::{Mock, ResponseTemplate};
@@ -72,6 +72,60 @@ async fn b2c_fails_if_no_amount_is_provided() {
     }
 }
 
+#[tokio::test]
+async fn b2c_sends_amount_as_whole_shillings() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/b2c/v1/paymentrequest"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "OriginatorConversationID": "29464-48063588-1",
+            "ConversationID": "AG_20230206_201056794190723278ff",
+            "ResponseDescription": "Accept the service request successfully.",
+            "ResponseCode": "0"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    client
+        .b2c("testapi496")
+        .party_a("600496")
+        .party_b("254708374149")
+        .result_url("https://testdomain.com/ok")
+        .timeout_url("https://testdomain.com/err")
+        .amount(1000.0)
+        .send()
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let body: serde_json::Value = requests.last().unwrap().body_json().unwrap();
+    assert_eq!(body["Amount"], json!(1000));
+}
+
+#[tokio::test]
+async fn b2c_fails_if_amount_is_not_whole_shillings() {
+    let (client, server) = get_mpesa_client!(expected_auth_requests = 0);
+    Mock::given(method("POST"))
+        .and(path("/mpesa/b2c/v1/paymentrequest"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    for amount in [999.99, -1000.0, f64::NAN] {
+        let err = client
+            .b2c("testapi496")
+            .party_a("600496")
+            .party_b("254708374149")
+            .result_url("https://testdomain.com/ok")
+            .timeout_url("https://testdomain.com/err")
+            .amount(amount)
+            .send()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MpesaError::Message(_)));
+    }
+}
+
 #[tokio::test]
 async fn b2c_fails_if_no_party_a_is_provided() {
     let (client, server) = get_mpesa_client!(expected_auth_requests = 0);
@@ -138,6 +192,34 @@ async fn b2c_fails_if_no_party_b_is_provided() {
     }
 }
 
+#[tokio::test]
+async fn b2c_fails_if_party_b_is_invalid() {
+    let (client, server) = get_mpesa_client!(expected_auth_requests = 0);
+    Mock::given(method("POST"))
+        .and(path("/mpesa/b2c/v1/paymentrequest"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let err = client
+        .b2c("testapi496")
+        .party_a("600496")
+        .party_b("0201234567")
+        .result_url("https://testdomain.com/ok")
+        .timeout_url("https://testdomain.com/err")
+        .amount(1000)
+        .send()
+        .await
+        .unwrap_err();
+    let MpesaError::BuilderError(BuilderError::ValidationError(msg)) = err else {
+        panic!("Expected BuilderError::ValidationError, but found {}", err);
+    };
+    assert_eq!(
+        msg,
+        "party_b is invalid: a phone number must be a Kenyan mobile number, e.g. 0712345678"
+    );
+}
+
 #[tokio::test]
 async fn b2c_fails_if_no_result_url_is_provided() {
     let (client, server) = get_mpesa_client!(expected_auth_requests = 0);
@@ -203,3 +285,60 @@ async fn b2c_fails_if_no_queue_timeout_is_provided() {
         panic!("Expected error");
     }
 }
+
+#[tokio::test]
+async fn b2c_typed_success() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/b2c/v1/paymentrequest"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "OriginatorConversationID": "29464-48063588-1",
+            "ConversationID": "AG_20230206_201056794190723278ff",
+            "ResponseDescription": "Accept the service request successfully.",
+            "ResponseCode": "0"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let response = client
+        .b2c_typed("testapi496")
+        .amount(1000)
+        .remarks("Salary")
+        .party_b("0708374149")
+        .result_url("https://testdomain.com/ok")
+        .party_a("600496")
+        .timeout_url("https://testdomain.com/err")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.originator_conversation_id, "29464-48063588-1");
+
+    let requests = server.received_requests().await.unwrap();
+    let body: serde_json::Value = requests.last().unwrap().body_json().unwrap();
+    assert_eq!(body["PartyA"], json!("600496"));
+    assert_eq!(body["PartyB"], json!("254708374149"));
+    assert_eq!(body["Remarks"], json!("Salary"));
+    assert_eq!(body["ResultURL"], json!("https://testdomain.com/ok"));
+}
+
+#[tokio::test]
+async fn b2c_typed_still_validates_values() {
+    let (client, server) = get_mpesa_client!(expected_auth_requests = 0);
+    Mock::given(method("POST"))
+        .and(path("/mpesa/b2c/v1/paymentrequest"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let err = client
+        .b2c_typed("testapi496")
+        .party_a("600496")
+        .party_b("254708374149")
+        .result_url("https://testdomain.com/ok")
+        .timeout_url("/err")
+        .amount(1000)
+        .send()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MpesaError::BuilderError(_)));
+}
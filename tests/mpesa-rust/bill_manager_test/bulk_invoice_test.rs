@@ -1,5 +1,5 @@
 use chrono::prelude::Utc;
-use mpesa::{Invoice, InvoiceItem, MpesaError};
+use mpesa::{Amount, Invoice, InvoiceItem, MpesaError, Msisdn};
 use serde_json::json;
 use wiremock::matchers::{method, path};
 use wiremock::{Mock, ResponseTemplate};
@@ -27,15 +27,15 @@ async fn bulk_invoice_success() {
     let response = client
         .bulk_invoice()
         .invoices(vec![Invoice {
-            amount: 1000.0,
+            amount: Amount::new(1000).unwrap(),
             account_reference: "John Doe",
             billed_full_name: "John Doe",
             billed_period: "August 2021",
-            billed_phone_number: "0712345678",
+            billed_phone_number: Msisdn::parse("0712345678").unwrap(),
             due_date: Utc::now(),
             external_reference: "INV2345",
             invoice_items: Some(vec![InvoiceItem {
-                amount: 1000.0,
+                amount: Amount::new(1000).unwrap(),
                 item_name: "An item",
             }]),
             invoice_name: "Invoice 001",
@@ -44,6 +44,7 @@ async fn bulk_invoice_success() {
         .await
         .unwrap();
     assert_eq!(response.response_code, "200");
+    assert!(response.response_code.is_success());
     assert_eq!(response.response_message, "Success");
     assert_eq!(response.status_message, "Invoice sent successfully");
 }
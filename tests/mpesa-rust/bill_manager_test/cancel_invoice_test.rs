@@ -30,6 +30,7 @@ async fn cancel_invoice_success() {
         .await
         .unwrap();
     assert_eq!(response.response_code, "200");
+    assert!(response.response_code.is_success());
     assert_eq!(response.response_message, "Success");
     assert_eq!(response.status_message, "Invoice cancelled successfully");
 }
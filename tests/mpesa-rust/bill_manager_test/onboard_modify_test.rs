@@ -30,5 +30,6 @@ async fn onboard_modify_success() {
         .await
         .unwrap();
     assert_eq!(response.response_code, "200");
+    assert!(response.response_code.is_success());
     assert_eq!(response.response_message, "Biller updated successfully");
 }
@@ -35,6 +35,7 @@ async fn onboard_success() {
         .unwrap();
     assert_eq!(response.app_key, "kfpB9X4o0H");
     assert_eq!(response.response_code, "200");
+    assert!(response.response_code.is_success());
     assert_eq!(response.response_message, "Success");
 }
 
@@ -37,9 +37,38 @@ async fn reconciliation_success() {
         .await
         .unwrap();
     assert_eq!(response.response_code, "200");
+    assert!(response.response_code.is_success());
     assert_eq!(response.response_message, "Success");
 }
 
+#[tokio::test]
+async fn reconciliation_sends_phone_number_in_local_format() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/v1/billmanager-invoice/reconciliation"))
+        .respond_with(sample_response())
+        .expect(1)
+        .mount(&server)
+        .await;
+    client
+        .reconciliation()
+        .account_reference("John Doe")
+        .external_reference("INV2345")
+        .full_name("John Doe")
+        .invoice_name("Invoice 001")
+        .paid_amount(1000)
+        .payment_date(Utc::now())
+        .phone_number("+254 712 345 678")
+        .transaction_id("TRANSACTION_ID")
+        .send()
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let body: serde_json::Value = requests.last().unwrap().body_json().unwrap();
+    assert_eq!(body["phoneNumber"], json!("0712345678"));
+}
+
 #[tokio::test]
 async fn reconciliation_fails_if_no_account_reference_is_provided() {
     let (client, server) = get_mpesa_client!(expected_auth_requests = 0);
@@ -279,3 +308,34 @@ async fn reconciliation_fails_if_no_transaction_id_is_provided() {
         panic!("Expected error")
     }
 }
+
+#[tokio::test]
+async fn reconciliation_typed_success() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/v1/billmanager-invoice/reconciliation"))
+        .respond_with(sample_response())
+        .expect(1)
+        .mount(&server)
+        .await;
+    let response = client
+        .reconciliation_typed()
+        .transaction_id("TRANSACTION_ID")
+        .phone_number("0712345678")
+        .payment_date(Utc::now())
+        .paid_amount(1000)
+        .invoice_name("Invoice 001")
+        .full_name("John Doe")
+        .external_reference("INV2345")
+        .account_reference("John Doe")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.response_code, "200");
+    assert!(response.response_code.is_success());
+
+    let requests = server.received_requests().await.unwrap();
+    let body: serde_json::Value = requests.last().unwrap().body_json().unwrap();
+    assert_eq!(body["externalReference"], json!("INV2345"));
+    assert_eq!(body["phoneNumber"], json!("0712345678"));
+}
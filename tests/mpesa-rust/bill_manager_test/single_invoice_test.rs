@@ -1,5 +1,5 @@
 use chrono::prelude::Utc;
-use mpesa::{InvoiceItem, MpesaError};
+use mpesa::{Amount, InvoiceItem, MpesaError};
 use serde_json::json;
 use wiremock::matchers::{method, path};
 use wiremock::{Mock, ResponseTemplate};
@@ -34,7 +34,7 @@ async fn single_invoice_success() {
         .due_date(Utc::now())
         .external_reference("INV2345")
         .invoice_items(vec![InvoiceItem {
-            amount: 1000.0,
+            amount: Amount::new(1000).unwrap(),
             item_name: "An item",
         }])
         .invoice_name("Invoice 001")
@@ -42,6 +42,7 @@ async fn single_invoice_success() {
         .await
         .unwrap();
     assert_eq!(response.response_code, "200");
+    assert!(response.response_code.is_success());
     assert_eq!(response.response_message, "Success");
     assert_eq!(response.status_message, "Invoice sent successfully");
 }
@@ -1,4 +1,4 @@
-use mpesa::MpesaError;
+use mpesa::{BuilderError, MpesaError};
 use serde_json::json;
 use wiremock::matchers::{method, path};
 use wiremock::{Mock, ResponseTemplate};
@@ -124,3 +124,67 @@ async fn c2b_register_fails_if_validation_url_is_not_provided() {
         panic!("Expected error");
     }
 }
+
+#[tokio::test]
+async fn c2b_register_fails_if_short_code_is_invalid() {
+    let (client, server) = get_mpesa_client!(expected_auth_requests = 0);
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let err = client
+        .c2b_register()
+        .short_code("6004")
+        .confirmation_url("https://testdomain.com/true")
+        .validation_url("https://testdomain.com/valid")
+        .send()
+        .await
+        .unwrap_err();
+    let MpesaError::BuilderError(BuilderError::ValidationError(msg)) = err else {
+        panic!("Expected BuilderError::ValidationError, but found {}", err);
+    };
+    assert_eq!(
+        msg,
+        "short_code is invalid: a short code must be 5 to 7 digits"
+    );
+}
+
+#[tokio::test]
+async fn c2b_register_fails_if_url_is_invalid() {
+    let (client, server) = get_mpesa_client!(expected_auth_requests = 0);
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    for (url, expected) in [
+        (
+            "https://testdomain.com/mpesa/valid",
+            "validation_url is invalid: a URL must not contain the keyword \"mpesa\"",
+        ),
+        (
+            "https://Safaricom.testdomain.com/valid",
+            "validation_url is invalid: a URL must not contain the keyword \"safaricom\"",
+        ),
+        (
+            "/valid",
+            "validation_url is invalid: a URL must be absolute",
+        ),
+    ] {
+        let err = client
+            .c2b_register()
+            .short_code("600496")
+            .confirmation_url("https://testdomain.com/true")
+            .validation_url(url)
+            .send()
+            .await
+            .unwrap_err();
+        let MpesaError::BuilderError(BuilderError::ValidationError(msg)) = err else {
+            panic!("Expected BuilderError::ValidationError, but found {}", err);
+        };
+        assert_eq!(msg, expected);
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use mpesa::{Mpesa, MpesaBuilder, MpesaError};
+use serde_json::json;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::get_mpesa_client;
+use crate::test_environment::TestEnvironment;
+
+#[tokio::test]
+async fn client_can_be_shared_across_tasks() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "OriginatorCoversationID": "29464-48063588-1",
+            "ResponseDescription": "Accept the service request successfully.",
+            "ResponseCode": "0"
+        })))
+        .expect(4)
+        .mount(&server)
+        .await;
+    let client = Arc::new(client);
+
+    let tasks = (0..4).map(|_| {
+        let client = Arc::clone(&client);
+        tokio::spawn(async move {
+            client
+                .c2b_register()
+                .short_code("600496")
+                .confirmation_url("https://testdomain.com/true")
+                .validation_url("https://testdomain.com/valid")
+                .send()
+                .await
+        })
+    });
+
+    for task in tasks.collect::<Vec<_>>() {
+        task.await.unwrap().unwrap();
+    }
+}
+
+async fn build_client(server: &MockServer, builder: MpesaBuilder) -> Mpesa {
+    Mock::given(method("GET"))
+        .and(path("/oauth/v1/generate"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "dummy_access_token",
+            "expires_in": "3600"
+        })))
+        .mount(server)
+        .await;
+
+    builder
+        .client_key("client_key")
+        .client_secret("client_secret")
+        .environment(TestEnvironment::new(server).await)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn builder_sets_user_agent() {
+    let server = MockServer::start().await;
+    let client = build_client(&server, Mpesa::builder().user_agent("my-app/1.0")).await;
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .and(header("User-Agent", "my-app/1.0"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "OriginatorCoversationID": "29464-48063588-1",
+            "ResponseDescription": "Accept the service request successfully.",
+            "ResponseCode": "0"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    client
+        .c2b_register()
+        .short_code("600496")
+        .confirmation_url("https://testdomain.com/true")
+        .validation_url("https://testdomain.com/valid")
+        .send()
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn builder_sets_request_timeout() {
+    let server = MockServer::start().await;
+    let client = build_client(
+        &server,
+        Mpesa::builder().timeout(Duration::from_millis(100)),
+    )
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+        .mount(&server)
+        .await;
+
+    let err = client
+        .c2b_register()
+        .short_code("600496")
+        .confirmation_url("https://testdomain.com/true")
+        .validation_url("https://testdomain.com/valid")
+        .send()
+        .await
+        .unwrap_err();
+
+    match err {
+        MpesaError::NetworkError(e) => assert!(e.is_timeout()),
+        err => panic!("unexpected error: {err}"),
+    }
+}
+
+#[tokio::test]
+async fn builder_uses_the_client_pass_key() {
+    let server = MockServer::start().await;
+    let client = build_client(&server, Mpesa::builder().pass_key("client_pass_key")).await;
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpushquery/v1/query"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "ResponseCode": "0",
+            "ResponseDescription": "The service request has been accepted successsfully",
+            "MerchantRequestID": "22205-34066-1",
+            "CheckoutRequestID": "ws_CO_13012021093521236557",
+            "ResultCode": "0",
+            "ResultDesc": "The service request is processed successfully."
+        })))
+        .mount(&server)
+        .await;
+
+    client
+        .express_query("174379")
+        .checkout_request_id("ws_CO_13012021093521236557")
+        .send()
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let body: serde_json::Value = requests.last().unwrap().body_json().unwrap();
+    let password = BASE64.decode(body["Password"].as_str().unwrap()).unwrap();
+    assert!(String::from_utf8(password)
+        .unwrap()
+        .starts_with("174379client_pass_key"));
+}
@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use mpesa::{Environment, MpesaConfig, MpesaError};
+
+fn write_config(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "mpesa-rust-config-test-{}-{}",
+        std::process::id(),
+        name
+    ));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+const PROFILES_JSON: &str = r#"{
+    "profiles": {
+        "sandbox": {
+            "client_key": "sandbox_key",
+            "client_secret": "sandbox_secret",
+            "environment": "sandbox"
+        },
+        "paybill_a": {
+            "client_key": "paybill_a_key",
+            "client_secret": "paybill_a_secret",
+            "environment": "production",
+            "short_code": "600496",
+            "result_url": "https://example.com/payments/result"
+        }
+    }
+}"#;
+
+#[test]
+fn config_is_loaded_from_json_file() {
+    let path = write_config(
+        "single.json",
+        r#"{"client_key": "key", "client_secret": "secret", "environment": "sandbox"}"#,
+    );
+
+    let config = MpesaConfig::from_file(&path).unwrap();
+
+    assert_eq!(config.client_key, "key");
+    assert_eq!(config.environment, Environment::Sandbox);
+    assert!(config.build().is_ok());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn profiles_are_loaded_from_json_file() {
+    let path = write_config("profiles.json", PROFILES_JSON);
+
+    let sandbox = MpesaConfig::from_file_profile(&path, "sandbox").unwrap();
+    let paybill_a = MpesaConfig::from_file_profile(&path, "paybill_a").unwrap();
+
+    assert_eq!(sandbox.client_key, "sandbox_key");
+    assert_eq!(paybill_a.environment, Environment::Production);
+    assert_eq!(paybill_a.short_code.unwrap(), "600496");
+
+    let err = MpesaConfig::from_file_profile(&path, "paybill_b").unwrap_err();
+    assert!(matches!(err, MpesaError::ConfigError(_)));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn missing_config_file_is_an_error() {
+    let err = MpesaConfig::from_file("/nonexistent/mpesa.json").unwrap_err();
+
+    assert!(matches!(err, MpesaError::ConfigError(_)));
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn profiles_are_loaded_from_toml_file() {
+    let path = write_config(
+        "profiles.toml",
+        r#"
+[profiles.sandbox]
+client_key = "sandbox_key"
+client_secret = "sandbox_secret"
+environment = "sandbox"
+
+[profiles.paybill_a]
+client_key = "paybill_a_key"
+client_secret = "paybill_a_secret"
+environment = "production"
+initiator_password = "initiator_password"
+queue_timeout_url = "https://example.com/payments/timeout"
+"#,
+    );
+
+    let config = MpesaConfig::from_file_profile(&path, "paybill_a").unwrap();
+
+    assert_eq!(config.client_key, "paybill_a_key");
+    assert_eq!(config.environment, Environment::Production);
+    assert_eq!(
+        config.queue_timeout_url.unwrap().as_str(),
+        "https://example.com/payments/timeout"
+    );
+    std::fs::remove_file(path).unwrap();
+}
@@ -1,4 +1,5 @@
 use mpesa::services::{DynamicQR, DynamicQRRequest};
+use mpesa::{Amount, MpesaError, TransactionType};
 use serde_json::json;
 use wiremock::matchers::{method, path};
 use wiremock::{Mock, ResponseTemplate};
@@ -24,7 +25,8 @@ async fn dynamic_qr_code_test_using_builder_pattern() {
 
     let response = client
         .dynamic_qr()
-        .amount(2000)
+        .try_amount(2000)
+        .unwrap()
         .credit_party_identifier("17408")
         .merchant_name("SafaricomLTD")
         .ref_no("rf38f04")
@@ -56,7 +58,7 @@ async fn dynamic_qr_code_test_using_struct_initialization() {
     });
 
     let request = DynamicQRRequest {
-        amount: 2000,
+        amount: Amount::new(2000).unwrap(),
         credit_party_identifier: "17408",
         merchant_name: "SafaricomLTD",
         ref_no: "rf38f04",
@@ -80,3 +82,40 @@ async fn dynamic_qr_code_test_using_struct_initialization() {
     );
     assert_eq!(response.response_code, "0");
 }
+
+#[tokio::test]
+async fn dynamic_qr_code_fails_if_credit_party_identifier_is_invalid() {
+    let (client, server) = get_mpesa_client!(expected_auth_requests = 0);
+
+    Mock::given(method("POST"))
+        .and(path("/mpesa/qrcode/v1/generate"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    for (transaction_type, credit_party_identifier) in [
+        (TransactionType::BG, "174"),
+        (TransactionType::PayBill, "PAYBILL"),
+        (TransactionType::SendMoney, "17408"),
+    ] {
+        let err = client
+            .dynamic_qr()
+            .try_amount(2000)
+            .unwrap()
+            .credit_party_identifier(credit_party_identifier)
+            .merchant_name("SafaricomLTD")
+            .ref_no("rf38f04")
+            .size("300")
+            .transaction_type(transaction_type)
+            .build()
+            .unwrap()
+            .send()
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, MpesaError::BuilderError(_) | MpesaError::Message(_)),
+            "Unexpected error {err}"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use mpesa::{MpesaError, MpesaResponseCode};
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::get_mpesa_client;
+
+#[tokio::test]
+async fn express_query_success() {
+    let (client, server) = get_mpesa_client!();
+    let sample_response_body = json!({
+        "ResponseCode": "0",
+        "ResponseDescription": "The service request has been accepted successsfully",
+        "MerchantRequestID": "22205-34066-1",
+        "CheckoutRequestID": "ws_CO_13012021093521236557",
+        "ResultCode": "0",
+        "ResultDesc": "The service request is processed successfully."
+    });
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpushquery/v1/query"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_response_body))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let response = client
+        .express_query("174379")
+        .checkout_request_id("ws_CO_13012021093521236557")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.merchant_request_id, "22205-34066-1");
+    assert_eq!(response.checkout_request_id, "ws_CO_13012021093521236557");
+    assert_eq!(response.response_code, "0");
+    assert_eq!(response.result_code, "0");
+    assert_eq!(
+        response.result_desc,
+        "The service request is processed successfully."
+    );
+}
+
+#[tokio::test]
+async fn express_query_accepts_numeric_result_code() {
+    let (client, server) = get_mpesa_client!();
+    let sample_response_body = json!({
+        "ResponseCode": "0",
+        "ResponseDescription": "The service request has been accepted successsfully",
+        "MerchantRequestID": "22205-34066-1",
+        "CheckoutRequestID": "ws_CO_13012021093521236557",
+        "ResultCode": 1032,
+        "ResultDesc": "Request cancelled by user"
+    });
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpushquery/v1/query"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_response_body))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let response = client
+        .express_query("174379")
+        .checkout_request_id("ws_CO_13012021093521236557")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.result_code, "1032");
+    assert_eq!(response.result_code, MpesaResponseCode::CancelledByUser);
+    assert!(!response.result_code.is_transient_callback_result());
+    assert_eq!(response.result_desc, "Request cancelled by user");
+}
+
+#[tokio::test]
+async fn express_query_fails_if_no_checkout_request_id_is_provided() {
+    let (client, server) = get_mpesa_client!(expected_auth_requests = 0);
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpushquery/v1/query"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    if let Err(e) = client.express_query("174379").send().await {
+        let MpesaError::Message(msg) = e else {
+            panic!("Expected MpesaError::Message, but found {}", e);
+        };
+        assert_eq!(msg, "checkout_request_id is required")
+    } else {
+        panic!("Expected error");
+    }
+}
@@ -1,33 +1,7 @@
-use mpesa::ApiEnvironment;
-use wiremock::MockServer;
-
-#[derive(Debug, Clone)]
-pub struct TestEnvironment {
-    pub server_url: String,
-}
-
-impl TestEnvironment {
-    pub async fn new(server: &MockServer) -> Self {
-        TestEnvironment {
-            server_url: server.uri(),
-        }
-    }
-}
-
-impl ApiEnvironment for TestEnvironment {
-    fn base_url(&self) -> &str {
-        &self.server_url
-    }
-
-    fn get_certificate(&self) -> &str {
-        include_str!("../../src/certificates/sandbox")
-    }
-}
-
 #[macro_export]
 macro_rules! get_mpesa_client {
     () => {{
-        use $crate::helpers::TestEnvironment;
+        use $crate::test_environment::TestEnvironment;
         use mpesa::Mpesa;
         use wiremock::{MockServer, Mock, ResponseTemplate};
         use serde_json::json;
@@ -54,7 +28,7 @@ macro_rules! get_mpesa_client {
     }};
 
     (expected_auth_requests = $expected_requests: expr) => {{
-        use $crate::helpers::TestEnvironment;
+        use $crate::test_environment::TestEnvironment;
         use mpesa::Mpesa;
         use wiremock::{MockServer, Mock, ResponseTemplate};
         use serde_json::json;
@@ -103,8 +77,6 @@ macro_rules! get_mpesa_client {
 
 #[cfg(test)]
 mod tests {
-    use crate::get_mpesa_client;
-
     #[tokio::test]
     async fn test_client_will_not_authenticate_with_wrong_credentials() {
         let client = get_mpesa_client!(
@@ -1,21 +1,58 @@
-#[cfg(test)]
+#[cfg(all(test, feature = "account_balance"))]
 mod account_balance_test;
-#[cfg(test)]
+#[cfg(all(test, feature = "b2b"))]
 mod b2b_test;
-#[cfg(test)]
+#[cfg(all(test, feature = "b2c"))]
 mod b2c_test;
-#[cfg(test)]
+#[cfg(all(test, feature = "bill_manager"))]
 mod bill_manager_test;
-#[cfg(test)]
+#[cfg(all(test, feature = "c2b_register"))]
 mod c2b_register_test;
-#[cfg(test)]
+#[cfg(all(test, feature = "c2b_simulate"))]
 mod c2b_simulate_test;
-
+#[cfg(all(test, feature = "c2b_register", feature = "express_query"))]
+mod client_test;
+#[cfg(test)]
+mod config_test;
+#[cfg(all(test, feature = "dynamic_qr"))]
 mod dynamic_qr_tests;
+#[cfg(all(test, feature = "express_query"))]
+mod express_query_test;
 mod helpers;
-#[cfg(test)]
+#[cfg(all(test, feature = "c2b_register"))]
+mod middleware_test;
+#[cfg(all(test, feature = "b2c", feature = "express_request"))]
+mod production_guard_test;
+#[cfg(all(test, feature = "c2b_register"))]
+mod rate_limit_test;
+#[cfg(all(test, feature = "c2b_simulate", feature = "express_query"))]
+mod retry_test;
+#[cfg(all(test, feature = "express_request"))]
 mod stk_push_test;
-#[cfg(test)]
+#[cfg(all(
+    test,
+    any(
+        feature = "account_balance",
+        feature = "b2b",
+        feature = "b2c",
+        feature = "bill_manager",
+        feature = "c2b_register",
+        feature = "c2b_simulate",
+        feature = "dynamic_qr",
+        feature = "express_query",
+        feature = "express_request",
+        feature = "transaction_reversal",
+        feature = "transaction_status"
+    )
+))]
+mod test_environment;
+#[cfg(all(test, feature = "c2b_register"))]
+mod token_refresh_test;
+#[cfg(all(test, feature = "c2b_register"))]
+mod token_store_test;
+#[cfg(all(test, feature = "transaction_reversal"))]
 mod transaction_reversal_test;
-#[cfg(test)]
+#[cfg(all(test, feature = "transaction_status"))]
 mod transaction_status_test;
+#[cfg(all(test, feature = "webhooks"))]
+mod webhooks_test;
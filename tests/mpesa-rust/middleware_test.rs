@@ -0,0 +1,190 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use mpesa::middleware::{Middleware, Request, Response};
+use mpesa::{MpesaError, MpesaResult};
+use serde_json::json;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::get_mpesa_client;
+
+#[derive(Debug)]
+struct CorrelationId(&'static str);
+
+#[async_trait]
+impl Middleware for CorrelationId {
+    async fn before_send(&self, request: &mut Request) -> MpesaResult<()> {
+        request
+            .headers_mut()
+            .insert("X-Correlation-ID", self.0.parse().unwrap());
+        Ok(())
+    }
+}
+
+/// Records the hooks called, in order
+#[derive(Debug, Clone, Default)]
+struct Recorder {
+    name: &'static str,
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl Middleware for Recorder {
+    async fn before_send(&self, request: &mut Request) -> MpesaResult<()> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("{} before {}", self.name, request.url().path()));
+        Ok(())
+    }
+
+    async fn after_response(&self, request: &Request, response: &Response) -> MpesaResult<()> {
+        self.calls.lock().unwrap().push(format!(
+            "{} after {} {}",
+            self.name,
+            request.url().path(),
+            response.status().as_u16()
+        ));
+        Ok(())
+    }
+
+    async fn on_error(&self, _error: &MpesaError) {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("{} error", self.name));
+    }
+}
+
+#[derive(Debug)]
+struct Unavailable;
+
+#[async_trait]
+impl Middleware for Unavailable {
+    async fn before_send(&self, request: &mut Request) -> MpesaResult<()> {
+        if request.url().path().starts_with("/mpesa") {
+            return Err(MpesaError::Message("injected fault".into()));
+        }
+        Ok(())
+    }
+}
+
+async fn mount_c2b_register(server: &MockServer, status: u16) {
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .respond_with(ResponseTemplate::new(status).set_body_json(json!({
+            "OriginatorCoversationID": "29464-48063588-1",
+            "ResponseDescription": "Accept the service request successfully.",
+            "ResponseCode": "0"
+        })))
+        .mount(server)
+        .await;
+}
+
+async fn c2b_register(client: &mpesa::Mpesa) -> MpesaResult<()> {
+    client
+        .c2b_register()
+        .short_code("600496")
+        .confirmation_url("https://testdomain.com/true")
+        .validation_url("https://testdomain.com/valid")
+        .send()
+        .await
+        .map(|_| ())
+}
+
+#[tokio::test]
+async fn middleware_can_inject_headers() {
+    let (client, server) = get_mpesa_client!();
+    let client = client.with_middleware(CorrelationId("b6f2e0d1"));
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .and(header("X-Correlation-ID", "b6f2e0d1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "OriginatorCoversationID": "29464-48063588-1",
+            "ResponseDescription": "Accept the service request successfully.",
+            "ResponseCode": "0"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    c2b_register(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn middleware_run_in_order_for_every_request() {
+    let (client, server) = get_mpesa_client!();
+    let first = Recorder {
+        name: "first",
+        ..Default::default()
+    };
+    let second = Recorder {
+        name: "second",
+        calls: Arc::clone(&first.calls),
+    };
+    let client = client
+        .with_middleware(first.clone())
+        .with_middleware(second);
+    mount_c2b_register(&server, 200).await;
+
+    c2b_register(&client).await.unwrap();
+
+    assert_eq!(
+        *first.calls.lock().unwrap(),
+        vec![
+            "first before /oauth/v1/generate",
+            "second before /oauth/v1/generate",
+            "first after /oauth/v1/generate 200",
+            "second after /oauth/v1/generate 200",
+            "first before /mpesa/c2b/v1/registerurl",
+            "second before /mpesa/c2b/v1/registerurl",
+            "first after /mpesa/c2b/v1/registerurl 200",
+            "second after /mpesa/c2b/v1/registerurl 200",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn middleware_are_notified_of_errors() {
+    let (client, server) = get_mpesa_client!();
+    let recorder = Recorder {
+        name: "recorder",
+        ..Default::default()
+    };
+    let client = client.with_middleware(recorder.clone());
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "requestId": "11728-2929992-1",
+            "errorCode": "400.002.02",
+            "errorMessage": "Bad Request - Invalid ShortCode"
+        })))
+        .mount(&server)
+        .await;
+
+    let err = c2b_register(&client).await.unwrap_err();
+
+    assert!(matches!(err, MpesaError::Service(_)));
+    assert_eq!(
+        recorder.calls.lock().unwrap().last().unwrap(),
+        "recorder error"
+    );
+}
+
+#[tokio::test]
+async fn middleware_can_inject_faults() {
+    let (client, server) = get_mpesa_client!();
+    let client = client.with_middleware(Unavailable);
+    mount_c2b_register(&server, 200).await;
+
+    let err = c2b_register(&client).await.unwrap_err();
+
+    assert!(matches!(err, MpesaError::Message(msg) if msg == "injected fault"));
+    assert!(server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .all(|request| request.url.path() != "/mpesa/c2b/v1/registerurl"));
+}
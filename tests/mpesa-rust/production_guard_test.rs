@@ -0,0 +1,137 @@
+use mpesa::{ApiEnvironment, BuilderError, Mpesa, MpesaError};
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// A mock server standing in for the production API
+#[derive(Debug, Clone)]
+struct ProductionTestEnvironment {
+    server_url: String,
+}
+
+impl ApiEnvironment for ProductionTestEnvironment {
+    fn base_url(&self) -> &str {
+        &self.server_url
+    }
+
+    fn get_certificate(&self) -> &str {
+        include_str!("../../src/certificates/sandbox")
+    }
+
+    fn is_production(&self) -> bool {
+        true
+    }
+}
+
+async fn production_client() -> (Mpesa, MockServer) {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/oauth/v1/generate"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "dummy_access_token",
+            "expires_in": "3600"
+        })))
+        .mount(&server)
+        .await;
+    let client = Mpesa::new(
+        "client_key",
+        "client_secret",
+        ProductionTestEnvironment {
+            server_url: server.uri(),
+        },
+    );
+    (client, server)
+}
+
+#[tokio::test]
+async fn express_request_requires_pass_key_in_production() {
+    let (client, server) = production_client().await;
+
+    let err = client
+        .express_request("174379")
+        .phone_number("254708374149")
+        .amount(500)
+        .callback_url("https://test.example.com/api")
+        .send()
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        MpesaError::SandboxDefaultInProduction("pass_key")
+    ));
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn express_request_uses_pass_key_in_production() {
+    let (client, server) = production_client().await;
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpush/v1/processrequest"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "CheckoutRequestID": "ws_CO_DMZ_12321_23423476",
+            "MerchantRequestID": "16813-1590513-1",
+            "ResponseCode": "0",
+            "ResponseDescription": "Accept the service request successfully.",
+            "CustomerMessage": "Success. Request accepted for processing"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    client
+        .express_request("174379")
+        .pass_key("production_pass_key")
+        .phone_number("254708374149")
+        .amount(500)
+        .callback_url("https://test.example.com/api")
+        .send()
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn express_request_requires_https_callback_url_in_production() {
+    let (client, server) = production_client().await;
+
+    let err = client
+        .express_request("174379")
+        .pass_key("production_pass_key")
+        .phone_number("254708374149")
+        .amount(500)
+        .callback_url("http://test.example.com/api")
+        .send()
+        .await
+        .unwrap_err();
+
+    let MpesaError::BuilderError(BuilderError::ValidationError(msg)) = err else {
+        panic!("Expected BuilderError::ValidationError, but found {}", err);
+    };
+    assert_eq!(
+        msg,
+        "callback_url is invalid: a URL must use https in production"
+    );
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn b2c_requires_initiator_password_in_production() {
+    let (client, server) = production_client().await;
+
+    let err = client
+        .b2c("testapi496")
+        .party_a("600496")
+        .party_b("254708374149")
+        .result_url("https://testdomain.com/ok")
+        .timeout_url("https://testdomain.com/err")
+        .amount(1000)
+        .send()
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        MpesaError::SandboxDefaultInProduction("initiator_password")
+    ));
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
@@ -0,0 +1,91 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use mpesa::services::C2B_REGISTER_URL;
+use mpesa::RateLimit;
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::get_mpesa_client;
+
+async fn mount_c2b_register(server: &MockServer, delay: Duration) {
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({
+                    "OriginatorCoversationID": "29464-48063588-1",
+                    "ResponseDescription": "Accept the service request successfully.",
+                    "ResponseCode": "0"
+                }))
+                .set_delay(delay),
+        )
+        .mount(server)
+        .await;
+}
+
+async fn c2b_register_concurrently(client: mpesa::Mpesa, requests: usize) -> Duration {
+    let client = Arc::new(client);
+    // Authenticate beforehand so that only the registrations are timed
+    assert!(client.is_connected().await);
+    let start = Instant::now();
+
+    let tasks = (0..requests)
+        .map(|_| {
+            let client = Arc::clone(&client);
+            tokio::spawn(async move {
+                client
+                    .c2b_register()
+                    .short_code("600496")
+                    .confirmation_url("https://testdomain.com/true")
+                    .validation_url("https://testdomain.com/valid")
+                    .send()
+                    .await
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for task in tasks {
+        task.await.unwrap().unwrap();
+    }
+    start.elapsed()
+}
+
+#[tokio::test]
+async fn endpoint_rate_limit_spaces_out_requests() {
+    let (client, server) = get_mpesa_client!();
+    let client =
+        client.with_endpoint_rate_limit(C2B_REGISTER_URL, RateLimit::per_second(20).burst(2));
+    mount_c2b_register(&server, Duration::ZERO).await;
+
+    // 2 requests are let through at once, the other 4 are spaced out by 50ms
+    let elapsed = c2b_register_concurrently(client, 6).await;
+
+    assert!(elapsed >= Duration::from_millis(200), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(1000), "{elapsed:?}");
+}
+
+#[tokio::test]
+async fn client_rate_limit_caps_requests_in_flight() {
+    let (client, server) = get_mpesa_client!();
+    let client = client.with_rate_limit(RateLimit::unlimited().max_in_flight(2));
+    mount_c2b_register(&server, Duration::from_millis(100)).await;
+
+    // 3 batches of 2 concurrent requests
+    let elapsed = c2b_register_concurrently(client, 6).await;
+
+    assert!(elapsed >= Duration::from_millis(300), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(1000), "{elapsed:?}");
+}
+
+#[tokio::test]
+async fn requests_are_not_limited_by_default() {
+    let (client, server) = get_mpesa_client!();
+    mount_c2b_register(&server, Duration::from_millis(100)).await;
+
+    let elapsed = c2b_register_concurrently(client, 6).await;
+
+    // Sequential requests would take at least 600ms
+    assert!(elapsed < Duration::from_millis(500), "{elapsed:?}");
+}
@@ -0,0 +1,197 @@
+use std::time::Duration;
+
+use mpesa::{MpesaError, RetryPolicy};
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::get_mpesa_client;
+
+fn retry_policy() -> RetryPolicy {
+    RetryPolicy::new()
+        .max_attempts(3)
+        .initial_backoff(Duration::from_millis(1))
+}
+
+fn system_busy() -> ResponseTemplate {
+    ResponseTemplate::new(503).set_body_json(json!({
+        "requestId": "11728-2929992-1",
+        "errorCode": "500.003.02",
+        "errorMessage": "System is busy. Please try again in few minutes."
+    }))
+}
+
+/// Fails the first `failures` requests to `url_path` with a `503`
+async fn mount_failures(server: &MockServer, url_path: &str, failures: u64) {
+    Mock::given(method("POST"))
+        .and(path(url_path))
+        .respond_with(system_busy())
+        .up_to_n_times(failures)
+        .with_priority(1)
+        .mount(server)
+        .await;
+}
+
+async fn mount_express_query(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpushquery/v1/query"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "ResponseCode": "0",
+            "ResponseDescription": "The service request has been accepted successsfully",
+            "MerchantRequestID": "22205-34066-1",
+            "CheckoutRequestID": "ws_CO_13012021093521236557",
+            "ResultCode": "0",
+            "ResultDesc": "The service request is processed successfully."
+        })))
+        .mount(server)
+        .await;
+}
+
+async fn requests_to(server: &MockServer, url_path: &str) -> usize {
+    server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .filter(|request| request.url.path() == url_path)
+        .count()
+}
+
+#[tokio::test]
+async fn idempotent_requests_are_retried_until_they_succeed() {
+    let (client, server) = get_mpesa_client!();
+    let client = client.with_retry_policy(retry_policy());
+    mount_failures(&server, "/mpesa/stkpushquery/v1/query", 2).await;
+    mount_express_query(&server).await;
+
+    let response = client
+        .express_query("174379")
+        .checkout_request_id("ws_CO_13012021093521236557")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.result_code, "0");
+    assert_eq!(
+        requests_to(&server, "/mpesa/stkpushquery/v1/query").await,
+        3
+    );
+}
+
+#[tokio::test]
+async fn retries_stop_after_max_attempts() {
+    let (client, server) = get_mpesa_client!();
+    let client = client.with_retry_policy(retry_policy());
+    mount_failures(&server, "/mpesa/stkpushquery/v1/query", 3).await;
+    mount_express_query(&server).await;
+
+    let err = client
+        .express_query("174379")
+        .checkout_request_id("ws_CO_13012021093521236557")
+        .send()
+        .await
+        .unwrap_err();
+
+    match err {
+        MpesaError::Service(err) => assert_eq!(err.error_code, "500.003.02"),
+        err => panic!("unexpected error: {err}"),
+    }
+    assert_eq!(
+        requests_to(&server, "/mpesa/stkpushquery/v1/query").await,
+        3
+    );
+}
+
+#[tokio::test]
+async fn retryable_response_codes_are_retried() {
+    let (client, server) = get_mpesa_client!();
+    let client = client.with_retry_policy(retry_policy());
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpushquery/v1/query"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "ResponseCode": "26",
+            "ResponseDescription": "System busy",
+            "MerchantRequestID": "22205-34066-1",
+            "CheckoutRequestID": "ws_CO_13012021093521236557",
+            "ResultCode": "26",
+            "ResultDesc": "System busy"
+        })))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    mount_express_query(&server).await;
+
+    let response = client
+        .express_query("174379")
+        .checkout_request_id("ws_CO_13012021093521236557")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.response_code, "0");
+    assert_eq!(
+        requests_to(&server, "/mpesa/stkpushquery/v1/query").await,
+        2
+    );
+}
+
+#[tokio::test]
+async fn requests_are_not_retried_by_default() {
+    let (client, server) = get_mpesa_client!();
+    mount_failures(&server, "/mpesa/stkpushquery/v1/query", 1).await;
+    mount_express_query(&server).await;
+
+    let err = client
+        .express_query("174379")
+        .checkout_request_id("ws_CO_13012021093521236557")
+        .send()
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, MpesaError::Service(_)));
+    assert_eq!(
+        requests_to(&server, "/mpesa/stkpushquery/v1/query").await,
+        1
+    );
+}
+
+#[tokio::test]
+async fn non_idempotent_requests_are_not_retried() {
+    let (client, server) = get_mpesa_client!();
+    let client = client.with_retry_policy(retry_policy());
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/simulate"))
+        .respond_with(system_busy())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let err = client
+        .c2b_simulate()
+        .short_code("600496")
+        .msisdn("254700000000")
+        .amount(1000)
+        .bill_ref_number("2")
+        .send()
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, MpesaError::Service(_)));
+}
+
+#[tokio::test]
+async fn authentication_is_retried() {
+    let (client, server) = get_mpesa_client!();
+    let client = client.with_retry_policy(retry_policy());
+    Mock::given(method("GET"))
+        .and(path("/oauth/v1/generate"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(2)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+
+    assert!(client.is_connected().await);
+    assert_eq!(requests_to(&server, "/oauth/v1/generate").await, 3);
+}
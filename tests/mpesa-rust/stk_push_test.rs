@@ -1,4 +1,4 @@
-use mpesa::MpesaError;
+use mpesa::{BuilderError, MpesaError, ServiceErrorKind};
 use serde_json::json;
 use wiremock::matchers::{method, path};
 use wiremock::{Mock, ResponseTemplate};
@@ -41,6 +41,64 @@ async fn stk_push_success_success() {
     );
 }
 
+#[tokio::test]
+async fn stk_push_normalizes_phone_number() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpush/v1/processrequest"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MerchantRequestID": "16813-1590513-1",
+            "CheckoutRequestID": "ws_CO_DMZ_12321_23423476",
+            "ResponseDescription": "Accept the service request successfully.",
+            "ResponseCode": "0",
+            "CustomerMessage": "Success. Request accepeted for processing"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    client
+        .express_request("174379")
+        .phone_number("0712 345 678")
+        .amount(500)
+        .callback_url("https://test.example.com/api")
+        .send()
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let body: serde_json::Value = requests.last().unwrap().body_json().unwrap();
+    assert_eq!(body["PhoneNumber"], json!("254712345678"));
+    assert_eq!(body["PartyA"], json!("254712345678"));
+}
+
+#[tokio::test]
+async fn stk_push_fails_if_phone_number_is_invalid() {
+    let (client, server) = get_mpesa_client!(expected_auth_requests = 0);
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpush/v1/processrequest"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    for phone_number in ["0201234567", "07123456", "+255712345678"] {
+        let err = client
+            .express_request("174379")
+            .phone_number(phone_number)
+            .amount(500)
+            .callback_url("https://test.example.com/api")
+            .send()
+            .await
+            .unwrap_err();
+        let MpesaError::BuilderError(BuilderError::ValidationError(msg)) = err else {
+            panic!("Expected BuilderError::ValidationError, but found {}", err);
+        };
+        assert_eq!(
+            msg,
+            "phone_number is invalid: a phone number must be a Kenyan mobile number, e.g. 0712345678"
+        );
+    }
+}
+
 #[tokio::test]
 async fn stk_push_fails_if_no_amount_is_provided() {
     let (client, server) = get_mpesa_client!(expected_auth_requests = 0);
@@ -136,3 +194,128 @@ async fn stk_push_fails_if_no_phone_number_is_provided() {
         panic!("Expected error");
     }
 }
+
+#[tokio::test]
+async fn stk_push_classifies_service_errors() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpush/v1/processrequest"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "requestId": "11728-2929992-1",
+            "errorCode": "400.002.02",
+            "errorMessage": "Bad Request - Invalid Amount"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let err = client
+        .express_request("174379")
+        .phone_number("254708374149")
+        .amount(500)
+        .callback_url("https://test.example.com/api")
+        .send()
+        .await
+        .unwrap_err();
+    assert_eq!(err.http_context().unwrap().status, 400);
+    let MpesaError::Service(err) = err else {
+        panic!("expected a service error, got {err:?}");
+    };
+    assert_eq!(
+        err.kind(),
+        ServiceErrorKind::BadRequest {
+            field: Some("Amount".into())
+        }
+    );
+}
+
+#[tokio::test]
+async fn stk_push_reports_non_json_error_bodies() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpush/v1/processrequest"))
+        .respond_with(
+            ResponseTemplate::new(502)
+                .set_body_string("<html><body><h1>502 Bad Gateway</h1></body></html>"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    let err = client
+        .express_request("174379")
+        .phone_number("254708374149")
+        .amount(500)
+        .callback_url("https://test.example.com/api")
+        .send()
+        .await
+        .unwrap_err();
+    match err {
+        MpesaError::UnexpectedResponse(http) => {
+            assert_eq!(http.status, 502);
+            assert_eq!(http.path, "/mpesa/stkpush/v1/processrequest");
+            assert!(http.body.contains("502 Bad Gateway"));
+        }
+        e => panic!("expected an unexpected response error, got {e:?}"),
+    }
+}
+
+#[tokio::test]
+async fn stk_push_keeps_http_context_on_decode_errors() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpush/v1/processrequest"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MerchantRequestID": "16813-1590513-1",
+            "ResponseCode": "0",
+            "PhoneNumber": "254708374149"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let err = client
+        .express_request("174379")
+        .phone_number("254708374149")
+        .amount(500)
+        .callback_url("https://test.example.com/api")
+        .send()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MpesaError::DecodeError { .. }));
+    let http = err.http_context().unwrap();
+    assert_eq!(http.status, 200);
+    assert_eq!(http.path, "/mpesa/stkpush/v1/processrequest");
+    assert!(http.body.contains("16813-1590513-1"));
+    assert!(!http.body.contains("254708374149"));
+}
+
+#[tokio::test]
+async fn stk_push_typed_success() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpush/v1/processrequest"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MerchantRequestID": "16813-1590513-1",
+            "CheckoutRequestID": "ws_CO_DMZ_12321_23423476",
+            "ResponseDescription": "Accept the service request successfully.",
+            "ResponseCode": "0",
+            "CustomerMessage": "Success. Request accepeted for processing"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let response = client
+        .express_request_typed("174379")
+        .callback_url("https://test.example.com/api")
+        .account_ref("INV2345")
+        .phone_number("0708374149")
+        .amount(500)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.merchant_request_id, "16813-1590513-1");
+
+    let requests = server.received_requests().await.unwrap();
+    let body: serde_json::Value = requests.last().unwrap().body_json().unwrap();
+    assert_eq!(body["PhoneNumber"], json!("254708374149"));
+    assert_eq!(body["AccountReference"], json!("INV2345"));
+    assert_eq!(body["PartyB"], json!("174379"));
+}
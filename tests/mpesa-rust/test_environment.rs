@@ -0,0 +1,25 @@
+use mpesa::ApiEnvironment;
+use wiremock::MockServer;
+
+#[derive(Debug, Clone)]
+pub struct TestEnvironment {
+    pub server_url: String,
+}
+
+impl TestEnvironment {
+    pub async fn new(server: &MockServer) -> Self {
+        TestEnvironment {
+            server_url: server.uri(),
+        }
+    }
+}
+
+impl ApiEnvironment for TestEnvironment {
+    fn base_url(&self) -> &str {
+        &self.server_url
+    }
+
+    fn get_certificate(&self) -> &str {
+        include_str!("../../src/certificates/sandbox")
+    }
+}
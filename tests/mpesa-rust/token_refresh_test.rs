@@ -0,0 +1,150 @@
+use mpesa::MpesaError;
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::get_mpesa_client;
+
+fn invalid_token() -> ResponseTemplate {
+    ResponseTemplate::new(401).set_body_json(json!({
+        "requestId": "11728-2929992-1",
+        "errorCode": "404.001.03",
+        "errorMessage": "Invalid Access Token"
+    }))
+}
+
+async fn requests_to(server: &MockServer, url_path: &str) -> usize {
+    server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .filter(|request| request.url.path() == url_path)
+        .count()
+}
+
+async fn c2b_register(client: &mpesa::Mpesa) -> Result<(), MpesaError> {
+    client
+        .c2b_register()
+        .short_code("600496")
+        .confirmation_url("https://testdomain.com/true")
+        .validation_url("https://testdomain.com/valid")
+        .send()
+        .await
+        .map(|_| ())
+}
+
+#[tokio::test]
+async fn request_is_replayed_with_a_new_token_after_invalid_token() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .respond_with(invalid_token())
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "OriginatorCoversationID": "29464-48063588-1",
+            "ResponseDescription": "Accept the service request successfully.",
+            "ResponseCode": "0"
+        })))
+        .mount(&server)
+        .await;
+
+    c2b_register(&client).await.unwrap();
+
+    assert_eq!(requests_to(&server, "/mpesa/c2b/v1/registerurl").await, 2);
+    assert_eq!(requests_to(&server, "/oauth/v1/generate").await, 2);
+}
+
+#[tokio::test]
+async fn request_is_replayed_only_once() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .respond_with(invalid_token())
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let err = c2b_register(&client).await.unwrap_err();
+
+    match err {
+        MpesaError::Service(err) => assert!(err.is_invalid_access_token()),
+        err => panic!("unexpected error: {err}"),
+    }
+}
+
+#[tokio::test]
+async fn other_service_errors_are_not_replayed() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "requestId": "11728-2929992-1",
+            "errorCode": "400.002.02",
+            "errorMessage": "Bad Request - Invalid ShortCode"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let err = c2b_register(&client).await.unwrap_err();
+
+    assert!(matches!(err, MpesaError::Service(_)));
+    assert_eq!(requests_to(&server, "/oauth/v1/generate").await, 1);
+}
+
+#[tokio::test]
+async fn refresh_token_requests_a_new_token() {
+    let (client, server) = get_mpesa_client!();
+
+    assert!(client.is_connected().await);
+    client.refresh_token().await.unwrap();
+    assert!(client.is_connected().await);
+
+    assert_eq!(requests_to(&server, "/oauth/v1/generate").await, 2);
+}
+
+#[tokio::test]
+async fn concurrent_requests_share_a_single_new_token() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("GET"))
+        .and(path("/oauth/v1/generate"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({
+                    "access_token": "dummy_access_token",
+                    "expires_in": "3600"
+                }))
+                .set_delay(std::time::Duration::from_millis(100)),
+        )
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "OriginatorCoversationID": "29464-48063588-1",
+            "ResponseDescription": "Accept the service request successfully.",
+            "ResponseCode": "0"
+        })))
+        .mount(&server)
+        .await;
+
+    let requests: Vec<_> = (0..10)
+        .map(|_| {
+            let client = client.clone();
+            tokio::spawn(async move { c2b_register(&client).await })
+        })
+        .collect();
+    for request in requests {
+        request.await.unwrap().unwrap();
+    }
+
+    assert_eq!(requests_to(&server, "/oauth/v1/generate").await, 1);
+    assert_eq!(requests_to(&server, "/mpesa/c2b/v1/registerurl").await, 10);
+}
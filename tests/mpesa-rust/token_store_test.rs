@@ -0,0 +1,152 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use mpesa::{FileTokenStore, MpesaError, MpesaResult, TokenCache, TokenStore};
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::get_mpesa_client;
+
+#[derive(Debug, Default)]
+struct CountingTokenStore {
+    inner: TokenCache,
+    gets: AtomicUsize,
+    sets: AtomicUsize,
+}
+
+#[async_trait]
+impl TokenStore for CountingTokenStore {
+    async fn get(&self, client_key: &str) -> MpesaResult<Option<String>> {
+        self.gets.fetch_add(1, Ordering::SeqCst);
+        self.inner.get(client_key).await
+    }
+
+    async fn set(
+        &self,
+        client_key: &str,
+        access_token: &str,
+        expires_in: Duration,
+    ) -> MpesaResult<()> {
+        self.sets.fetch_add(1, Ordering::SeqCst);
+        self.inner.set(client_key, access_token, expires_in).await
+    }
+
+    async fn remove(&self, client_key: &str) -> MpesaResult<()> {
+        self.inner.remove(client_key).await
+    }
+}
+
+#[derive(Debug)]
+struct FailingTokenStore;
+
+#[async_trait]
+impl TokenStore for FailingTokenStore {
+    async fn get(&self, _client_key: &str) -> MpesaResult<Option<String>> {
+        Err(MpesaError::TokenStoreError("connection refused".into()))
+    }
+
+    async fn set(&self, _: &str, _: &str, _: Duration) -> MpesaResult<()> {
+        Err(MpesaError::TokenStoreError("connection refused".into()))
+    }
+
+    async fn remove(&self, _client_key: &str) -> MpesaResult<()> {
+        Ok(())
+    }
+}
+
+async fn auth_requests(server: &MockServer) -> usize {
+    server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .filter(|request| request.url.path() == "/oauth/v1/generate")
+        .count()
+}
+
+async fn mount_c2b_register(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "OriginatorCoversationID": "29464-48063588-1",
+            "ResponseDescription": "Accept the service request successfully.",
+            "ResponseCode": "0"
+        })))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn custom_token_store_is_consulted_before_authenticating() {
+    let (client, server) = get_mpesa_client!();
+    let store = Arc::new(CountingTokenStore::default());
+    let client = client.with_token_store(store.clone());
+    mount_c2b_register(&server).await;
+
+    for _ in 0..3 {
+        client
+            .c2b_register()
+            .short_code("600496")
+            .confirmation_url("https://testdomain.com/true")
+            .validation_url("https://testdomain.com/valid")
+            .send()
+            .await
+            .unwrap();
+    }
+
+    // The first request checks the store again once it holds the authentication lock
+    assert_eq!(store.gets.load(Ordering::SeqCst), 4);
+    assert_eq!(store.sets.load(Ordering::SeqCst), 1);
+    assert_eq!(auth_requests(&server).await, 1);
+}
+
+#[tokio::test]
+async fn file_token_store_shares_tokens_between_clients() {
+    let file = std::env::temp_dir().join(format!(
+        "mpesa-rust-token-store-test-{}.json",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&file);
+    let (client, server) = get_mpesa_client!();
+    mount_c2b_register(&server).await;
+
+    // Two clients, e.g in different replicas, using the same file
+    let first = client.clone().with_token_store(FileTokenStore::new(&file));
+    let second = client.with_token_store(FileTokenStore::new(&file));
+
+    for client in [&first, &second] {
+        client
+            .c2b_register()
+            .short_code("600496")
+            .confirmation_url("https://testdomain.com/true")
+            .validation_url("https://testdomain.com/valid")
+            .send()
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(auth_requests(&server).await, 1);
+    std::fs::remove_file(file).unwrap();
+}
+
+#[tokio::test]
+async fn token_store_errors_are_returned() {
+    let (client, server) = get_mpesa_client!();
+    let client = client.with_token_store(FailingTokenStore);
+    mount_c2b_register(&server).await;
+
+    let err = client
+        .c2b_register()
+        .short_code("600496")
+        .confirmation_url("https://testdomain.com/true")
+        .validation_url("https://testdomain.com/valid")
+        .send()
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, MpesaError::TokenStoreError(_)));
+    assert_eq!(auth_requests(&server).await, 0);
+}
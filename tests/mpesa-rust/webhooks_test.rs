@@ -0,0 +1,268 @@
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex};
+
+use mpesa::callbacks::{
+    BillManagerPaymentNotification, C2bConfirmation, C2bValidationRequest, C2bValidationResultCode,
+    CallbackResult, StkCallback, ValidationResponse,
+};
+use mpesa::webhooks::{self, WebhookHandler};
+use serde_json::{json, Value};
+
+#[derive(Clone, Default)]
+struct TestHandler {
+    received: Arc<Mutex<Vec<String>>>,
+}
+
+impl TestHandler {
+    fn record(&self, event: String) {
+        self.received.lock().unwrap().push(event);
+    }
+}
+
+#[async_trait::async_trait]
+impl WebhookHandler for TestHandler {
+    async fn stk_push(&self, callback: StkCallback) {
+        self.record(format!("stk:{}", callback.checkout_request_id));
+    }
+
+    async fn b2c_result(&self, result: CallbackResult) {
+        self.record(format!("b2c:{}", result.conversation_id));
+    }
+
+    async fn account_balance_result(&self, result: CallbackResult) {
+        self.record(format!("balance:{}", result.conversation_id));
+    }
+
+    async fn c2b_validation(&self, request: C2bValidationRequest) -> ValidationResponse {
+        self.record(format!("validation:{}", request.bill_ref_number));
+        if request.bill_ref_number == "invoice008" {
+            ValidationResponse::accept()
+        } else {
+            ValidationResponse::reject(C2bValidationResultCode::InvalidAccountNumber)
+        }
+    }
+
+    async fn c2b_confirmation(&self, confirmation: C2bConfirmation) {
+        self.record(format!("confirmation:{}", confirmation.trans_id));
+    }
+
+    async fn bill_manager_payment(&self, notification: BillManagerPaymentNotification) {
+        self.record(format!("bill_manager:{}", notification.transaction_id));
+    }
+}
+
+fn start_server(handler: TestHandler) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = axum::Server::from_tcp(listener)
+        .unwrap()
+        .serve(webhooks::router(handler).into_make_service());
+    tokio::spawn(server);
+    addr
+}
+
+async fn post(addr: SocketAddr, path: &str, body: Value) -> (u16, Value) {
+    let response = reqwest::Client::new()
+        .post(format!("http://{addr}{path}"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    (response.status().as_u16(), response.json().await.unwrap())
+}
+
+/// Acknowledgement M-Pesa expects, with the result code as a string
+fn accepted() -> Value {
+    json!({ "ResultCode": "0", "ResultDesc": "Accepted" })
+}
+
+fn result_callback(conversation_id: &str) -> Value {
+    json!({
+        "Result": {
+            "ResultType": 0,
+            "ResultCode": 0,
+            "ResultDesc": "The service request is processed successfully.",
+            "OriginatorConversationID": "10571-7910404-1",
+            "ConversationID": conversation_id,
+            "TransactionID": "NLJ41HAY6Q",
+            "ReferenceData": {
+                "ReferenceItem": {
+                    "Key": "QueueTimeoutURL",
+                    "Value": "https://internalsandbox.safaricom.co.ke/mpesa/b2cresults/v1/submit"
+                }
+            }
+        }
+    })
+}
+
+fn c2b_transaction(bill_ref_number: &str) -> Value {
+    json!({
+        "TransactionType": "Pay Bill",
+        "TransID": "RKTQDM7W6S",
+        "TransTime": "20191122063845",
+        "TransAmount": "10",
+        "BusinessShortCode": "600638",
+        "BillRefNumber": bill_ref_number,
+        "InvoiceNumber": "",
+        "OrgAccountBalance": "",
+        "ThirdPartyTransID": "",
+        "MSISDN": "25470****149",
+        "FirstName": "John",
+        "MiddleName": "",
+        "LastName": "Doe"
+    })
+}
+
+#[tokio::test]
+async fn webhooks_dispatch_stk_push_callback() {
+    let handler = TestHandler::default();
+    let addr = start_server(handler.clone());
+
+    let (status, body) = post(
+        addr,
+        webhooks::STK_PUSH_PATH,
+        json!({
+            "Body": {
+                "stkCallback": {
+                    "MerchantRequestID": "29115-34620561-1",
+                    "CheckoutRequestID": "ws_CO_191220191020363925",
+                    "ResultCode": 1032,
+                    "ResultDesc": "Request cancelled by user."
+                }
+            }
+        }),
+    )
+    .await;
+
+    assert_eq!(status, 200);
+    assert_eq!(body, accepted());
+    assert_eq!(
+        *handler.received.lock().unwrap(),
+        vec!["stk:ws_CO_191220191020363925"]
+    );
+}
+
+#[tokio::test]
+async fn webhooks_dispatch_result_callbacks_by_route() {
+    let handler = TestHandler::default();
+    let addr = start_server(handler.clone());
+
+    let (status, body) = post(
+        addr,
+        webhooks::B2C_RESULT_PATH,
+        result_callback("AG_20191219_00004e48cf7e3533f581"),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(body, accepted());
+
+    let (status, body) = post(
+        addr,
+        webhooks::ACCOUNT_BALANCE_RESULT_PATH,
+        result_callback("AG_20200206_00005e091a8ec6b9eac5"),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(body, accepted());
+
+    // Uses the default, no-op, implementation
+    let (status, body) = post(
+        addr,
+        webhooks::B2B_RESULT_PATH,
+        result_callback("AG_20221110_00004e48cf7e3533f581"),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(body, accepted());
+
+    assert_eq!(
+        *handler.received.lock().unwrap(),
+        vec![
+            "b2c:AG_20191219_00004e48cf7e3533f581",
+            "balance:AG_20200206_00005e091a8ec6b9eac5"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn webhooks_c2b_validation_accepts_and_rejects() {
+    let handler = TestHandler::default();
+    let addr = start_server(handler.clone());
+
+    let (status, body) = post(
+        addr,
+        webhooks::C2B_VALIDATION_PATH,
+        c2b_transaction("invoice008"),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(body, accepted());
+
+    let (status, body) = post(
+        addr,
+        webhooks::C2B_VALIDATION_PATH,
+        c2b_transaction("unknown"),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(
+        body,
+        json!({ "ResultCode": "C2B00012", "ResultDesc": "Rejected" })
+    );
+
+    let (status, body) = post(
+        addr,
+        webhooks::C2B_CONFIRMATION_PATH,
+        c2b_transaction("invoice008"),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(body, accepted());
+
+    assert_eq!(
+        *handler.received.lock().unwrap(),
+        vec![
+            "validation:invoice008",
+            "validation:unknown",
+            "confirmation:RKTQDM7W6S"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn webhooks_dispatch_bill_manager_payment() {
+    let handler = TestHandler::default();
+    let addr = start_server(handler.clone());
+
+    let (status, body) = post(
+        addr,
+        webhooks::BILL_MANAGER_PAYMENT_PATH,
+        json!({
+            "transactionId": "RJB53MYR1N",
+            "paidAmount": "5000",
+            "msisdn": "254710119383",
+            "dateCreated": "2021-09-15",
+            "accountReference": "LGHJIO789",
+            "shortCode": "174379"
+        }),
+    )
+    .await;
+
+    assert_eq!(status, 200);
+    assert_eq!(body, json!({ "rescode": "200", "resmsg": "success" }));
+    assert_eq!(
+        *handler.received.lock().unwrap(),
+        vec!["bill_manager:RJB53MYR1N"]
+    );
+}
+
+#[tokio::test]
+async fn webhooks_reject_invalid_payloads() {
+    let handler = TestHandler::default();
+    let addr = start_server(handler.clone());
+
+    let (status, _) = post(addr, webhooks::B2C_RESULT_PATH, json!({ "foo": "bar" })).await;
+
+    assert_eq!(status, 400);
+    assert!(handler.received.lock().unwrap().is_empty());
+}